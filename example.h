@@ -8,6 +8,12 @@ typedef enum ReturnCode {
 	Fail = -1,
 } ReturnCode;
 
+typedef enum SmartThermostatMode {
+	Off = 0,
+	Heat = 1,
+	Auto = 2,
+} SmartThermostatMode;
+
 typedef struct Option_CString Option_CString;
 typedef struct Handle Handle;
 typedef struct Home Home;
@@ -15,6 +21,7 @@ typedef struct Room Room;
 typedef struct Device Device;
 typedef struct SmartThermometer SmartThermometer;
 typedef struct SmartSocket SmartSocket;
+typedef struct SmartThermostat SmartThermostat;
 
 typedef struct HandleIter {
 	struct Handle *handle;
@@ -70,6 +77,21 @@ Home *smart_home_add_home(struct Handle *handle, const char *name);
  */
 enum ReturnCode smart_home_del_home(struct Handle *handle, const char *name);
 
+/**
+ * Moves a home to the position (zero-based)
+ *
+ * # Safety
+ *
+ * handle gets from smart_home_new()
+ *
+ * * `handle`: hub handle
+ * * `name`: home name to move
+ * * `position`: new home position
+ */
+enum ReturnCode smart_home_set_home_position(struct Handle *handle,
+                                             const char *name,
+                                             uintptr_t position);
+
 /**
  * Get Home handle from SmartHub by name
  *
@@ -141,6 +163,19 @@ Room *smart_home_add_room(Home *handle, const char *name);
  */
 enum ReturnCode smart_home_del_room(Home *handle, const char *name);
 
+/**
+ * Moves a room to the position (zero-based)
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `name`: room name to move
+ * * `position`: new room position
+ */
+enum ReturnCode smart_home_set_room_position(Home *handle, const char *name, uintptr_t position);
+
 /**
  * Gets a room from home by name
  *
@@ -234,6 +269,19 @@ SmartSocket *smart_home_add_socket(struct Handle *handle,
  */
 enum ReturnCode smart_home_del_device(Room *handle, const char *name);
 
+/**
+ * Moves a device to the position (zero-based)
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `name`: device name to move
+ * * `position`: new device position
+ */
+enum ReturnCode smart_home_set_device_position(Room *handle, const char *name, uintptr_t position);
+
 /**
  * Get a device by name
  *
@@ -401,3 +449,523 @@ enum ReturnCode smart_home_socket_on(struct Handle *handle, const SmartSocket *d
  * * `handle`: room handle
  */
 enum ReturnCode smart_home_socket_off(struct Handle *handle, const SmartSocket *device);
+
+/**
+ * Adds a new thermostat to room
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `name`: thermostat name to add
+ * * `description`: thermostat description to add
+ * * `thermometer_server`: thermostat thermometer server
+ * * `heater_server`: thermostat heater socket server
+ */
+SmartThermostat *smart_home_add_thermostat(struct Handle *handle,
+                                           Room *room,
+                                           const char *name,
+                                           const char *description,
+                                           const char *thermometer_server,
+                                           const char *heater_server);
+
+/**
+ * Gets all thermostats size
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ */
+uintptr_t smart_home_get_thermostat_size(const Room *handle);
+
+/**
+ * Gets all thermostats iter
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ */
+struct HandleDeviceIter smart_home_get_thermostat_iter(struct Handle *handle, Room *room);
+
+/**
+ * Gets all thermostats in room
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ */
+const SmartThermostat *smart_home_get_thermostat_next(struct HandleDeviceIter *handle);
+
+/**
+ * Get thermostat name
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+const char *smart_home_get_thermostat_name(struct Handle *handle, const SmartThermostat *device);
+
+/**
+ * Get thermostat description
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+const char *smart_home_get_thermostat_description(struct Handle *handle,
+                                                  const SmartThermostat *device);
+
+/**
+ * Get thermostat current temperature
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+double smart_home_get_thermostat_temperature(struct Handle *handle, const SmartThermostat *device);
+
+/**
+ * Get thermostat target temperature
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+double smart_home_get_thermostat_target(struct Handle *handle, const SmartThermostat *device);
+
+/**
+ * Set thermostat target temperature
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ * * `target`: target temperature
+ */
+enum ReturnCode smart_home_set_thermostat_target(struct Handle *handle,
+                                                 const SmartThermostat *device,
+                                                 double target);
+
+/**
+ * Get thermostat mode
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+enum SmartThermostatMode smart_home_get_thermostat_mode(struct Handle *handle,
+                                                        const SmartThermostat *device);
+
+/**
+ * Set thermostat mode
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ * * `mode`: thermostat mode
+ */
+enum ReturnCode smart_home_set_thermostat_mode(struct Handle *handle,
+                                               const SmartThermostat *device,
+                                               enum SmartThermostatMode mode);
+
+/**
+ * Check whether thermostat heater is running
+ *
+ * # Safety
+ *
+ * Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+ *
+ * * `handle`: smart hub handle
+ */
+bool smart_home_thermostat_is_heating(struct Handle *handle, const SmartThermostat *device);
+
+/**
+ * Adds a tag to home
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `tag`: tag to add
+ */
+enum ReturnCode smart_home_add_home_tag(Home *handle, const char *tag);
+
+/**
+ * Removes a tag from home
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `tag`: tag to del
+ */
+enum ReturnCode smart_home_del_home_tag(Home *handle, const char *tag);
+
+/**
+ * Check whether home has a tag
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `tag`: tag to check
+ */
+bool smart_home_home_has_tag(const Home *handle, const char *tag);
+
+/**
+ * Sets a home property
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `key`: property key
+ * * `value`: property value
+ */
+enum ReturnCode smart_home_set_home_property(Home *handle, const char *key, const char *value);
+
+/**
+ * Gets a home property, NULL if it is not set
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy value before next call
+ *
+ * * `handle`: smart hub handle
+ * * `home`: home handle
+ * * `key`: property key
+ */
+const char *smart_home_get_home_property(struct Handle *handle, const Home *home, const char *key);
+
+/**
+ * Adds a tag to room
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `tag`: tag to add
+ */
+enum ReturnCode smart_home_add_room_tag(Room *handle, const char *tag);
+
+/**
+ * Removes a tag from room
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `tag`: tag to del
+ */
+enum ReturnCode smart_home_del_room_tag(Room *handle, const char *tag);
+
+/**
+ * Check whether room has a tag
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `tag`: tag to check
+ */
+bool smart_home_room_has_tag(const Room *handle, const char *tag);
+
+/**
+ * Sets a room property
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: room handle
+ * * `key`: property key
+ * * `value`: property value
+ */
+enum ReturnCode smart_home_set_room_property(Room *handle, const char *key, const char *value);
+
+/**
+ * Gets a room property, NULL if it is not set
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy value before next call
+ *
+ * * `handle`: smart hub handle
+ * * `room`: room handle
+ * * `key`: property key
+ */
+const char *smart_home_get_room_property(struct Handle *handle, const Room *room, const char *key);
+
+/**
+ * Adds a tag to device
+ *
+ * # Safety
+ *
+ * Device gets from smart_home_get_device()
+ *
+ * * `handle`: device handle
+ * * `tag`: tag to add
+ */
+enum ReturnCode smart_home_add_device_tag(Device *handle, const char *tag);
+
+/**
+ * Removes a tag from device
+ *
+ * # Safety
+ *
+ * Device gets from smart_home_get_device()
+ *
+ * * `handle`: device handle
+ * * `tag`: tag to del
+ */
+enum ReturnCode smart_home_del_device_tag(Device *handle, const char *tag);
+
+/**
+ * Check whether device has a tag
+ *
+ * # Safety
+ *
+ * Device gets from smart_home_get_device()
+ *
+ * * `handle`: device handle
+ * * `tag`: tag to check
+ */
+bool smart_home_device_has_tag(const Device *handle, const char *tag);
+
+/**
+ * Sets a device property
+ *
+ * # Safety
+ *
+ * Device gets from smart_home_get_device()
+ *
+ * * `handle`: device handle
+ * * `key`: property key
+ * * `value`: property value
+ */
+enum ReturnCode smart_home_set_device_property(Device *handle, const char *key, const char *value);
+
+/**
+ * Gets a device property, NULL if it is not set
+ *
+ * # Safety
+ *
+ * Device gets from smart_home_get_device()
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy value before next call
+ *
+ * * `handle`: smart hub handle
+ * * `device`: device handle
+ * * `key`: property key
+ */
+const char *smart_home_get_device_property(struct Handle *handle,
+                                           const Device *device,
+                                           const char *key);
+
+/**
+ * Get next home name with the tag
+ *
+ * # Safety
+ *
+ * iter gets from smart_home_get_home_iter(), use the same tag for all calls
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy name before next call
+ *
+ * * `iter`: home iterator
+ * * `tag`: tag to filter
+ */
+const char *smart_home_get_home_next_with_tag(struct HandleIter *iter, const char *tag);
+
+/**
+ * Get next room name with the tag
+ *
+ * # Safety
+ *
+ * iter gets from smart_home_get_room_iter(), use the same tag for all calls
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy name before next call
+ *
+ * * `iter`: room iterator
+ * * `tag`: tag to filter
+ */
+const char *smart_home_get_room_next_with_tag(struct HandleRoomIter *iter, const char *tag);
+
+/**
+ * Gets a device iterator
+ *
+ * # Safety
+ *
+ * Room gets from smart_home_get_room()
+ *
+ * * `handle`: smart hub handle
+ * * `room`: room handle
+ */
+struct HandleDeviceIter smart_home_get_device_iter(struct Handle *handle, Room *room);
+
+/**
+ * Get next device name with the tag
+ *
+ * # Safety
+ *
+ * iter gets from smart_home_get_device_iter(), use the same tag for all calls
+ *
+ * # Warning
+ *
+ * This function returns borrowed pointer, use copy name before next call
+ *
+ * * `iter`: device iterator
+ * * `tag`: tag to filter
+ */
+const char *smart_home_get_device_next_with_tag(struct HandleDeviceIter *iter, const char *tag);
+
+/**
+ * Adds a new device group to home
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `name`: group name to add
+ */
+enum ReturnCode smart_home_add_group(Home *handle, const char *name);
+
+/**
+ * Dels a device group from home, devices stay in their rooms
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `name`: group name to del
+ */
+enum ReturnCode smart_home_del_group(Home *handle, const char *name);
+
+/**
+ * Adds a device of home to the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `group`: group name
+ * * `room`: room name of the device
+ * * `device`: device name
+ */
+enum ReturnCode smart_home_add_group_member(Home *handle,
+                                            const char *group,
+                                            const char *room,
+                                            const char *device);
+
+/**
+ * Removes a device from the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: home handle
+ * * `group`: group name
+ * * `room`: room name of the device
+ * * `device`: device name
+ */
+enum ReturnCode smart_home_del_group_member(Home *handle,
+                                            const char *group,
+                                            const char *room,
+                                            const char *device);
+
+/**
+ * Turn on all switchable devices of the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: smart hub handle
+ * * `home`: home handle
+ * * `group`: group name
+ */
+enum ReturnCode smart_home_group_on(struct Handle *handle, const Home *home, const char *group);
+
+/**
+ * Turn off all switchable devices of the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: smart hub handle
+ * * `home`: home handle
+ * * `group`: group name
+ */
+enum ReturnCode smart_home_group_off(struct Handle *handle, const Home *home, const char *group);
+
+/**
+ * Get average temperature of the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: smart hub handle
+ * * `home`: home handle
+ * * `group`: group name
+ */
+double smart_home_get_group_temperature(struct Handle *handle, const Home *home, const char *group);
+
+/**
+ * Get total power of the group
+ *
+ * # Safety
+ *
+ * Home gets from smart_home_get_home()
+ *
+ * * `handle`: smart hub handle
+ * * `home`: home handle
+ * * `group`: group name
+ */
+double smart_home_get_group_power(struct Handle *handle, const Home *home, const char *group);
+
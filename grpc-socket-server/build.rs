@@ -1,5 +1,11 @@
+use std::{env, path::PathBuf};
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=proto/smart_home_socket.proto");
-    tonic_build::compile_protos("proto/smart_home_socket.proto")?;
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("smart_home_socket_descriptor.bin"))
+        .compile(&["proto/smart_home_socket.proto"], &["proto"])?;
     Ok(())
 }
@@ -0,0 +1,21 @@
+#!/bin/sh
+# Generate a local CA, server and client certificates for the socket server TLS:
+#
+#   SOCKET_TLS_CERT=certs/server.pem SOCKET_TLS_KEY=certs/server.key \
+#   SOCKET_TLS_CLIENT_CA=certs/ca.pem SOCKET_TOKEN=secret cargo run -p socket-server
+set -e
+
+dir=${1:-certs}
+mkdir -p "$dir"
+cd "$dir"
+
+openssl req -x509 -newkey rsa:2048 -nodes -days 365 -subj "/CN=smart home CA" \
+    -keyout ca.key -out ca.pem
+
+for name in server client; do
+    openssl req -newkey rsa:2048 -nodes -subj "/CN=$name" -keyout "$name.key" -out "$name.csr"
+    printf "subjectAltName=DNS:localhost,IP:127.0.0.1\n" > "$name.ext"
+    openssl x509 -req -days 365 -in "$name.csr" -CA ca.pem -CAkey ca.key -CAcreateserial \
+        -extfile "$name.ext" -out "$name.pem"
+    rm "$name.csr" "$name.ext"
+done
@@ -1,4 +1,4 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{env, fs, net::SocketAddr, sync::Arc};
 
 use rand::Rng;
 use socket::{
@@ -6,19 +6,62 @@ use socket::{
     CurrentPowerRequest, CurrentPowerResponse, OffRequest, OffResponse, OnRequest, OnResponse,
 };
 use tokio::sync::Mutex;
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    transport::{Certificate, Identity, NamedService, Server, ServerTlsConfig},
+    Request, Response, Status,
+};
+use tonic_health::{server::HealthReporter, ServingStatus};
 
 mod socket {
     tonic::include_proto!("smart_home_socket");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        tonic::include_file_descriptor_set!("smart_home_socket_descriptor");
 }
 
 struct State {
     state: bool,
     current_power: f64,
+    /// Socket is overloaded, only OFF command resets it
+    fault: bool,
 }
 
 struct MySocket {
     state: Arc<Mutex<State>>,
+    health: HealthReporter,
+    /// Probability of the overload on the ON command
+    fault_probability: f64,
+}
+
+impl MySocket {
+    fn new(health: HealthReporter, fault_probability: f64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                state: false,
+                current_power: 0.0,
+                fault: false,
+            })),
+            health,
+            fault_probability,
+        }
+    }
+
+    /// Report the fault state through the health service
+    async fn report_health(&self, fault: bool) {
+        let status = if fault {
+            ServingStatus::NotServing
+        } else {
+            ServingStatus::Serving
+        };
+
+        let mut health = self.health.clone();
+        health.set_service_status("", status).await;
+        health
+            .set_service_status(<SocketServer<MySocket> as NamedService>::NAME, status)
+            .await;
+    }
 }
 
 #[tonic::async_trait]
@@ -29,12 +72,30 @@ impl Socket for MySocket {
     ) -> std::result::Result<Response<OnResponse>, Status> {
         let reply = OnResponse {};
         let mut state = self.state.lock().await;
-        let mut rng = rand::thread_rng();
+        let (current_power, overload) = {
+            let mut rng = rand::thread_rng();
+            (
+                rng.gen_range(1.0..=200.0),
+                rng.gen_bool(self.fault_probability),
+            )
+        };
+
+        if state.fault {
+            return Err(Status::unavailable("socket is overloaded"));
+        }
+
         state.state = true;
-        state.current_power = rng.gen_range(1.0..=200.0);
+        state.current_power = current_power;
 
         log::info!("Handle ON command, generate {} power", state.current_power);
 
+        if overload {
+            log::warn!("Socket is overloaded");
+            state.fault = true;
+            self.report_health(true).await;
+            return Err(Status::unavailable("socket is overloaded"));
+        }
+
         Ok(Response::new(reply))
     }
 
@@ -49,6 +110,12 @@ impl Socket for MySocket {
 
         log::info!("Handle OFF command");
 
+        if state.fault {
+            log::info!("Socket fault is reset");
+            state.fault = false;
+            self.report_health(false).await;
+        }
+
         Ok(Response::new(reply))
     }
 
@@ -57,6 +124,11 @@ impl Socket for MySocket {
         _request: Request<CurrentPowerRequest>,
     ) -> std::result::Result<Response<CurrentPowerResponse>, Status> {
         let state = self.state.lock().await;
+
+        if state.fault {
+            return Err(Status::unavailable("socket is overloaded"));
+        }
+
         let reply = CurrentPowerResponse {
             current_power: state.current_power,
         };
@@ -67,21 +139,178 @@ impl Socket for MySocket {
     }
 }
 
+/// Build TLS settings from `SOCKET_TLS_CERT` and `SOCKET_TLS_KEY` PEM files, client certificates
+/// signed by `SOCKET_TLS_CLIENT_CA` are required if it is set
+fn tls_config() -> Result<Option<ServerTlsConfig>, Box<dyn std::error::Error>> {
+    let (cert, key) = match (
+        env::var_os("SOCKET_TLS_CERT"),
+        env::var_os("SOCKET_TLS_KEY"),
+    ) {
+        (Some(cert), Some(key)) => (fs::read(cert)?, fs::read(key)?),
+        (None, None) => return Ok(None),
+        _ => return Err("SOCKET_TLS_CERT and SOCKET_TLS_KEY must be set together".into()),
+    };
+    let mut config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    if let Some(client_ca) = env::var_os("SOCKET_TLS_CLIENT_CA") {
+        config = config.client_ca_root(Certificate::from_pem(fs::read(client_ca)?));
+    }
+
+    Ok(Some(config))
+}
+
+/// Take the probability of the simulated overload from `SOCKET_FAULT_PROBABILITY`, no faults if
+/// it is not set
+fn fault_probability() -> Result<f64, Box<dyn std::error::Error>> {
+    let probability: f64 = match env::var("SOCKET_FAULT_PROBABILITY") {
+        Ok(probability) => probability.parse()?,
+        Err(env::VarError::NotPresent) => return Ok(0.0),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !(0.0..=1.0).contains(&probability) {
+        return Err("SOCKET_FAULT_PROBABILITY must be between 0 and 1".into());
+    }
+
+    Ok(probability)
+}
+
+/// Check of the `authorization: Bearer <token>` header of the socket requests
+#[derive(Clone)]
+struct TokenCheck {
+    expected: Option<MetadataValue<Ascii>>,
+}
+
+impl TokenCheck {
+    /// Take the token from `SOCKET_TOKEN`, all requests are accepted if it is not set
+    fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let expected = match env::var("SOCKET_TOKEN") {
+            Ok(token) => Some(format!("Bearer {}", token).parse()?),
+            Err(env::VarError::NotPresent) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self { expected })
+    }
+}
+
+impl Interceptor for TokenCheck {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        match &self.expected {
+            Some(expected) if request.metadata().get("authorization") != Some(expected) => {
+                Err(Status::unauthenticated("invalid token"))
+            }
+            _ => Ok(request),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     let addr: SocketAddr = "127.0.0.1:50051".parse()?;
-    let socket = MySocket {
-        state: Arc::new(Mutex::new(State {
-            state: false,
-            current_power: 0.0,
-        })),
-    };
+    let mut server = Server::builder();
 
-    Server::builder()
-        .add_service(SocketServer::new(socket))
+    if let Some(tls) = tls_config()? {
+        log::info!("Use TLS");
+        server = server.tls_config(tls)?;
+    }
+
+    let (health, health_service) = tonic_health::server::health_reporter();
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(socket::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(
+            tonic_health::proto::GRPC_HEALTH_V1_FILE_DESCRIPTOR_SET,
+        )
+        .build()?;
+    let socket = MySocket::new(health, fault_probability()?);
+    socket.report_health(false).await;
+
+    server
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .add_service(SocketServer::with_interceptor(
+            socket,
+            TokenCheck::from_env()?,
+        ))
         .serve(addr)
         .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Channel;
+    use tonic_health::proto::{
+        health_check_response::ServingStatus as HealthStatus, health_client::HealthClient,
+        HealthCheckRequest,
+    };
+
+    use super::*;
+
+    /// Serve the health service of the socket on a random port
+    async fn start(fault_probability: f64) -> (MySocket, HealthClient<Channel>) {
+        let (health, health_service) = tonic_health::server::health_reporter();
+        let socket = MySocket::new(health, fault_probability);
+        socket.report_health(false).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(health_service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let client = HealthClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap();
+
+        (socket, client)
+    }
+
+    async fn status(client: &mut HealthClient<Channel>) -> HealthStatus {
+        let response = client
+            .check(HealthCheckRequest {
+                service: String::new(),
+            })
+            .await
+            .unwrap();
+
+        HealthStatus::from_i32(response.into_inner().status).unwrap()
+    }
+
+    #[tokio::test]
+    async fn no_faults_by_default() {
+        let (socket, mut client) = start(0.0).await;
+
+        for _ in 0..100 {
+            socket.on(Request::new(OnRequest {})).await.unwrap();
+        }
+        assert_eq!(status(&mut client).await, HealthStatus::Serving);
+    }
+
+    #[tokio::test]
+    async fn fault_is_reset_by_off() {
+        let (socket, mut client) = start(1.0).await;
+        assert_eq!(status(&mut client).await, HealthStatus::Serving);
+
+        assert!(socket.on(Request::new(OnRequest {})).await.is_err());
+        assert_eq!(status(&mut client).await, HealthStatus::NotServing);
+        assert!(socket
+            .current_power(Request::new(CurrentPowerRequest {}))
+            .await
+            .is_err());
+
+        socket.off(Request::new(OffRequest {})).await.unwrap();
+        assert_eq!(status(&mut client).await, HealthStatus::Serving);
+        let power = socket
+            .current_power(Request::new(CurrentPowerRequest {}))
+            .await
+            .unwrap();
+        assert_eq!(power.into_inner().current_power, 0.0);
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     os::raw::c_char,
 };
 
-use smart_home_lib::{Device, Home, Room, SmartDevice, SmartHub, SmartSocket, SmartThermometer};
+use smart_home_lib::{
+    Device, DeviceGroup, GroupMember, Home, Room, SmartDevice, SmartHub, SmartSocket,
+    SmartThermometer, SmartThermostat, ThermostatMode,
+};
 
 #[repr(C)]
 pub struct Handle {
@@ -12,6 +15,7 @@ pub struct Handle {
     room_name_buffer: Option<CString>,
     device_name_buffer: Option<CString>,
     device_desc_buffer: Option<CString>,
+    property_buffer: Option<CString>,
     rt: tokio::runtime::Runtime,
 }
 
@@ -41,6 +45,33 @@ pub enum ReturnCode {
     Fail = -1,
 }
 
+#[repr(C)]
+pub enum SmartThermostatMode {
+    Off = 0,
+    Heat = 1,
+    Auto = 2,
+}
+
+impl From<ThermostatMode> for SmartThermostatMode {
+    fn from(mode: ThermostatMode) -> Self {
+        match mode {
+            ThermostatMode::Off => SmartThermostatMode::Off,
+            ThermostatMode::Heat => SmartThermostatMode::Heat,
+            ThermostatMode::Auto => SmartThermostatMode::Auto,
+        }
+    }
+}
+
+impl From<SmartThermostatMode> for ThermostatMode {
+    fn from(mode: SmartThermostatMode) -> Self {
+        match mode {
+            SmartThermostatMode::Off => ThermostatMode::Off,
+            SmartThermostatMode::Heat => ThermostatMode::Heat,
+            SmartThermostatMode::Auto => ThermostatMode::Auto,
+        }
+    }
+}
+
 #[no_mangle]
 /// get new smart hub
 pub extern "C" fn smart_home_new() -> *mut Handle {
@@ -50,6 +81,7 @@ pub extern "C" fn smart_home_new() -> *mut Handle {
         home_name_buffer: None,
         device_name_buffer: None,
         device_desc_buffer: None,
+        property_buffer: None,
         rt: tokio::runtime::Runtime::new().unwrap(),
     };
 
@@ -63,7 +95,7 @@ pub extern "C" fn smart_home_new() -> *mut Handle {
 ///
 /// It's ok, because memory allocated from smart_home_new()
 pub unsafe extern "C" fn smart_home_free(hub: *mut Handle) {
-    Box::from_raw(hub);
+    drop(Box::from_raw(hub));
 }
 
 #[no_mangle]
@@ -110,6 +142,30 @@ pub unsafe extern "C" fn smart_home_del_home(
     }
 }
 
+#[no_mangle]
+/// Moves a home to the position (zero-based)
+///
+/// # Safety
+///
+/// handle gets from smart_home_new()
+///
+/// * `handle`: hub handle
+/// * `name`: home name to move
+/// * `position`: new home position
+pub unsafe extern "C" fn smart_home_set_home_position(
+    handle: *mut Handle,
+    name: *const c_char,
+    position: usize,
+) -> ReturnCode {
+    let hub = &mut (*handle).controller;
+    let name = CStr::from_ptr(name).to_str().unwrap();
+
+    match hub.set_home_position(name, position) {
+        Ok(_) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
 #[no_mangle]
 /// Get Home handle from SmartHub by name
 ///
@@ -222,6 +278,30 @@ pub unsafe extern "C" fn smart_home_del_room(handle: *mut Home, name: *const c_c
     }
 }
 
+#[no_mangle]
+/// Moves a room to the position (zero-based)
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `name`: room name to move
+/// * `position`: new room position
+pub unsafe extern "C" fn smart_home_set_room_position(
+    handle: *mut Home,
+    name: *const c_char,
+    position: usize,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let name = CStr::from_ptr(name).to_str().unwrap();
+
+    match home.set_room_position(name, position) {
+        Ok(_) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
 #[no_mangle]
 /// Gets a room from home by name
 ///
@@ -328,10 +408,9 @@ pub unsafe extern "C" fn smart_home_add_thermometer(
     });
 
     match room.add_device(thermometer) {
-        Ok(thermometer) => match thermometer {
-            Device::Thermometer(t) => t as *mut SmartThermometer,
-            _ => unreachable!(),
-        },
+        Ok(device) => device
+            .downcast_mut::<SmartThermometer>()
+            .expect("just added SmartThermometer") as *mut SmartThermometer,
         Err(_) => std::ptr::null_mut(),
     }
 }
@@ -365,10 +444,9 @@ pub unsafe extern "C" fn smart_home_add_socket(
         .block_on(async { SmartSocket::new(device_name, device_description, device_server).await });
 
     match room.add_device(thermometer) {
-        Ok(device) => match device {
-            Device::Socket(s) => s as *mut SmartSocket,
-            _ => unreachable!(),
-        },
+        Ok(device) => device
+            .downcast_mut::<SmartSocket>()
+            .expect("just added SmartSocket") as *mut SmartSocket,
         Err(_) => std::ptr::null_mut(),
     }
 }
@@ -395,6 +473,30 @@ pub unsafe extern "C" fn smart_home_del_device(
     }
 }
 
+#[no_mangle]
+/// Moves a device to the position (zero-based)
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `name`: device name to move
+/// * `position`: new device position
+pub unsafe extern "C" fn smart_home_set_device_position(
+    handle: *mut Room,
+    name: *const c_char,
+    position: usize,
+) -> ReturnCode {
+    let room = &mut *handle;
+    let name = CStr::from_ptr(name).to_str().unwrap();
+
+    match room.set_device_position(name, position) {
+        Ok(_) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
 #[no_mangle]
 /// Get a device by name
 ///
@@ -590,7 +692,6 @@ pub unsafe extern "C" fn smart_home_get_thermometer_temperature(
 }
 
 #[no_mangle]
-
 /// Get socket name
 ///
 /// # Safety
@@ -695,3 +796,1014 @@ pub unsafe extern "C" fn smart_home_socket_off(
         }
     })
 }
+
+#[no_mangle]
+/// Adds a new thermostat to room
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `name`: thermostat name to add
+/// * `description`: thermostat description to add
+/// * `thermometer_server`: thermostat thermometer server
+/// * `heater_server`: thermostat heater socket server
+pub unsafe extern "C" fn smart_home_add_thermostat(
+    handle: *mut Handle,
+    room: *mut Room,
+    name: *const c_char,
+    description: *const c_char,
+    thermometer_server: *const c_char,
+    heater_server: *const c_char,
+) -> *mut SmartThermostat {
+    let handle = &mut *handle;
+    let room = &mut *room;
+    let device_name = CStr::from_ptr(name).to_str().unwrap();
+    let device_description = CStr::from_ptr(description).to_str().unwrap();
+    let thermometer_server = CStr::from_ptr(thermometer_server).to_str().unwrap();
+    let heater_server = CStr::from_ptr(heater_server).to_str().unwrap();
+
+    let thermostat = handle.rt.block_on(async {
+        let thermometer =
+            SmartThermometer::new(device_name, device_description, thermometer_server).await;
+        let heater = SmartSocket::new(device_name, device_description, heater_server).await;
+
+        SmartThermostat::new(device_name, device_description, thermometer, heater).await
+    });
+
+    match room.add_device(thermostat) {
+        Ok(device) => device
+            .downcast_mut::<SmartThermostat>()
+            .expect("just added SmartThermostat") as *mut SmartThermostat,
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+/// Gets all thermostats size
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+pub unsafe extern "C" fn smart_home_get_thermostat_size(handle: *const Room) -> usize {
+    let room = &*handle;
+    room.thermostat_devices().count()
+}
+
+#[no_mangle]
+/// Gets all thermostats iter
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+pub unsafe extern "C" fn smart_home_get_thermostat_iter(
+    handle: *mut Handle,
+    room: *mut Room,
+) -> HandleDeviceIter {
+    HandleDeviceIter {
+        handle,
+        room,
+        cursor: 0,
+    }
+}
+
+#[no_mangle]
+/// Gets all thermostats in room
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+pub unsafe extern "C" fn smart_home_get_thermostat_next(
+    handle: *mut HandleDeviceIter,
+) -> *const SmartThermostat {
+    let handle = &mut *handle;
+    let room = &*handle.room;
+
+    let device = match room.thermostat_devices().nth(handle.cursor) {
+        Some(device) => device,
+        None => return std::ptr::null(),
+    };
+
+    handle.cursor += 1;
+
+    device as *const SmartThermostat
+}
+
+#[no_mangle]
+/// Get thermostat name
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_get_thermostat_name(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> *const c_char {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.device_name_buffer = Some(CString::new(device.name()).unwrap());
+
+    handle.device_name_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Get thermostat description
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_get_thermostat_description(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> *const c_char {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.device_desc_buffer = Some(CString::new(device.description()).unwrap());
+
+    handle.device_desc_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Get thermostat current temperature
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_get_thermostat_temperature(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> f64 {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle
+        .rt
+        .block_on(async { device.current_temperature().await.unwrap_or_default() })
+}
+
+#[no_mangle]
+/// Get thermostat target temperature
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_get_thermostat_target(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> f64 {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle
+        .rt
+        .block_on(async { device.target_temperature().await })
+}
+
+#[no_mangle]
+/// Set thermostat target temperature
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+/// * `target`: target temperature
+pub unsafe extern "C" fn smart_home_set_thermostat_target(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+    target: f64,
+) -> ReturnCode {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.rt.block_on(async {
+        match device.set_target_temperature(target).await {
+            Ok(()) => ReturnCode::Success,
+            Err(_) => ReturnCode::Fail,
+        }
+    })
+}
+
+#[no_mangle]
+/// Get thermostat mode
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_get_thermostat_mode(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> SmartThermostatMode {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.rt.block_on(async { device.mode().await.into() })
+}
+
+#[no_mangle]
+/// Set thermostat mode
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+/// * `mode`: thermostat mode
+pub unsafe extern "C" fn smart_home_set_thermostat_mode(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+    mode: SmartThermostatMode,
+) -> ReturnCode {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.rt.block_on(device.set_mode(mode.into()));
+
+    ReturnCode::Success
+}
+
+#[no_mangle]
+/// Check whether thermostat heater is running
+///
+/// # Safety
+///
+/// Thermostat gets from smart_home_add_thermostat() or smart_home_get_thermostat_next()
+///
+/// * `handle`: smart hub handle
+pub unsafe extern "C" fn smart_home_thermostat_is_heating(
+    handle: *mut Handle,
+    device: *const SmartThermostat,
+) -> bool {
+    let handle = &mut *handle;
+    let device = &*device;
+
+    handle.rt.block_on(async { device.is_heating().await })
+}
+
+#[no_mangle]
+/// Adds a tag to home
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `tag`: tag to add
+pub unsafe extern "C" fn smart_home_add_home_tag(
+    handle: *mut Home,
+    tag: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match home.metadata_mut().add_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Removes a tag from home
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `tag`: tag to del
+pub unsafe extern "C" fn smart_home_del_home_tag(
+    handle: *mut Home,
+    tag: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match home.metadata_mut().remove_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Check whether home has a tag
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `tag`: tag to check
+pub unsafe extern "C" fn smart_home_home_has_tag(handle: *const Home, tag: *const c_char) -> bool {
+    let home = &*handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    home.metadata().has_tag(tag)
+}
+
+#[no_mangle]
+/// Sets a home property
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `key`: property key
+/// * `value`: property value
+pub unsafe extern "C" fn smart_home_set_home_property(
+    handle: *mut Home,
+    key: *const c_char,
+    value: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+    let value = CStr::from_ptr(value).to_str().unwrap();
+
+    home.metadata_mut().set(key, value);
+
+    ReturnCode::Success
+}
+
+#[no_mangle]
+/// Gets a home property, NULL if it is not set
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy value before next call
+///
+/// * `handle`: smart hub handle
+/// * `home`: home handle
+/// * `key`: property key
+pub unsafe extern "C" fn smart_home_get_home_property(
+    handle: *mut Handle,
+    home: *const Home,
+    key: *const c_char,
+) -> *const c_char {
+    let handle = &mut *handle;
+    let home = &*home;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+
+    let value = match home.metadata().get(key) {
+        Some(value) => value,
+        None => return std::ptr::null(),
+    };
+
+    handle.property_buffer = Some(CString::new(value).unwrap());
+
+    handle.property_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Adds a tag to room
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `tag`: tag to add
+pub unsafe extern "C" fn smart_home_add_room_tag(
+    handle: *mut Room,
+    tag: *const c_char,
+) -> ReturnCode {
+    let room = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match room.metadata_mut().add_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Removes a tag from room
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `tag`: tag to del
+pub unsafe extern "C" fn smart_home_del_room_tag(
+    handle: *mut Room,
+    tag: *const c_char,
+) -> ReturnCode {
+    let room = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match room.metadata_mut().remove_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Check whether room has a tag
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `tag`: tag to check
+pub unsafe extern "C" fn smart_home_room_has_tag(handle: *const Room, tag: *const c_char) -> bool {
+    let room = &*handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    room.metadata().has_tag(tag)
+}
+
+#[no_mangle]
+/// Sets a room property
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: room handle
+/// * `key`: property key
+/// * `value`: property value
+pub unsafe extern "C" fn smart_home_set_room_property(
+    handle: *mut Room,
+    key: *const c_char,
+    value: *const c_char,
+) -> ReturnCode {
+    let room = &mut *handle;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+    let value = CStr::from_ptr(value).to_str().unwrap();
+
+    room.metadata_mut().set(key, value);
+
+    ReturnCode::Success
+}
+
+#[no_mangle]
+/// Gets a room property, NULL if it is not set
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy value before next call
+///
+/// * `handle`: smart hub handle
+/// * `room`: room handle
+/// * `key`: property key
+pub unsafe extern "C" fn smart_home_get_room_property(
+    handle: *mut Handle,
+    room: *const Room,
+    key: *const c_char,
+) -> *const c_char {
+    let handle = &mut *handle;
+    let room = &*room;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+
+    let value = match room.metadata().get(key) {
+        Some(value) => value,
+        None => return std::ptr::null(),
+    };
+
+    handle.property_buffer = Some(CString::new(value).unwrap());
+
+    handle.property_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Adds a tag to device
+///
+/// # Safety
+///
+/// Device gets from smart_home_get_device()
+///
+/// * `handle`: device handle
+/// * `tag`: tag to add
+pub unsafe extern "C" fn smart_home_add_device_tag(
+    handle: *mut Device,
+    tag: *const c_char,
+) -> ReturnCode {
+    let device = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match device.metadata_mut().add_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Removes a tag from device
+///
+/// # Safety
+///
+/// Device gets from smart_home_get_device()
+///
+/// * `handle`: device handle
+/// * `tag`: tag to del
+pub unsafe extern "C" fn smart_home_del_device_tag(
+    handle: *mut Device,
+    tag: *const c_char,
+) -> ReturnCode {
+    let device = &mut *handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    match device.metadata_mut().remove_tag(tag) {
+        true => ReturnCode::Success,
+        false => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Check whether device has a tag
+///
+/// # Safety
+///
+/// Device gets from smart_home_get_device()
+///
+/// * `handle`: device handle
+/// * `tag`: tag to check
+pub unsafe extern "C" fn smart_home_device_has_tag(
+    handle: *const Device,
+    tag: *const c_char,
+) -> bool {
+    let device = &*handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    device.metadata().has_tag(tag)
+}
+
+#[no_mangle]
+/// Sets a device property
+///
+/// # Safety
+///
+/// Device gets from smart_home_get_device()
+///
+/// * `handle`: device handle
+/// * `key`: property key
+/// * `value`: property value
+pub unsafe extern "C" fn smart_home_set_device_property(
+    handle: *mut Device,
+    key: *const c_char,
+    value: *const c_char,
+) -> ReturnCode {
+    let device = &mut *handle;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+    let value = CStr::from_ptr(value).to_str().unwrap();
+
+    device.metadata_mut().set(key, value);
+
+    ReturnCode::Success
+}
+
+#[no_mangle]
+/// Gets a device property, NULL if it is not set
+///
+/// # Safety
+///
+/// Device gets from smart_home_get_device()
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy value before next call
+///
+/// * `handle`: smart hub handle
+/// * `device`: device handle
+/// * `key`: property key
+pub unsafe extern "C" fn smart_home_get_device_property(
+    handle: *mut Handle,
+    device: *const Device,
+    key: *const c_char,
+) -> *const c_char {
+    let handle = &mut *handle;
+    let device = &*device;
+    let key = CStr::from_ptr(key).to_str().unwrap();
+
+    let value = match device.metadata().get(key) {
+        Some(value) => value,
+        None => return std::ptr::null(),
+    };
+
+    handle.property_buffer = Some(CString::new(value).unwrap());
+
+    handle.property_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Get next home name with the tag
+///
+/// # Safety
+///
+/// iter gets from smart_home_get_home_iter(), use the same tag for all calls
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy name before next call
+///
+/// * `iter`: home iterator
+/// * `tag`: tag to filter
+pub unsafe extern "C" fn smart_home_get_home_next_with_tag(
+    iter: *mut HandleIter,
+    tag: *const c_char,
+) -> *const c_char {
+    let iter = &mut *iter;
+    let handle = &mut *iter.handle;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    let home = match handle.controller.homes_with_tag(tag).nth(iter.cursor) {
+        Some(home) => home,
+        None => return std::ptr::null(),
+    };
+
+    iter.cursor += 1;
+
+    handle.home_name_buffer = Some(CString::new(home.name()).unwrap());
+
+    handle.home_name_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Get next room name with the tag
+///
+/// # Safety
+///
+/// iter gets from smart_home_get_room_iter(), use the same tag for all calls
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy name before next call
+///
+/// * `iter`: room iterator
+/// * `tag`: tag to filter
+pub unsafe extern "C" fn smart_home_get_room_next_with_tag(
+    iter: *mut HandleRoomIter,
+    tag: *const c_char,
+) -> *const c_char {
+    let iter = &mut *iter;
+    let handle = &mut *iter.handle;
+    let home = &*iter.home;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    let room = match home.rooms_with_tag(tag).nth(iter.cursor) {
+        Some(room) => room,
+        None => return std::ptr::null(),
+    };
+
+    iter.cursor += 1;
+
+    handle.room_name_buffer = Some(CString::new(room.name()).unwrap());
+
+    handle.room_name_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Gets a device iterator
+///
+/// # Safety
+///
+/// Room gets from smart_home_get_room()
+///
+/// * `handle`: smart hub handle
+/// * `room`: room handle
+pub unsafe extern "C" fn smart_home_get_device_iter(
+    handle: *mut Handle,
+    room: *mut Room,
+) -> HandleDeviceIter {
+    HandleDeviceIter {
+        handle,
+        room,
+        cursor: 0,
+    }
+}
+
+#[no_mangle]
+/// Get next device name with the tag
+///
+/// # Safety
+///
+/// iter gets from smart_home_get_device_iter(), use the same tag for all calls
+///
+/// # Warning
+///
+/// This function returns borrowed pointer, use copy name before next call
+///
+/// * `iter`: device iterator
+/// * `tag`: tag to filter
+pub unsafe extern "C" fn smart_home_get_device_next_with_tag(
+    iter: *mut HandleDeviceIter,
+    tag: *const c_char,
+) -> *const c_char {
+    let iter = &mut *iter;
+    let handle = &mut *iter.handle;
+    let room = &*iter.room;
+    let tag = CStr::from_ptr(tag).to_str().unwrap();
+
+    let device = match room.devices_with_tag(tag).nth(iter.cursor) {
+        Some(device) => device,
+        None => return std::ptr::null(),
+    };
+
+    iter.cursor += 1;
+
+    handle.device_name_buffer = Some(CString::new(device.name()).unwrap());
+
+    handle.device_name_buffer.as_ref().unwrap().as_ptr()
+}
+
+#[no_mangle]
+/// Adds a new device group to home
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `name`: group name to add
+pub unsafe extern "C" fn smart_home_add_group(
+    handle: *mut Home,
+    name: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let group_name = CStr::from_ptr(name).to_str().unwrap();
+
+    match home.add_group(DeviceGroup::new(group_name)) {
+        Ok(_) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Dels a device group from home, devices stay in their rooms
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `name`: group name to del
+pub unsafe extern "C" fn smart_home_del_group(
+    handle: *mut Home,
+    name: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let group_name = CStr::from_ptr(name).to_str().unwrap();
+
+    match home.del_group(group_name) {
+        Some(_) => ReturnCode::Success,
+        None => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Adds a device of home to the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `group`: group name
+/// * `room`: room name of the device
+/// * `device`: device name
+pub unsafe extern "C" fn smart_home_add_group_member(
+    handle: *mut Home,
+    group: *const c_char,
+    room: *const c_char,
+    device: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+    let room = CStr::from_ptr(room).to_str().unwrap();
+    let device = CStr::from_ptr(device).to_str().unwrap();
+    let member = GroupMember::new(room, device);
+
+    match home.add_group_member(group, member) {
+        Ok(()) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Removes a device from the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: home handle
+/// * `group`: group name
+/// * `room`: room name of the device
+/// * `device`: device name
+pub unsafe extern "C" fn smart_home_del_group_member(
+    handle: *mut Home,
+    group: *const c_char,
+    room: *const c_char,
+    device: *const c_char,
+) -> ReturnCode {
+    let home = &mut *handle;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+    let room = CStr::from_ptr(room).to_str().unwrap();
+    let device = CStr::from_ptr(device).to_str().unwrap();
+    let member = GroupMember::new(room, device);
+
+    match home.del_group_member(group, &member) {
+        Ok(()) => ReturnCode::Success,
+        Err(_) => ReturnCode::Fail,
+    }
+}
+
+#[no_mangle]
+/// Turn on all switchable devices of the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: smart hub handle
+/// * `home`: home handle
+/// * `group`: group name
+pub unsafe extern "C" fn smart_home_group_on(
+    handle: *mut Handle,
+    home: *const Home,
+    group: *const c_char,
+) -> ReturnCode {
+    let handle = &mut *handle;
+    let home = &*home;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+
+    handle.rt.block_on(async {
+        match home.group_on(group).await {
+            Ok(()) => ReturnCode::Success,
+            Err(_) => ReturnCode::Fail,
+        }
+    })
+}
+
+#[no_mangle]
+/// Turn off all switchable devices of the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: smart hub handle
+/// * `home`: home handle
+/// * `group`: group name
+pub unsafe extern "C" fn smart_home_group_off(
+    handle: *mut Handle,
+    home: *const Home,
+    group: *const c_char,
+) -> ReturnCode {
+    let handle = &mut *handle;
+    let home = &*home;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+
+    handle.rt.block_on(async {
+        match home.group_off(group).await {
+            Ok(()) => ReturnCode::Success,
+            Err(_) => ReturnCode::Fail,
+        }
+    })
+}
+
+#[no_mangle]
+/// Get average temperature of the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: smart hub handle
+/// * `home`: home handle
+/// * `group`: group name
+pub unsafe extern "C" fn smart_home_get_group_temperature(
+    handle: *mut Handle,
+    home: *const Home,
+    group: *const c_char,
+) -> f64 {
+    let handle = &mut *handle;
+    let home = &*home;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+
+    handle.rt.block_on(async {
+        home.group_average_temperature(group)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    })
+}
+
+#[no_mangle]
+/// Get total power of the group
+///
+/// # Safety
+///
+/// Home gets from smart_home_get_home()
+///
+/// * `handle`: smart hub handle
+/// * `home`: home handle
+/// * `group`: group name
+pub unsafe extern "C" fn smart_home_get_group_power(
+    handle: *mut Handle,
+    home: *const Home,
+    group: *const c_char,
+) -> f64 {
+    let handle = &mut *handle;
+    let home = &*home;
+    let group = CStr::from_ptr(group).to_str().unwrap();
+
+    handle
+        .rt
+        .block_on(async { home.group_total_power(group).await.unwrap_or_default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &CStr = c"http://127.0.0.1:1";
+
+    #[test]
+    fn pointers_survive_sibling_changes() {
+        unsafe {
+            let handle = smart_home_new();
+
+            smart_home_add_home(handle, c"cottage".as_ptr());
+            let home = smart_home_add_home(handle, c"flat".as_ptr());
+            smart_home_add_room(home, c"hall".as_ptr());
+            let room = smart_home_add_room(home, c"kitchen".as_ptr());
+            smart_home_add_socket(
+                handle,
+                room,
+                c"lamp".as_ptr(),
+                c"".as_ptr(),
+                SERVER.as_ptr(),
+            );
+            smart_home_add_socket(
+                handle,
+                room,
+                c"kettle".as_ptr(),
+                c"".as_ptr(),
+                SERVER.as_ptr(),
+            );
+            let device = smart_home_get_device(room, c"kettle".as_ptr());
+
+            // the new siblings take the freed places, the old pointers still see their objects
+            assert!(matches!(
+                smart_home_del_home(handle, c"cottage".as_ptr()),
+                ReturnCode::Success
+            ));
+            smart_home_add_home(handle, c"garage".as_ptr());
+            assert!(matches!(
+                smart_home_set_home_position(handle, c"garage".as_ptr(), 0),
+                ReturnCode::Success
+            ));
+            assert_eq!((*home).name(), "flat");
+
+            assert!(matches!(
+                smart_home_del_room(home, c"hall".as_ptr()),
+                ReturnCode::Success
+            ));
+            smart_home_add_room(home, c"bedroom".as_ptr());
+            assert_eq!((*room).name(), "kitchen");
+            assert_eq!(
+                (*smart_home_get_room(home, c"kitchen".as_ptr())).name(),
+                "kitchen"
+            );
+
+            assert!(matches!(
+                smart_home_del_device(room, c"lamp".as_ptr()),
+                ReturnCode::Success
+            ));
+            smart_home_add_socket(handle, room, c"fan".as_ptr(), c"".as_ptr(), SERVER.as_ptr());
+            assert_eq!((*device).name(), "kettle");
+
+            smart_home_free(handle);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Aggregated measurements and commands over a set of devices
+//!
+//! The functions take any devices, e.g. clones collected from the locked hub, so the devices can
+//! be queried after the hub is released.
+
+use crate::{error::Result, Device};
+
+/// Average temperature of the devices which report it, None if no device answered
+///
+/// Unavailable devices are skipped.
+pub async fn average_temperature<'a>(devices: impl Iterator<Item = &'a Device>) -> Option<f64> {
+    let mut sum = 0.0;
+    let mut count = 0;
+
+    for sensor in devices.filter_map(|device| device.as_temperature_sensor()) {
+        if let Ok(temperature) = sensor.current_temperature().await {
+            sum += temperature;
+            count += 1;
+        }
+    }
+
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Total power of the devices which report it
+///
+/// Unavailable devices are skipped.
+pub async fn total_power<'a>(devices: impl Iterator<Item = &'a Device>) -> f64 {
+    let mut total = 0.0;
+
+    for meter in devices.filter_map(|device| device.as_power_meter()) {
+        if let Ok(power) = meter.current_power().await {
+            total += power;
+        }
+    }
+
+    total
+}
+
+/// Turn on or off the switchable devices
+///
+/// All devices are tried, the first error is returned.
+pub async fn switch<'a>(devices: impl Iterator<Item = &'a Device>, on: bool) -> Result<()> {
+    let mut result = Ok(());
+
+    for switch in devices.filter_map(|device| device.as_switchable()) {
+        let switched = match on {
+            true => switch.on().await,
+            false => switch.off().await,
+        };
+
+        if let (Err(e), Ok(())) = (switched, &result) {
+            result = Err(e);
+        }
+    }
+
+    result
+}
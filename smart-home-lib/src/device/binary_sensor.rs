@@ -0,0 +1,142 @@
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use async_trait::async_trait;
+use derivative::Derivative;
+use tokio::sync::broadcast;
+
+pub use self::udp_smart_binary_sensor::BinarySensorEvent;
+use self::udp_smart_binary_sensor::UdpSmartBinarySensor;
+use crate::{
+    device::BinaryState,
+    error::{Error, Result},
+    SmartDevice,
+};
+
+mod udp_smart_binary_sensor;
+
+/// Kind of the binary sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinarySensorKind {
+    /// Door contact sensor (active = open)
+    Door,
+    /// Window contact sensor (active = open)
+    Window,
+    /// Motion sensor (active = motion detected)
+    Motion,
+}
+
+impl BinarySensorKind {
+    /// Gets kind name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinarySensorKind::Door => "door",
+            BinarySensorKind::Window => "window",
+            BinarySensorKind::Motion => "motion",
+        }
+    }
+}
+
+impl fmt::Display for BinarySensorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BinarySensorKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "door" => Ok(BinarySensorKind::Door),
+            "window" => Ok(BinarySensorKind::Window),
+            "motion" => Ok(BinarySensorKind::Motion),
+            _ => Err(Error::InvalidBinarySensorKind),
+        }
+    }
+}
+
+/// Smart binary sensor (door/window contact or motion)
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct SmartBinarySensor {
+    name: String,
+    description: String,
+    kind: BinarySensorKind,
+    #[derivative(Debug = "ignore")]
+    server_addr: String,
+    #[derivative(Debug = "ignore")]
+    sensor: UdpSmartBinarySensor,
+}
+
+impl SmartBinarySensor {
+    pub async fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        kind: BinarySensorKind,
+        server_addr: impl Into<String>,
+    ) -> Self {
+        let server_addr = server_addr.into();
+
+        Self {
+            name: name.into(),
+            description: description.into(),
+            kind,
+            sensor: UdpSmartBinarySensor::new(server_addr.clone()).await,
+            server_addr,
+        }
+    }
+
+    /// Gets address the sensor listens on
+    pub fn server_addr(&self) -> &str {
+        &self.server_addr
+    }
+
+    /// Gets sensor kind
+    pub fn kind(&self) -> BinarySensorKind {
+        self.kind
+    }
+
+    /// Check whether sensor is active (open or motion detected)
+    pub async fn is_active(&self) -> Result<bool> {
+        Ok(self.sensor.last_event().await.is_some_and(|e| e.active))
+    }
+
+    /// Get time of the last state change, None if no events received yet
+    pub async fn last_change(&self) -> Result<Option<SystemTime>> {
+        Ok(self.sensor.last_event().await.map(|e| e.timestamp))
+    }
+
+    /// Subscribe to the state change events
+    pub fn subscribe(&self) -> broadcast::Receiver<BinarySensorEvent> {
+        self.sensor.subscribe()
+    }
+}
+
+impl SmartDevice for SmartBinarySensor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn device_type(&self) -> &str {
+        "binary_sensor"
+    }
+
+    fn as_binary_state(&self) -> Option<&dyn BinaryState> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl BinaryState for SmartBinarySensor {
+    async fn is_active(&self) -> Result<bool> {
+        SmartBinarySensor::is_active(self).await
+    }
+
+    async fn last_change(&self) -> Result<Option<SystemTime>> {
+        SmartBinarySensor::last_change(self).await
+    }
+}
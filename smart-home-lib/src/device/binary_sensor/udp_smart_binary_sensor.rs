@@ -0,0 +1,173 @@
+use std::{sync::Arc, time::SystemTime};
+
+use tokio::{
+    net::UdpSocket,
+    sync::{broadcast, Mutex},
+    task::JoinHandle,
+    time::Duration,
+};
+
+/// Capacity of the event channel per subscriber
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+/// Length of the sensor packet
+const PACKET_LEN: usize = 1;
+
+/// State change event of the binary sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinarySensorEvent {
+    /// New state (open or motion detected)
+    pub active: bool,
+    /// Time of the state change
+    pub timestamp: SystemTime,
+}
+
+/// Parse packet: one byte, 0 - inactive, 1 - active
+fn parse_packet(packet: &[u8]) -> Option<bool> {
+    if packet.len() != PACKET_LEN {
+        return None;
+    }
+
+    match packet {
+        [0] => Some(false),
+        [1] => Some(true),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct UdpSmartBinarySensor {
+    last_event: Arc<Mutex<Option<BinarySensorEvent>>>,
+    events: broadcast::Sender<BinarySensorEvent>,
+    receiver: JoinHandle<()>,
+}
+
+impl UdpSmartBinarySensor {
+    pub async fn new(server_addr: String) -> Self {
+        let last_event: Arc<Mutex<Option<BinarySensorEvent>>> = Arc::new(Mutex::new(None));
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
+        let receiver = {
+            let last_event = last_event.clone();
+            let events = events.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    let sock = if let Ok(sock) = UdpSocket::bind(&server_addr).await {
+                        sock
+                    } else {
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        continue;
+                    };
+
+                    // one extra byte, so the longer datagrams are not truncated to a valid packet
+                    let mut buf = [0u8; PACKET_LEN + 1];
+
+                    // keep the socket bound between packets, so bursts of events are not lost
+                    while let Ok((len, _)) = sock.recv_from(&mut buf).await {
+                        let active = match parse_packet(&buf[..len]) {
+                            Some(active) => active,
+                            None => continue,
+                        };
+                        let mut last_event = last_event.lock().await;
+
+                        // the sensor repeats its state periodically, report only changes
+                        if last_event.map(|e| e.active) != Some(active) {
+                            let event = BinarySensorEvent {
+                                active,
+                                timestamp: SystemTime::now(),
+                            };
+
+                            *last_event = Some(event);
+                            // no subscribers is not an error
+                            let _ = events.send(event);
+                        }
+                    }
+                }
+            })
+        };
+
+        Self {
+            last_event,
+            events,
+            receiver,
+        }
+    }
+
+    pub async fn last_event(&self) -> Option<BinarySensorEvent> {
+        *self.last_event.lock().await
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BinarySensorEvent> {
+        self.events.subscribe()
+    }
+}
+
+impl Drop for UdpSmartBinarySensor {
+    fn drop(&mut self) {
+        // releases the socket, so the address can be bound again
+        self.receiver.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_packet(&[0]), Some(false));
+        assert_eq!(parse_packet(&[1]), Some(true));
+        assert_eq!(parse_packet(&[2]), None);
+        assert_eq!(parse_packet(&[]), None);
+        assert_eq!(parse_packet(&[1, 0]), None);
+    }
+
+    #[tokio::test]
+    async fn events() {
+        let sensor = UdpSmartBinarySensor::new("127.0.0.1:10102".to_string()).await;
+        let mut events = sensor.subscribe();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // wait for the receiver to bind
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(sensor.last_event().await, None);
+
+        for state in [1, 1, 0] {
+            sender.send_to(&[state], "127.0.0.1:10102").await.unwrap();
+        }
+
+        let event = events.recv().await.unwrap();
+        assert!(event.active);
+        let event = events.recv().await.unwrap();
+        assert!(!event.active);
+
+        assert_eq!(sensor.last_event().await, Some(event));
+
+        // longer datagrams are rejected as a whole
+        sender.send_to(&[1, 0], "127.0.0.1:10102").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(sensor.last_event().await, Some(event));
+    }
+
+    #[tokio::test]
+    async fn rebind_after_drop() {
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        for _ in 0..2 {
+            let sensor = UdpSmartBinarySensor::new("127.0.0.1:10103".to_string()).await;
+            let mut events = sensor.subscribe();
+
+            // wait for the receiver to bind
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            sender.send_to(&[1], "127.0.0.1:10103").await.unwrap();
+
+            let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+                .await
+                .expect("the address is released by the dropped sensor")
+                .unwrap();
+            assert!(event.active);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use async_trait::async_trait;
+
+use crate::error::{Error, Result};
+
+/// Device capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Device can be turned on and off ([`Switchable`])
+    Switchable,
+    /// Device reports temperature ([`TemperatureSensor`])
+    Temperature,
+    /// Device reports using power ([`PowerMeter`])
+    Power,
+    /// Device reports humidity ([`HumiditySensor`])
+    Humidity,
+    /// Device reports pressure ([`PressureSensor`])
+    Pressure,
+    /// Device reports an active/inactive state ([`BinaryState`])
+    BinaryState,
+    /// Device keeps a target temperature ([`TemperatureControl`])
+    TemperatureControl,
+}
+
+impl Capability {
+    /// All known capabilities
+    pub const ALL: [Capability; 7] = [
+        Capability::Switchable,
+        Capability::Temperature,
+        Capability::Power,
+        Capability::Humidity,
+        Capability::Pressure,
+        Capability::BinaryState,
+        Capability::TemperatureControl,
+    ];
+
+    /// Gets capability name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Switchable => "switchable",
+            Capability::Temperature => "temperature",
+            Capability::Power => "power",
+            Capability::Humidity => "humidity",
+            Capability::Pressure => "pressure",
+            Capability::BinaryState => "binary_state",
+            Capability::TemperatureControl => "temperature_control",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Capability::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .ok_or(Error::InvalidCapability)
+    }
+}
+
+/// Device which can be turned on and off
+#[async_trait]
+pub trait Switchable: Send + Sync {
+    /// Turn on
+    async fn on(&self) -> Result<()>;
+    /// Turn off
+    async fn off(&self) -> Result<()>;
+}
+
+/// Device which reports temperature
+#[async_trait]
+pub trait TemperatureSensor: Send + Sync {
+    /// Get current temperature (Celsius)
+    async fn current_temperature(&self) -> Result<f64>;
+}
+
+/// Device which reports using power
+#[async_trait]
+pub trait PowerMeter: Send + Sync {
+    /// Get current using power (Watts)
+    async fn current_power(&self) -> Result<f64>;
+}
+
+/// Device which reports humidity
+#[async_trait]
+pub trait HumiditySensor: Send + Sync {
+    /// Get current relative humidity (percent)
+    async fn current_humidity(&self) -> Result<f64>;
+}
+
+/// Device which reports pressure
+#[async_trait]
+pub trait PressureSensor: Send + Sync {
+    /// Get current pressure (hPa)
+    async fn current_pressure(&self) -> Result<f64>;
+}
+
+/// Device which reports an active/inactive state (open/closed, motion/no motion)
+#[async_trait]
+pub trait BinaryState: Send + Sync {
+    /// Check whether device is active
+    async fn is_active(&self) -> Result<bool>;
+    /// Get time of the last state change, None if unknown
+    async fn last_change(&self) -> Result<Option<SystemTime>>;
+}
+
+/// Device which keeps a target temperature
+#[async_trait]
+pub trait TemperatureControl: Send + Sync {
+    /// Get target temperature (Celsius)
+    async fn target_temperature(&self) -> Result<f64>;
+    /// Set target temperature (Celsius)
+    async fn set_target_temperature(&self, target_temperature: f64) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        for capability in Capability::ALL {
+            assert_eq!(
+                capability.to_string().parse::<Capability>().unwrap(),
+                capability
+            );
+        }
+
+        assert!(matches!(
+            "flying".parse::<Capability>(),
+            Err(Error::InvalidCapability)
+        ));
+    }
+}
@@ -1,36 +1,94 @@
+use std::{any::Any, fmt, sync::Arc};
+
+use crate::{
+    health::{Health, HealthProbe, SharedHealth},
+    Id, Metadata,
+};
+
+pub mod binary_sensor;
+pub mod capability;
+pub mod multi_sensor;
 pub mod socket;
 pub mod thermometer;
+pub mod thermostat;
+mod udp_receiver;
 
-pub use socket::SmartSocket;
+pub use binary_sensor::{BinarySensorEvent, BinarySensorKind, SmartBinarySensor};
+pub use capability::{
+    BinaryState, Capability, HumiditySensor, PowerMeter, PressureSensor, Switchable,
+    TemperatureControl, TemperatureSensor,
+};
+pub use multi_sensor::{Measurements, SmartMultiSensor};
+pub use socket::{SmartSocket, SocketSecurity, SocketTls};
 pub use thermometer::SmartThermometer;
+pub use thermostat::{SmartThermostat, ThermostatMode};
 
-pub trait SmartDevice {
-    /// Gets device name
+/// Smart device implementation
+///
+/// Device declares its capabilities by overriding the `as_*` methods,
+/// so the hub can use any device type without knowing it.
+pub trait SmartDevice: Any + Send + Sync + fmt::Debug {
+    /// Gets device name given on creation, the device is renamed by [`crate::Room::rename_device`]
     fn name(&self) -> &str;
     /// Gets device description
     fn description(&self) -> &str;
     /// Gets device type
     fn device_type(&self) -> &str;
-}
 
-/// Smart device
-#[derive(Debug)]
-pub enum Device {
-    /// smart thermometer
-    Thermometer(SmartThermometer),
-    /// smart socket
-    Socket(SmartSocket),
-}
+    /// Gets switchable capability
+    fn as_switchable(&self) -> Option<&dyn Switchable> {
+        None
+    }
+    /// Gets temperature capability
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        None
+    }
+    /// Gets power capability
+    fn as_power_meter(&self) -> Option<&dyn PowerMeter> {
+        None
+    }
+    /// Gets humidity capability
+    fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        None
+    }
+    /// Gets pressure capability
+    fn as_pressure_sensor(&self) -> Option<&dyn PressureSensor> {
+        None
+    }
+    /// Gets binary state capability
+    fn as_binary_state(&self) -> Option<&dyn BinaryState> {
+        None
+    }
+    /// Gets temperature control capability
+    fn as_temperature_control(&self) -> Option<&dyn TemperatureControl> {
+        None
+    }
 
-impl From<SmartThermometer> for Device {
-    fn from(s: SmartThermometer) -> Self {
-        Device::Thermometer(s)
+    /// Gets health check of the device, None if the device can't be checked
+    fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+        None
     }
 }
 
-impl From<SmartSocket> for Device {
-    fn from(s: SmartSocket) -> Self {
-        Device::Socket(s)
+/// Smart device
+///
+/// Clones share the implementation and the health, so a clone can talk to the device without
+/// borrowing the hub. Name, tags and properties of a clone are a copy.
+#[derive(Clone)]
+pub struct Device {
+    id: Id,
+    name: String,
+    metadata: Metadata,
+    health: SharedHealth,
+    inner: Arc<dyn SmartDevice>,
+}
+
+impl<T> From<T> for Device
+where
+    T: SmartDevice,
+{
+    fn from(device: T) -> Self {
+        Device::new(device)
     }
 }
 
@@ -40,44 +98,216 @@ impl PartialEq for Device {
     }
 }
 
+impl fmt::Debug for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
 impl Device {
     /// Create a new smart device
     pub fn new<T>(device: T) -> Self
     where
-        T: SmartDevice + Into<Device>,
+        T: SmartDevice,
     {
-        device.into()
+        Self {
+            id: Id::new_v4(),
+            name: device.name().to_string(),
+            metadata: Default::default(),
+            health: Default::default(),
+            inner: Arc::new(device),
+        }
     }
-}
 
-impl SmartDevice for Device {
-    fn name(&self) -> &str {
-        match self {
-            Device::Socket(socket) => socket.name(),
-            Device::Thermometer(thermometer) => thermometer.name(),
-        }
+    /// Use the id instead of the generated one, e.g. to restore the device from a storage
+    pub fn with_id(mut self, id: Id) -> Self {
+        self.id = id;
+        self
     }
 
-    fn description(&self) -> &str {
-        match self {
-            Device::Socket(socket) => socket.description(),
-            Device::Thermometer(thermometer) => thermometer.description(),
-        }
+    /// Gets device id
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Gets device name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets device name, use [`crate::Room::rename_device`] for the device in the room
+    pub(crate) fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Gets device description
+    pub fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    /// Gets device tags and properties
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Gets mutable device tags and properties
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// Gets device type
+    pub fn device_type(&self) -> &str {
+        self.inner.device_type()
+    }
+
+    /// Gets device health, updated by [`crate::HealthChecker`]
+    pub fn health(&self) -> Health {
+        self.health.lock().expect("health lock").clone()
+    }
+
+    /// Gets health check of the device
+    pub fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+        self.inner.health_probe()
+    }
+
+    pub(crate) fn shared_health(&self) -> SharedHealth {
+        self.health.clone()
+    }
+
+    /// Gets device implementation
+    pub fn as_smart_device(&self) -> &dyn SmartDevice {
+        self.inner.as_ref()
+    }
+
+    /// Check whether device implementation is `T`
+    pub fn is<T>(&self) -> bool
+    where
+        T: SmartDevice,
+    {
+        self.downcast_ref::<T>().is_some()
     }
 
-    fn device_type(&self) -> &str {
-        match self {
-            Device::Socket(socket) => socket.device_type(),
-            Device::Thermometer(thermometer) => thermometer.device_type(),
+    /// Gets device implementation as `T`
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: SmartDevice,
+    {
+        let device: &dyn Any = self.inner.as_ref();
+        device.downcast_ref()
+    }
+
+    /// Gets mutable device implementation as `T`, None while a clone of the device exists
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: SmartDevice,
+    {
+        let device: &mut dyn Any = Arc::get_mut(&mut self.inner)?;
+        device.downcast_mut()
+    }
+
+    /// Gets switchable capability
+    pub fn as_switchable(&self) -> Option<&dyn Switchable> {
+        self.inner.as_switchable()
+    }
+
+    /// Gets temperature capability
+    pub fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        self.inner.as_temperature_sensor()
+    }
+
+    /// Gets power capability
+    pub fn as_power_meter(&self) -> Option<&dyn PowerMeter> {
+        self.inner.as_power_meter()
+    }
+
+    /// Gets humidity capability
+    pub fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        self.inner.as_humidity_sensor()
+    }
+
+    /// Gets pressure capability
+    pub fn as_pressure_sensor(&self) -> Option<&dyn PressureSensor> {
+        self.inner.as_pressure_sensor()
+    }
+
+    /// Gets binary state capability
+    pub fn as_binary_state(&self) -> Option<&dyn BinaryState> {
+        self.inner.as_binary_state()
+    }
+
+    /// Gets temperature control capability
+    pub fn as_temperature_control(&self) -> Option<&dyn TemperatureControl> {
+        self.inner.as_temperature_control()
+    }
+
+    /// Check whether device has a capability
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Switchable => self.as_switchable().is_some(),
+            Capability::Temperature => self.as_temperature_sensor().is_some(),
+            Capability::Power => self.as_power_meter().is_some(),
+            Capability::Humidity => self.as_humidity_sensor().is_some(),
+            Capability::Pressure => self.as_pressure_sensor().is_some(),
+            Capability::BinaryState => self.as_binary_state().is_some(),
+            Capability::TemperatureControl => self.as_temperature_control().is_some(),
         }
     }
+
+    /// Gets all device capabilities
+    pub fn capabilities(&self) -> Vec<Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(|c| self.has_capability(*c))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use async_trait::async_trait;
     use pretty_assertions::assert_eq;
+    use tokio::sync::Mutex;
 
     use super::*;
+    use crate::error::Result;
+
+    /// Device type which is defined outside of the library
+    #[derive(Debug)]
+    struct Lamp {
+        name: String,
+        enabled: Mutex<bool>,
+    }
+
+    impl SmartDevice for Lamp {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "lamp"
+        }
+
+        fn device_type(&self) -> &str {
+            "lamp"
+        }
+
+        fn as_switchable(&self) -> Option<&dyn Switchable> {
+            Some(self)
+        }
+    }
+
+    #[async_trait]
+    impl Switchable for Lamp {
+        async fn on(&self) -> Result<()> {
+            *self.enabled.lock().await = true;
+            Ok(())
+        }
+
+        async fn off(&self) -> Result<()> {
+            *self.enabled.lock().await = false;
+            Ok(())
+        }
+    }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn device_stuff() {
@@ -89,11 +319,71 @@ mod tests {
         let device = Device::new(smart_socket);
         assert_eq!(device.name(), "socket");
         assert_eq!(device.description(), "socket in the bedroom");
-        assert!(matches!(&device, &Device::Socket { .. }));
+        assert!(device.is::<SmartSocket>());
+        assert_eq!(
+            device.capabilities(),
+            vec![Capability::Switchable, Capability::Power]
+        );
 
         let device = Device::new(smart_thermometer);
         assert_eq!(device.name(), "thermometer");
         assert_eq!(device.description(), "thermometer in the bedroom");
-        assert!(matches!(&device, &Device::Thermometer { .. }));
+        assert!(device.is::<SmartThermometer>());
+        assert_eq!(device.capabilities(), vec![Capability::Temperature]);
+
+        let smart_sensor =
+            SmartMultiSensor::new("sensor", "sensor in the bedroom", "0.0.0.0:82").await;
+
+        let device = Device::new(smart_sensor);
+        assert_eq!(device.name(), "sensor");
+        assert_eq!(device.description(), "sensor in the bedroom");
+        assert_eq!(device.device_type(), "multi_sensor");
+        assert!(device.is::<SmartMultiSensor>());
+        assert_eq!(
+            device.capabilities(),
+            vec![
+                Capability::Temperature,
+                Capability::Humidity,
+                Capability::Pressure
+            ]
+        );
+
+        let smart_sensor =
+            SmartBinarySensor::new("door", "front door", BinarySensorKind::Door, "0.0.0.0:84")
+                .await;
+        assert_eq!(smart_sensor.kind(), BinarySensorKind::Door);
+        assert!(!smart_sensor.is_active().await.unwrap());
+        assert_eq!(smart_sensor.last_change().await.unwrap(), None);
+
+        let device = Device::new(smart_sensor);
+        assert_eq!(device.name(), "door");
+        assert_eq!(device.device_type(), "binary_sensor");
+        assert!(device.is::<SmartBinarySensor>());
+        assert_eq!(device.capabilities(), vec![Capability::BinaryState]);
+    }
+
+    #[tokio::test]
+    async fn custom_device() {
+        let mut device = Device::new(Lamp {
+            name: "lamp".to_string(),
+            enabled: Mutex::new(false),
+        });
+
+        assert_eq!(device.name(), "lamp");
+        assert!(!device.is::<SmartSocket>());
+        assert!(device.has_capability(Capability::Switchable));
+        assert!(!device.has_capability(Capability::Power));
+
+        device.as_switchable().unwrap().on().await.unwrap();
+        assert!(*device.downcast_ref::<Lamp>().unwrap().enabled.lock().await);
+
+        let handle = device.clone();
+        assert!(device.downcast_mut::<Lamp>().is_none());
+        handle.as_switchable().unwrap().off().await.unwrap();
+        assert!(!*device.downcast_ref::<Lamp>().unwrap().enabled.lock().await);
+        drop(handle);
+
+        *device.downcast_mut::<Lamp>().unwrap().enabled.get_mut() = true;
+        assert!(*device.downcast_ref::<Lamp>().unwrap().enabled.lock().await);
     }
 }
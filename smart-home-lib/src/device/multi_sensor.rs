@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derivative::Derivative;
+
+pub use self::udp_smart_multi_sensor::Measurements;
+use self::udp_smart_multi_sensor::UdpSmartMultiSensor;
+use crate::{
+    device::{HumiditySensor, PressureSensor, TemperatureSensor},
+    error::{Error, Result},
+    HealthProbe, SmartDevice,
+};
+
+mod udp_smart_multi_sensor;
+
+/// Smart multi-sensor (get temperature, humidity and pressure)
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct SmartMultiSensor {
+    name: String,
+    description: String,
+    #[derivative(Debug = "ignore")]
+    server_addr: String,
+    #[derivative(Debug = "ignore")]
+    sensor: UdpSmartMultiSensor,
+}
+
+impl SmartMultiSensor {
+    pub async fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        server_addr: impl Into<String>,
+    ) -> Self {
+        let server_addr = server_addr.into();
+
+        Self {
+            name: name.into(),
+            description: description.into(),
+            sensor: UdpSmartMultiSensor::new(server_addr.clone()).await,
+            server_addr,
+        }
+    }
+
+    /// Gets address the sensor listens on
+    pub fn server_addr(&self) -> &str {
+        &self.server_addr
+    }
+
+    /// Get all last received measurements
+    pub async fn current_measurements(&self) -> Result<Measurements> {
+        self.sensor.current_measurements().await
+    }
+
+    /// Get current temperature (Celsius)
+    pub async fn current_temperature(&self) -> Result<f64> {
+        self.current_measurements()
+            .await?
+            .temperature
+            .ok_or(Error::MeasurementNotAvailable)
+    }
+
+    /// Get current relative humidity (percent)
+    pub async fn current_humidity(&self) -> Result<f64> {
+        self.current_measurements()
+            .await?
+            .humidity
+            .ok_or(Error::MeasurementNotAvailable)
+    }
+
+    /// Get current pressure (hPa)
+    pub async fn current_pressure(&self) -> Result<f64> {
+        self.current_measurements()
+            .await?
+            .pressure
+            .ok_or(Error::MeasurementNotAvailable)
+    }
+}
+
+impl SmartDevice for SmartMultiSensor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn device_type(&self) -> &str {
+        "multi_sensor"
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+
+    fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        Some(self)
+    }
+
+    fn as_pressure_sensor(&self) -> Option<&dyn PressureSensor> {
+        Some(self)
+    }
+
+    fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+        Some(self.sensor.health_probe())
+    }
+}
+
+#[async_trait]
+impl TemperatureSensor for SmartMultiSensor {
+    async fn current_temperature(&self) -> Result<f64> {
+        SmartMultiSensor::current_temperature(self).await
+    }
+}
+
+#[async_trait]
+impl HumiditySensor for SmartMultiSensor {
+    async fn current_humidity(&self) -> Result<f64> {
+        SmartMultiSensor::current_humidity(self).await
+    }
+}
+
+#[async_trait]
+impl PressureSensor for SmartMultiSensor {
+    async fn current_pressure(&self) -> Result<f64> {
+        SmartMultiSensor::current_pressure(self).await
+    }
+}
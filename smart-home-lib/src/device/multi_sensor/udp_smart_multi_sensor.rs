@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crate::{device::udp_receiver::UdpReceiver, error::Result, HealthProbe};
+
+/// Size of the one measurement record: kind (u8) + value (f64, big endian)
+const RECORD_SIZE: usize = 9;
+/// Maximum count of the records in the one packet
+const MAX_RECORDS: usize = 16;
+
+const KIND_TEMPERATURE: u8 = 1;
+const KIND_HUMIDITY: u8 = 2;
+const KIND_PRESSURE: u8 = 3;
+
+/// Last received measurements from the multi-sensor
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Measurements {
+    /// Temperature (Celsius)
+    pub temperature: Option<f64>,
+    /// Relative humidity (percent)
+    pub humidity: Option<f64>,
+    /// Pressure (hPa)
+    pub pressure: Option<f64>,
+}
+
+impl Measurements {
+    /// Update measurements from the packet
+    ///
+    /// The packet is a sequence of the records: one byte of the measurement kind and
+    /// 8 bytes of the big endian f64 value. Unknown kinds are skipped.
+    /// Returns false if the packet is malformed.
+    fn update_from_packet(&mut self, packet: &[u8]) -> bool {
+        if packet.is_empty() || packet.len() % RECORD_SIZE != 0 {
+            return false;
+        }
+
+        for record in packet.chunks_exact(RECORD_SIZE) {
+            let value = f64::from_be_bytes(record[1..].try_into().expect("8 bytes"));
+
+            match record[0] {
+                KIND_TEMPERATURE => self.temperature = Some(value),
+                KIND_HUMIDITY => self.humidity = Some(value),
+                KIND_PRESSURE => self.pressure = Some(value),
+                _ => {}
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct UdpSmartMultiSensor {
+    receiver: Arc<UdpReceiver<Measurements>>,
+}
+
+impl UdpSmartMultiSensor {
+    pub async fn new(server_addr: String) -> Self {
+        // packets may carry a part of the measurements, so they are merged
+        let mut measurements = Measurements::default();
+        let receiver = UdpReceiver::spawn(server_addr, RECORD_SIZE * MAX_RECORDS, move |packet| {
+            measurements
+                .update_from_packet(packet)
+                .then_some(measurements)
+        });
+
+        Self {
+            receiver: Arc::new(receiver),
+        }
+    }
+
+    pub async fn current_measurements(&self) -> Result<Measurements> {
+        Ok(self.receiver.value().await.unwrap_or_default())
+    }
+
+    /// Check of the received data freshness
+    pub fn health_probe(&self) -> Arc<dyn HealthProbe> {
+        self.receiver.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::{
+        net::UdpSocket,
+        time::{self, Duration},
+    };
+
+    use super::*;
+
+    fn record(kind: u8, value: f64) -> Vec<u8> {
+        let mut record = vec![kind];
+        record.extend_from_slice(&value.to_be_bytes());
+        record
+    }
+
+    #[test]
+    fn parse_packet() {
+        let mut measurements = Measurements::default();
+
+        let packet = [record(KIND_TEMPERATURE, 21.5), record(KIND_HUMIDITY, 40.0)].concat();
+        assert!(measurements.update_from_packet(&packet));
+        assert_eq!(
+            measurements,
+            Measurements {
+                temperature: Some(21.5),
+                humidity: Some(40.0),
+                pressure: None,
+            }
+        );
+
+        let packet = [record(KIND_PRESSURE, 1013.25), record(42, 1.0)].concat();
+        assert!(measurements.update_from_packet(&packet));
+        assert_eq!(
+            measurements,
+            Measurements {
+                temperature: Some(21.5),
+                humidity: Some(40.0),
+                pressure: Some(1013.25),
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_packet() {
+        let mut measurements = Measurements::default();
+
+        assert!(!measurements.update_from_packet(&[]));
+        assert!(!measurements.update_from_packet(&21.5f64.to_be_bytes()));
+        assert_eq!(measurements, Measurements::default());
+    }
+
+    #[tokio::test]
+    async fn receive_packets() {
+        let sensor = UdpSmartMultiSensor::new("127.0.0.1:10107".to_string()).await;
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // wait for the receiver to bind
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(sensor.health_probe().probe().await.is_err());
+
+        for packet in [record(KIND_TEMPERATURE, 21.5), record(KIND_HUMIDITY, 40.0)] {
+            sender.send_to(&packet, "127.0.0.1:10107").await.unwrap();
+        }
+        time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(
+            sensor.current_measurements().await.unwrap(),
+            Measurements {
+                temperature: Some(21.5),
+                humidity: Some(40.0),
+                pressure: None,
+            }
+        );
+        assert!(sensor.health_probe().probe().await.is_ok());
+    }
+}
@@ -1,9 +1,18 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
 use derivative::Derivative;
 
 use self::grpc_smart_socket::GrpcSmartSocket;
-use crate::{error::Result, SmartDevice};
+pub use self::security::{SocketSecurity, SocketTls};
+use crate::{
+    device::{PowerMeter, Switchable},
+    error::Result,
+    HealthProbe, SmartDevice,
+};
 
 mod grpc_smart_socket;
+mod security;
 
 /// Smart socket (on/off power, get current using power)
 #[derive(Derivative)]
@@ -12,6 +21,8 @@ pub struct SmartSocket {
     name: String,
     description: String,
     #[derivative(Debug = "ignore")]
+    server_addr: String,
+    #[derivative(Debug = "ignore")]
     socket: GrpcSmartSocket,
 }
 
@@ -27,6 +38,36 @@ impl SmartDevice for SmartSocket {
     fn device_type(&self) -> &str {
         "socket"
     }
+
+    fn as_switchable(&self) -> Option<&dyn Switchable> {
+        Some(self)
+    }
+
+    fn as_power_meter(&self) -> Option<&dyn PowerMeter> {
+        Some(self)
+    }
+
+    fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+        Some(Arc::new(self.socket.clone()))
+    }
+}
+
+#[async_trait]
+impl Switchable for SmartSocket {
+    async fn on(&self) -> Result<()> {
+        self.socket.on().await
+    }
+
+    async fn off(&self) -> Result<()> {
+        self.socket.off().await
+    }
+}
+
+#[async_trait]
+impl PowerMeter for SmartSocket {
+    async fn current_power(&self) -> Result<f64> {
+        self.socket.current_power().await
+    }
 }
 
 impl SmartSocket {
@@ -36,13 +77,27 @@ impl SmartSocket {
         description: impl Into<String>,
         server_addr: impl Into<String>,
     ) -> Self {
+        let server_addr = server_addr.into();
+
         Self {
             name: name.into(),
             description: description.into(),
-            socket: GrpcSmartSocket::new(server_addr.into()).await,
+            socket: GrpcSmartSocket::new(server_addr.clone()).await,
+            server_addr,
         }
     }
 
+    /// Gets address of the socket server
+    pub fn server_addr(&self) -> &str {
+        &self.server_addr
+    }
+
+    /// Use TLS and/or token authentication for the connection to the socket server
+    pub fn with_security(mut self, security: SocketSecurity) -> Self {
+        self.socket.set_security(security);
+        self
+    }
+
     /// Enable smart socket
     pub async fn on(&self) -> Result<()> {
         self.socket.on().await
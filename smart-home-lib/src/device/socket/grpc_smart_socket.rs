@@ -1,53 +1,295 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
 use smart_socket::{socket_client::SocketClient, CurrentPowerRequest, OffRequest, OnRequest};
+use tonic::{
+    codegen::InterceptedService,
+    transport::{Channel, Endpoint},
+    Code,
+};
+use tonic_health::proto::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
 
-use crate::error::{Error, Result};
+use super::security::{BearerToken, SocketSecurity};
+use crate::{
+    error::{Error, Result},
+    HealthProbe,
+};
 
 /// GRPC Socket implementation
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GrpcSmartSocket {
     server_addr: tonic::transport::Uri,
+    security: SocketSecurity,
 }
 
 mod smart_socket {
     tonic::include_proto!("smart_home_socket");
 }
 
+/// Name of the socket service in the standard gRPC health checking service
+const SOCKET_SERVICE: &str = "smart_home_socket.Socket";
+/// Time to establish the connection to the socket server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Time for the socket server to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl GrpcSmartSocket {
     /// connect to GRPC socket server
     pub async fn new(server_addr: String) -> Self {
         Self {
             server_addr: server_addr.try_into().expect("valid server address"),
+            security: SocketSecurity::default(),
         }
     }
 
-    pub async fn on(&self) -> Result<()> {
-        SocketClient::connect(self.server_addr.clone())
+    pub fn set_security(&mut self, security: SocketSecurity) {
+        self.security = security;
+    }
+
+    async fn channel(&self) -> Result<InterceptedService<Channel, BearerToken>> {
+        let token = BearerToken::new(self.security.token())
+            .ok_or_else(|| Error::DeviceUnreachable("invalid token".to_string()))?;
+        let mut endpoint = Endpoint::from(self.server_addr.clone())
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT);
+
+        if let Some(tls) = self.security.tls() {
+            endpoint = endpoint
+                .tls_config(tls.client_config())
+                .map_err(|e| Error::DeviceUnreachable(e.to_string()))?;
+        }
+
+        let channel = endpoint
+            .connect()
             .await
-            .expect("connect")
+            .map_err(|e| Error::DeviceUnreachable(e.to_string()))?;
+
+        Ok(InterceptedService::new(channel, token))
+    }
+
+    async fn connect(&self) -> Result<SocketClient<InterceptedService<Channel, BearerToken>>> {
+        Ok(SocketClient::new(self.channel().await?))
+    }
+
+    pub async fn on(&self) -> Result<()> {
+        self.connect()
+            .await?
             .on(OnRequest {})
             .await
-            .map_err(|_| Error::Internal)?;
+            .map_err(|e| Error::DeviceUnreachable(e.message().to_string()))?;
         Ok(())
     }
 
     pub async fn off(&self) -> Result<()> {
-        SocketClient::connect(self.server_addr.clone())
-            .await
-            .expect("connect")
+        self.connect()
+            .await?
             .off(OffRequest {})
             .await
-            .map_err(|_| Error::Internal)?;
+            .map_err(|e| Error::DeviceUnreachable(e.message().to_string()))?;
         Ok(())
     }
 
     pub async fn current_power(&self) -> Result<f64> {
-        let response = SocketClient::connect(self.server_addr.clone())
-            .await
-            .expect("connect")
+        let response = self
+            .connect()
+            .await?
             .current_power(CurrentPowerRequest {})
             .await
-            .map_err(|_| Error::Internal)?;
+            .map_err(|e| Error::DeviceUnreachable(e.message().to_string()))?;
 
         Ok(response.into_inner().current_power)
     }
 }
+
+#[async_trait]
+impl HealthProbe for GrpcSmartSocket {
+    /// Ask the standard gRPC health service, servers without it are pinged with the cheapest
+    /// request
+    async fn probe(&self) -> Result<SystemTime> {
+        let mut client = HealthClient::new(self.channel().await?);
+        let response = client
+            .check(HealthCheckRequest {
+                service: SOCKET_SERVICE.to_string(),
+            })
+            .await;
+
+        match response {
+            Ok(response) if response.get_ref().status == ServingStatus::Serving as i32 => {
+                Ok(SystemTime::now())
+            }
+            Ok(_) => Err(Error::DeviceUnreachable(
+                "socket is not serving".to_string(),
+            )),
+            Err(status) if status.code() == Code::Unimplemented => {
+                self.current_power().await?;
+                Ok(SystemTime::now())
+            }
+            Err(status) => Err(Error::DeviceUnreachable(status.message().to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{
+        service::Interceptor,
+        transport::{Certificate, Identity, Server, ServerTlsConfig},
+        Request, Response, Status,
+    };
+
+    use super::{
+        smart_socket::{
+            socket_server::{Socket, SocketServer},
+            CurrentPowerResponse, OffResponse, OnResponse,
+        },
+        *,
+    };
+    use crate::SocketTls;
+
+    struct TestSocket;
+
+    #[tonic::async_trait]
+    impl Socket for TestSocket {
+        async fn on(
+            &self,
+            _: Request<OnRequest>,
+        ) -> std::result::Result<Response<OnResponse>, Status> {
+            Ok(Response::new(OnResponse {}))
+        }
+
+        async fn off(
+            &self,
+            _: Request<OffRequest>,
+        ) -> std::result::Result<Response<OffResponse>, Status> {
+            Ok(Response::new(OffResponse {}))
+        }
+
+        async fn current_power(
+            &self,
+            _: Request<CurrentPowerRequest>,
+        ) -> std::result::Result<Response<CurrentPowerResponse>, Status> {
+            Ok(Response::new(CurrentPowerResponse {
+                current_power: 42.0,
+            }))
+        }
+    }
+
+    struct Pki {
+        ca: String,
+        server: (String, String),
+        client: (String, String),
+    }
+
+    fn generate_pki() -> Pki {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let leaf = |name: &str| {
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec![name.to_string()])
+                .unwrap()
+                .signed_by(&key, &ca, &ca_key)
+                .unwrap();
+            (cert.pem(), key.serialize_pem())
+        };
+
+        Pki {
+            server: leaf("localhost"),
+            client: leaf("client"),
+            ca: ca.pem(),
+        }
+    }
+
+    #[derive(Clone)]
+    struct TokenCheck;
+
+    impl Interceptor for TokenCheck {
+        fn call(&mut self, request: Request<()>) -> std::result::Result<Request<()>, Status> {
+            match request.metadata().get("authorization") {
+                Some(token) if token == "Bearer secret" => Ok(request),
+                _ => Err(Status::unauthenticated("invalid token")),
+            }
+        }
+    }
+
+    /// Start the socket server which requires TLS client certificates and the token
+    async fn serve(pki: &Pki) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tls = ServerTlsConfig::new()
+            .identity(Identity::from_pem(&pki.server.0, &pki.server.1))
+            .client_ca_root(Certificate::from_pem(&pki.ca));
+        let server = Server::builder()
+            .tls_config(tls)
+            .unwrap()
+            .add_service(SocketServer::with_interceptor(TestSocket, TokenCheck))
+            .serve_with_incoming(TcpListenerStream::new(listener));
+        tokio::spawn(server);
+
+        format!("https://127.0.0.1:{}", addr.port())
+    }
+
+    fn socket(addr: &str, security: SocketSecurity) -> GrpcSmartSocket {
+        GrpcSmartSocket {
+            server_addr: addr.try_into().unwrap(),
+            security,
+        }
+    }
+
+    #[tokio::test]
+    async fn tls_and_token() {
+        let pki = generate_pki();
+        let addr = serve(&pki).await;
+        let tls = SocketTls::new()
+            .with_ca_certificate(pki.ca.as_str())
+            .with_domain_name("localhost");
+        let client_tls = tls
+            .clone()
+            .with_identity(pki.client.0.as_str(), pki.client.1.as_str());
+
+        let trusted = socket(
+            &addr,
+            SocketSecurity::new()
+                .with_tls(client_tls.clone())
+                .with_token("secret"),
+        );
+        assert_eq!(trusted.current_power().await.unwrap(), 42.0);
+        trusted.on().await.unwrap();
+        trusted.off().await.unwrap();
+        // the test server has no health service, so the probe falls back to the power request
+        trusted.probe().await.unwrap();
+
+        let wrong_token = socket(
+            &addr,
+            SocketSecurity::new()
+                .with_tls(client_tls.clone())
+                .with_token("guess"),
+        );
+        assert!(matches!(
+            wrong_token.current_power().await,
+            Err(Error::DeviceUnreachable(message)) if message == "invalid token"
+        ));
+
+        let no_token = socket(&addr, SocketSecurity::new().with_tls(client_tls));
+        assert!(no_token.current_power().await.is_err());
+
+        let no_client_certificate = socket(
+            &addr,
+            SocketSecurity::new().with_tls(tls).with_token("secret"),
+        );
+        assert!(no_client_certificate.current_power().await.is_err());
+
+        let plaintext = socket(
+            &addr.replace("https", "http"),
+            SocketSecurity::new().with_token("secret"),
+        );
+        assert!(plaintext.current_power().await.is_err());
+    }
+}
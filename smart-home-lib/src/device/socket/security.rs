@@ -0,0 +1,142 @@
+use derivative::Derivative;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    transport::{Certificate, ClientTlsConfig, Identity},
+    Request, Status,
+};
+
+/// TLS settings of the socket connection, certificates and keys are PEM encoded
+/// ```
+/// use smart_home_lib::SocketTls;
+///
+/// # let (ca, cert, key) = (Vec::new(), Vec::new(), Vec::new());
+/// let tls = SocketTls::new()
+///     .with_ca_certificate(ca)
+///     .with_identity(cert, key)
+///     .with_domain_name("socket.local");
+/// ```
+#[derive(Derivative, Default, Clone)]
+#[derivative(Debug)]
+pub struct SocketTls {
+    ca_certificate: Option<Vec<u8>>,
+    #[derivative(Debug = "ignore")]
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    domain_name: Option<String>,
+}
+
+impl SocketTls {
+    /// Construct TLS settings which trust the system root certificates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust the server certificates signed by the custom CA
+    pub fn with_ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificate = Some(pem.into());
+        self
+    }
+
+    /// Authenticate with the client certificate
+    pub fn with_identity(
+        mut self,
+        certificate: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.identity = Some((certificate.into(), key.into()));
+        self
+    }
+
+    /// Expect the server certificate for this domain instead of the host of the server address
+    pub fn with_domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    pub(crate) fn client_config(&self) -> ClientTlsConfig {
+        let mut config = ClientTlsConfig::new();
+
+        if let Some(ca_certificate) = &self.ca_certificate {
+            config = config.ca_certificate(Certificate::from_pem(ca_certificate));
+        }
+
+        if let Some((certificate, key)) = &self.identity {
+            config = config.identity(Identity::from_pem(certificate, key));
+        }
+
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name);
+        }
+
+        config
+    }
+}
+
+/// Security settings of the socket connection, plaintext without authentication by default
+/// ```
+/// use smart_home_lib::{SocketSecurity, SocketTls};
+///
+/// let security = SocketSecurity::new()
+///     .with_tls(SocketTls::new())
+///     .with_token("secret");
+/// ```
+#[derive(Derivative, Default, Clone)]
+#[derivative(Debug)]
+pub struct SocketSecurity {
+    tls: Option<SocketTls>,
+    #[derivative(Debug = "ignore")]
+    token: Option<String>,
+}
+
+impl SocketSecurity {
+    /// Construct plaintext settings without authentication
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect over TLS
+    pub fn with_tls(mut self, tls: SocketTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Send the bearer token with every request
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub(crate) fn tls(&self) -> Option<&SocketTls> {
+        self.tls.as_ref()
+    }
+
+    pub(crate) fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+/// Interceptor which adds the `authorization` header to the requests
+#[derive(Clone)]
+pub(crate) struct BearerToken(Option<MetadataValue<Ascii>>);
+
+impl BearerToken {
+    /// Returns `None` if the token can't be sent in the header
+    pub(crate) fn new(token: Option<&str>) -> Option<Self> {
+        match token {
+            Some(token) => Some(Self(Some(format!("Bearer {}", token).parse().ok()?))),
+            None => Some(Self(None)),
+        }
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.0 {
+            request
+                .metadata_mut()
+                .insert("authorization", token.clone());
+        }
+
+        Ok(request)
+    }
+}
@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
 use derivative::Derivative;
 
 use self::udp_smart_thermometer::UdpSmartThermometer;
-use crate::{error::Result, SmartDevice};
+use crate::{device::TemperatureSensor, error::Result, HealthProbe, SmartDevice};
 
 mod udp_smart_thermometer;
 
@@ -12,6 +15,8 @@ pub struct SmartThermometer {
     name: String,
     description: String,
     #[derivative(Debug = "ignore")]
+    server_addr: String,
+    #[derivative(Debug = "ignore")]
     thermometer: UdpSmartThermometer,
 }
 
@@ -21,13 +26,38 @@ impl SmartThermometer {
         description: impl Into<String>,
         server_addr: impl Into<String>,
     ) -> Self {
+        let server_addr = server_addr.into();
+
+        Self {
+            name: name.into(),
+            description: description.into(),
+            thermometer: UdpSmartThermometer::new(server_addr.clone(), None).await,
+            server_addr,
+        }
+    }
+
+    /// Construct thermometer which accepts only packets signed with the pre-shared key
+    pub async fn with_key(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        server_addr: impl Into<String>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        let server_addr = server_addr.into();
+
         Self {
             name: name.into(),
             description: description.into(),
-            thermometer: UdpSmartThermometer::new(server_addr.into()).await,
+            thermometer: UdpSmartThermometer::new(server_addr.clone(), Some(key.into())).await,
+            server_addr,
         }
     }
 
+    /// Gets address the thermometer listens on
+    pub fn server_addr(&self) -> &str {
+        &self.server_addr
+    }
+
     /// Get current temperature
     pub async fn current_temperature(&self) -> Result<f64> {
         self.thermometer.current_temperature().await
@@ -46,4 +76,19 @@ impl SmartDevice for SmartThermometer {
     fn device_type(&self) -> &str {
         "thermometer"
     }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+
+    fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+        Some(self.thermometer.health_probe())
+    }
+}
+
+#[async_trait]
+impl TemperatureSensor for SmartThermometer {
+    async fn current_temperature(&self) -> Result<f64> {
+        self.thermometer.current_temperature().await
+    }
 }
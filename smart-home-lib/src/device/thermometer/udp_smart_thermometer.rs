@@ -1,56 +1,223 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::time::Duration;
 
-use tokio::{
-    net::UdpSocket,
-    sync::Mutex,
-    time::{self, Duration},
+use crate::{
+    device::udp_receiver::UdpReceiver,
+    error::{Error, Result},
+    HealthProbe,
 };
 
-use crate::error::Result;
+/// Length of the plain packet: temperature (`f64` big endian)
+const PLAIN_PACKET_LEN: usize = 8;
+/// Length of the signed packet: sequence number (`u64` big endian), temperature (`f64` big endian)
+/// and HMAC-SHA256 of them
+const SIGNED_PACKET_LEN: usize = 8 + 8 + 32;
+/// Signed packets with the sequence number further behind the clock are replays. The sender starts
+/// the sequence from the current time in milliseconds, so it doesn't lag behind while it sends
+/// less than one packet per millisecond.
+const REPLAY_WINDOW: Duration = Duration::from_secs(60);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Verification of the received packets
+#[derive(Debug, Default)]
+struct PacketVerifier {
+    /// Pre-shared key, only signed packets are accepted if it is set
+    key: Option<Vec<u8>>,
+    /// Sequence number of the last accepted packet
+    last_sequence: Option<u64>,
+}
+
+impl PacketVerifier {
+    fn new(key: Option<Vec<u8>>) -> Self {
+        Self {
+            key,
+            last_sequence: None,
+        }
+    }
+
+    /// Get temperature from the packet, returns `None` for malformed, forged or replayed packets
+    fn accept(&mut self, packet: &[u8]) -> Option<f64> {
+        self.accept_at(packet, SystemTime::now())
+    }
+
+    /// Same as [`Self::accept`] with the given current time, the last sequence number is lost on
+    /// restart, so old packets are told by the time
+    fn accept_at(&mut self, packet: &[u8], now: SystemTime) -> Option<f64> {
+        let key = match &self.key {
+            Some(key) => key,
+            None if packet.len() == PLAIN_PACKET_LEN => {
+                return Some(f64::from_be_bytes(packet.try_into().ok()?))
+            }
+            None => return None,
+        };
+
+        if packet.len() != SIGNED_PACKET_LEN {
+            return None;
+        }
+
+        let (payload, signature) = packet.split_at(16);
+        let mut mac = HmacSha256::new_from_slice(key).ok()?;
+        mac.update(payload);
+        mac.verify_slice(signature).ok()?;
+
+        let (sequence, temperature) = payload.split_at(8);
+        let sequence = u64::from_be_bytes(sequence.try_into().ok()?);
+
+        if self.last_sequence.is_some_and(|last| sequence <= last) {
+            return None;
+        }
 
-#[derive(Debug)]
+        let oldest = now
+            .checked_sub(REPLAY_WINDOW)
+            .and_then(|oldest| oldest.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |oldest| oldest.as_millis() as u64);
+        if sequence < oldest {
+            return None;
+        }
+
+        self.last_sequence = Some(sequence);
+        Some(f64::from_be_bytes(temperature.try_into().ok()?))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct UdpSmartThermometer {
-    temperature: Arc<Mutex<f64>>,
+    /// Shared by the clones, the address is released when the last clone is dropped
+    receiver: Arc<UdpReceiver<f64>>,
 }
 
 impl UdpSmartThermometer {
-    pub async fn new(server_addr: String) -> Self {
-        let temperature = Arc::new(Mutex::new(0.0));
-
-        {
-            let temperature = temperature.clone();
-
-            tokio::spawn(async move {
-                loop {
-                    let sock = if let Ok(sock) = UdpSocket::bind(&server_addr).await {
-                        sock
-                    } else {
-                        tokio::time::sleep(Duration::from_secs(10)).await;
-                        continue;
-                    };
-
-                    let sleep = time::sleep(Duration::from_secs(10));
-                    tokio::pin!(sleep);
-
-                    let mut buf = [0u8; 8];
-
-                    tokio::select! {
-                        maybe_bytes = sock.recv_from(&mut buf) => {
-                            if let Ok((8, _)) = maybe_bytes {
-                                *temperature.lock().await = f64::from_be_bytes(buf);
-                            }
-                        }
-                        _ = &mut sleep => {
-                        }
-                    }
-                }
-            });
-        }
+    /// Listen for the thermometer packets, only signed with the key packets are accepted if it is
+    /// set
+    pub async fn new(server_addr: String, key: Option<Vec<u8>>) -> Self {
+        let mut verifier = PacketVerifier::new(key);
+        let receiver = UdpReceiver::spawn(server_addr, SIGNED_PACKET_LEN, move |packet| {
+            verifier.accept(packet)
+        });
 
-        Self { temperature }
+        Self {
+            receiver: Arc::new(receiver),
+        }
     }
 
+    /// Last received temperature, it is not available until the first packet
     pub async fn current_temperature(&self) -> Result<f64> {
-        Ok(*self.temperature.lock().await)
+        self.receiver
+            .value()
+            .await
+            .ok_or(Error::MeasurementNotAvailable)
+    }
+
+    /// Check of the received data freshness
+    pub fn health_probe(&self) -> Arc<dyn HealthProbe> {
+        self.receiver.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::{net::UdpSocket, time};
+
+    use super::*;
+
+    fn signed_packet(key: &[u8], sequence: u64, temperature: f64) -> Vec<u8> {
+        let mut packet = [sequence.to_be_bytes(), temperature.to_be_bytes()].concat();
+        let mut mac = HmacSha256::new_from_slice(key).unwrap();
+        mac.update(&packet);
+        packet.extend(mac.finalize().into_bytes());
+        packet
+    }
+
+    #[test]
+    fn plain_packets() {
+        let mut verifier = PacketVerifier::new(None);
+
+        assert_eq!(verifier.accept(&21.5f64.to_be_bytes()), Some(21.5));
+        assert_eq!(verifier.accept(&[0; 4]), None);
+        assert_eq!(verifier.accept(&signed_packet(b"key", 1, 20.0)), None);
+    }
+
+    /// Sequence number the sender uses at the given time
+    fn sequence_at(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+
+    #[test]
+    fn signed_packets() {
+        let mut verifier = PacketVerifier::new(Some(b"key".to_vec()));
+        let now = SystemTime::now();
+        let start = sequence_at(now);
+
+        assert_eq!(verifier.accept_at(&21.5f64.to_be_bytes(), now), None);
+        assert_eq!(
+            verifier.accept_at(&signed_packet(b"other", start, 20.0), now),
+            None
+        );
+
+        let packet = signed_packet(b"key", start + 5, 20.0);
+        assert_eq!(verifier.accept_at(&packet, now), Some(20.0));
+        // replayed
+        assert_eq!(verifier.accept_at(&packet, now), None);
+        assert_eq!(
+            verifier.accept_at(&signed_packet(b"key", start + 4, 19.0), now),
+            None
+        );
+
+        let mut tampered = signed_packet(b"key", start + 6, 21.0);
+        tampered[15] ^= 1;
+        assert_eq!(verifier.accept_at(&tampered, now), None);
+
+        assert_eq!(
+            verifier.accept_at(&signed_packet(b"key", start + 7, 22.0), now),
+            Some(22.0)
+        );
+    }
+
+    #[test]
+    fn replay_after_restart() {
+        let sent = SystemTime::now();
+        let packet = signed_packet(b"key", sequence_at(sent), 20.0);
+
+        let mut verifier = PacketVerifier::new(Some(b"key".to_vec()));
+        assert_eq!(
+            verifier.accept_at(&packet, sent + Duration::from_secs(1)),
+            Some(20.0)
+        );
+
+        // the restarted receiver has no last sequence number
+        let mut verifier = PacketVerifier::new(Some(b"key".to_vec()));
+        let later = sent + REPLAY_WINDOW + Duration::from_secs(1);
+        assert_eq!(verifier.accept_at(&packet, later), None);
+
+        let fresh = signed_packet(b"key", sequence_at(later), 21.0);
+        assert_eq!(verifier.accept_at(&fresh, later), Some(21.0));
+    }
+
+    #[tokio::test]
+    async fn no_temperature_before_first_packet() {
+        let thermometer = UdpSmartThermometer::new("127.0.0.1:10104".to_string(), None).await;
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // wait for the receiver to bind
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            thermometer.current_temperature().await,
+            Err(Error::MeasurementNotAvailable)
+        ));
+
+        sender
+            .send_to(&21.5f64.to_be_bytes(), "127.0.0.1:10104")
+            .await
+            .unwrap();
+        time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(thermometer.current_temperature().await.unwrap(), 21.5);
     }
 }
@@ -0,0 +1,417 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use derivative::Derivative;
+use tokio::{
+    sync::Mutex,
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+use crate::{
+    device::{TemperatureControl, TemperatureSensor},
+    error::{Error, Result},
+    SmartDevice, SmartSocket, SmartThermometer,
+};
+
+/// Period of the thermostat control loop
+const CONTROL_PERIOD: Duration = Duration::from_secs(5);
+/// Default target temperature (Celsius)
+const DEFAULT_TARGET_TEMPERATURE: f64 = 21.0;
+/// Default hysteresis (Celsius)
+const DEFAULT_HYSTERESIS: f64 = 0.5;
+
+/// Thermostat operation mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermostatMode {
+    /// Heater is always off
+    Off,
+    /// Heater is always on
+    Heat,
+    /// Heater keeps the target temperature
+    Auto,
+}
+
+impl ThermostatMode {
+    /// Gets mode name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThermostatMode::Off => "off",
+            ThermostatMode::Heat => "heat",
+            ThermostatMode::Auto => "auto",
+        }
+    }
+}
+
+impl fmt::Display for ThermostatMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ThermostatMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(ThermostatMode::Off),
+            "heat" => Ok(ThermostatMode::Heat),
+            "auto" => Ok(ThermostatMode::Auto),
+            _ => Err(Error::InvalidThermostatMode),
+        }
+    }
+}
+
+/// Decide whether the heater should run
+///
+/// In the auto mode the heater turns on below `target - hysteresis` and turns off above
+/// `target + hysteresis`, inside this band the previous heater state is kept.
+fn heater_should_run(
+    mode: ThermostatMode,
+    temperature: f64,
+    target: f64,
+    hysteresis: f64,
+    heating: bool,
+) -> bool {
+    match mode {
+        ThermostatMode::Off => false,
+        ThermostatMode::Heat => true,
+        ThermostatMode::Auto => {
+            if temperature < target - hysteresis {
+                true
+            } else if temperature > target + hysteresis {
+                false
+            } else {
+                heating
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ThermostatState {
+    mode: ThermostatMode,
+    target_temperature: f64,
+    hysteresis: f64,
+    /// Last state sent to the heater, None if unknown
+    heating: Option<bool>,
+}
+
+#[derive(Debug)]
+struct ThermostatInner {
+    thermometer: SmartThermometer,
+    heater: SmartSocket,
+    state: Mutex<ThermostatState>,
+    /// Serializes the control steps, so the state stays readable while the heater is switched
+    step: Mutex<()>,
+}
+
+impl ThermostatInner {
+    /// Read the temperature and switch the heater if needed
+    ///
+    /// The temperature is read only in the auto mode, the other modes don't depend on it.
+    async fn control_step(&self) -> Result<()> {
+        let _step = self.step.lock().await;
+        let temperature = match self.state.lock().await.mode {
+            ThermostatMode::Auto => self.thermometer.current_temperature().await?,
+            ThermostatMode::Off | ThermostatMode::Heat => f64::NAN,
+        };
+
+        let should_run = {
+            let state = self.state.lock().await;
+            let should_run = heater_should_run(
+                state.mode,
+                temperature,
+                state.target_temperature,
+                state.hysteresis,
+                state.heating.unwrap_or(false),
+            );
+
+            if state.heating == Some(should_run) {
+                return Ok(());
+            }
+
+            should_run
+        };
+
+        if should_run {
+            self.heater.on().await?;
+        } else {
+            self.heater.off().await?;
+        }
+
+        self.state.lock().await.heating = Some(should_run);
+
+        Ok(())
+    }
+
+    /// Apply the changed settings to the heater
+    ///
+    /// The settings are kept if the heater can't be switched now, the control loop retries.
+    async fn apply(&self) {
+        if let Err(e) = self.control_step().await {
+            log::warn!("Failed to apply the thermostat settings: {}", e);
+        }
+    }
+}
+
+/// Smart thermostat (thermometer with the target temperature, which drives the heater socket)
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct SmartThermostat {
+    name: String,
+    description: String,
+    #[derivative(Debug = "ignore")]
+    inner: Arc<ThermostatInner>,
+    #[derivative(Debug = "ignore")]
+    control_loop: JoinHandle<()>,
+}
+
+impl SmartThermostat {
+    /// Create thermostat and start the control loop
+    pub async fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        thermometer: SmartThermometer,
+        heater: SmartSocket,
+    ) -> Self {
+        let inner = Arc::new(ThermostatInner {
+            thermometer,
+            heater,
+            state: Mutex::new(ThermostatState {
+                mode: ThermostatMode::Off,
+                target_temperature: DEFAULT_TARGET_TEMPERATURE,
+                hysteresis: DEFAULT_HYSTERESIS,
+                heating: None,
+            }),
+            step: Mutex::new(()),
+        });
+
+        let control_loop = {
+            let inner = inner.clone();
+
+            tokio::spawn(async move {
+                let mut interval = time::interval(CONTROL_PERIOD);
+
+                loop {
+                    interval.tick().await;
+                    // errors are retried on the next tick
+                    let _ = inner.control_step().await;
+                }
+            })
+        };
+
+        Self {
+            name: name.into(),
+            description: description.into(),
+            inner,
+            control_loop,
+        }
+    }
+
+    /// Gets the thermometer of the thermostat
+    pub fn thermometer(&self) -> &SmartThermometer {
+        &self.inner.thermometer
+    }
+
+    /// Gets the heater of the thermostat
+    pub fn heater(&self) -> &SmartSocket {
+        &self.inner.heater
+    }
+
+    /// Get current temperature
+    pub async fn current_temperature(&self) -> Result<f64> {
+        self.inner.thermometer.current_temperature().await
+    }
+
+    /// Get target temperature
+    pub async fn target_temperature(&self) -> f64 {
+        self.inner.state.lock().await.target_temperature
+    }
+
+    /// Set target temperature and apply it to the heater
+    ///
+    /// Fails only for an invalid temperature, the heater is switched later if it is unreachable
+    pub async fn set_target_temperature(&self, target_temperature: f64) -> Result<()> {
+        if !target_temperature.is_finite() {
+            return Err(Error::InvalidTemperature);
+        }
+
+        self.inner.state.lock().await.target_temperature = target_temperature;
+        self.inner.apply().await;
+
+        Ok(())
+    }
+
+    /// Get hysteresis
+    pub async fn hysteresis(&self) -> f64 {
+        self.inner.state.lock().await.hysteresis
+    }
+
+    /// Set hysteresis and apply it to the heater
+    ///
+    /// Fails only for an invalid hysteresis, the heater is switched later if it is unreachable
+    pub async fn set_hysteresis(&self, hysteresis: f64) -> Result<()> {
+        if !hysteresis.is_finite() || hysteresis < 0.0 {
+            return Err(Error::InvalidTemperature);
+        }
+
+        self.inner.state.lock().await.hysteresis = hysteresis;
+        self.inner.apply().await;
+
+        Ok(())
+    }
+
+    /// Get operation mode
+    pub async fn mode(&self) -> ThermostatMode {
+        self.inner.state.lock().await.mode
+    }
+
+    /// Set operation mode and apply it to the heater
+    pub async fn set_mode(&self, mode: ThermostatMode) {
+        self.inner.state.lock().await.mode = mode;
+        self.inner.apply().await;
+    }
+
+    /// Check whether the heater is running
+    pub async fn is_heating(&self) -> bool {
+        self.inner.state.lock().await.heating.unwrap_or(false)
+    }
+}
+
+impl Drop for SmartThermostat {
+    fn drop(&mut self) {
+        self.control_loop.abort();
+    }
+}
+
+impl SmartDevice for SmartThermostat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn device_type(&self) -> &str {
+        "thermostat"
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+
+    fn as_temperature_control(&self) -> Option<&dyn TemperatureControl> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl TemperatureSensor for SmartThermostat {
+    async fn current_temperature(&self) -> Result<f64> {
+        SmartThermostat::current_temperature(self).await
+    }
+}
+
+#[async_trait]
+impl TemperatureControl for SmartThermostat {
+    async fn target_temperature(&self) -> Result<f64> {
+        Ok(SmartThermostat::target_temperature(self).await)
+    }
+
+    async fn set_target_temperature(&self, target_temperature: f64) -> Result<()> {
+        SmartThermostat::set_target_temperature(self, target_temperature).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn mode_parse() {
+        for mode in [
+            ThermostatMode::Off,
+            ThermostatMode::Heat,
+            ThermostatMode::Auto,
+        ] {
+            assert_eq!(mode.to_string().parse::<ThermostatMode>().unwrap(), mode);
+        }
+
+        assert!(matches!(
+            "cool".parse::<ThermostatMode>(),
+            Err(Error::InvalidThermostatMode)
+        ));
+    }
+
+    #[test]
+    fn hysteresis() {
+        use ThermostatMode::*;
+
+        assert!(!heater_should_run(Off, 10.0, 21.0, 0.5, true));
+        assert!(heater_should_run(Heat, 30.0, 21.0, 0.5, false));
+
+        // below the band
+        assert!(heater_should_run(Auto, 20.4, 21.0, 0.5, false));
+        // inside the band: keep the previous state
+        assert!(heater_should_run(Auto, 21.4, 21.0, 0.5, true));
+        assert!(!heater_should_run(Auto, 20.6, 21.0, 0.5, false));
+        // above the band
+        assert!(!heater_should_run(Auto, 21.6, 21.0, 0.5, true));
+    }
+
+    #[tokio::test]
+    async fn settings() {
+        let thermostat = SmartThermostat::new(
+            "thermostat",
+            "thermostat in the bedroom",
+            SmartThermometer::new("thermometer", "", "0.0.0.0:83").await,
+            SmartSocket::new("heater", "", "http://127.0.0.1:1").await,
+        )
+        .await;
+
+        assert_eq!(thermostat.name(), "thermostat");
+        assert_eq!(thermostat.device_type(), "thermostat");
+        assert_eq!(thermostat.mode().await, ThermostatMode::Off);
+        assert_eq!(
+            thermostat.target_temperature().await,
+            DEFAULT_TARGET_TEMPERATURE
+        );
+        assert!(!thermostat.is_heating().await);
+        assert_eq!(thermostat.heater().name(), "heater");
+
+        assert!(matches!(
+            thermostat.set_target_temperature(f64::NAN).await,
+            Err(Error::InvalidTemperature)
+        ));
+        assert_eq!(
+            thermostat.target_temperature().await,
+            DEFAULT_TARGET_TEMPERATURE
+        );
+    }
+
+    #[tokio::test]
+    async fn settings_without_heater() {
+        // the thermometer has no reading and nothing listens on the heater port
+        let thermostat = SmartThermostat::new(
+            "thermostat",
+            "",
+            SmartThermometer::new("thermometer", "", "127.0.0.1:0").await,
+            SmartSocket::new("heater", "", "http://127.0.0.1:1").await,
+        )
+        .await;
+
+        thermostat.set_mode(ThermostatMode::Heat).await;
+        thermostat.set_target_temperature(23.5).await.unwrap();
+        thermostat.set_hysteresis(1.0).await.unwrap();
+        thermostat.set_mode(ThermostatMode::Auto).await;
+
+        assert_eq!(thermostat.mode().await, ThermostatMode::Auto);
+        assert_eq!(thermostat.target_temperature().await, 23.5);
+        assert_eq!(thermostat.hysteresis().await, 1.0);
+        assert!(!thermostat.is_heating().await);
+    }
+}
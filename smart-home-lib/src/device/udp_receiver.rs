@@ -0,0 +1,136 @@
+use std::{sync::Arc, time::SystemTime};
+
+use async_trait::async_trait;
+use tokio::{
+    net::UdpSocket,
+    sync::Mutex,
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+use crate::{
+    error::{Error, Result},
+    HealthProbe,
+};
+
+/// Delay before the next attempt to bind the address
+const REBIND_DELAY: Duration = Duration::from_secs(10);
+
+/// Value of the last accepted packet
+#[derive(Debug, Clone, Copy)]
+struct Reading<T> {
+    value: T,
+    /// Time the packet is received
+    received: SystemTime,
+}
+
+/// Background task which listens on the UDP address and keeps the value of the last accepted
+/// packet
+///
+/// The task is stopped and the address is released when the receiver is dropped.
+#[derive(Debug)]
+pub(crate) struct UdpReceiver<T> {
+    last: Arc<Mutex<Option<Reading<T>>>>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Copy + Send + 'static> UdpReceiver<T> {
+    /// Bind the address and pass the received packets of up to `max_len` bytes to `parse`, which
+    /// returns None for the rejected packets
+    pub(crate) fn spawn(
+        server_addr: String,
+        max_len: usize,
+        mut parse: impl FnMut(&[u8]) -> Option<T> + Send + 'static,
+    ) -> Self {
+        let last = Arc::new(Mutex::new(None));
+
+        let task = {
+            let last = last.clone();
+
+            tokio::spawn(async move {
+                // one extra byte, so the longer datagrams are not truncated to a valid packet
+                let mut buf = vec![0u8; max_len + 1];
+
+                loop {
+                    let sock = if let Ok(sock) = UdpSocket::bind(&server_addr).await {
+                        sock
+                    } else {
+                        time::sleep(REBIND_DELAY).await;
+                        continue;
+                    };
+
+                    // keep the socket bound between packets, so bursts of packets are not lost
+                    while let Ok((len, _)) = sock.recv_from(&mut buf).await {
+                        if let Some(value) = parse(&buf[..len]) {
+                            *last.lock().await = Some(Reading {
+                                value,
+                                received: SystemTime::now(),
+                            });
+                        }
+                    }
+                }
+            })
+        };
+
+        Self { last, task }
+    }
+
+    /// Value of the last accepted packet, None until the first one
+    pub(crate) async fn value(&self) -> Option<T> {
+        self.last.lock().await.map(|reading| reading.value)
+    }
+}
+
+impl<T> Drop for UdpReceiver<T> {
+    fn drop(&mut self) {
+        // releases the socket, so the address can be bound again
+        self.task.abort();
+    }
+}
+
+#[async_trait]
+impl<T: Copy + Send + 'static> HealthProbe for UdpReceiver<T> {
+    /// Check freshness of the received data
+    async fn probe(&self) -> Result<SystemTime> {
+        self.last
+            .lock()
+            .await
+            .map(|reading| reading.received)
+            .ok_or(Error::MeasurementNotAvailable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn rebind_after_drop() {
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        for value in [1, 2] {
+            let receiver =
+                UdpReceiver::spawn("127.0.0.1:10106".to_string(), 1, |packet| match packet {
+                    [value] => Some(*value),
+                    _ => None,
+                });
+
+            // wait for the receiver to bind
+            time::sleep(Duration::from_millis(100)).await;
+            assert!(receiver.probe().await.is_err());
+            sender.send_to(&[value], "127.0.0.1:10106").await.unwrap();
+            // longer packets are not truncated
+            sender.send_to(&[9, 9], "127.0.0.1:10106").await.unwrap();
+            time::sleep(Duration::from_millis(100)).await;
+
+            assert_eq!(
+                receiver.value().await,
+                Some(value),
+                "the address is released by the dropped receiver"
+            );
+            assert!(receiver.probe().await.is_ok());
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::{Device, Home, Room};
+use crate::{Device, DeviceGroup, Home, Room};
 
 /// Error kind
 #[derive(Error, Debug)]
@@ -18,13 +18,61 @@ pub enum Error {
     ThermometerImplNotFound,
 
     #[error("Home is already exists")]
-    HomeAlreadyExists(Home),
+    HomeAlreadyExists(Box<Home>),
 
     #[error("Device is already exists")]
-    DeviceAlreadyExists(Device),
+    DeviceAlreadyExists(Box<Device>),
 
     #[error("Room is already exists")]
-    RoomAlreadyExists(Room),
+    RoomAlreadyExists(Box<Room>),
+
+    #[error("Home is not found")]
+    HomeNotFound,
+
+    #[error("Room is not found")]
+    RoomNotFound,
+
+    #[error("Device is not found")]
+    DeviceNotFound,
+
+    #[error("Device group is already exists")]
+    GroupAlreadyExists(Box<DeviceGroup>),
+
+    #[error("Device group is not found")]
+    GroupNotFound,
+
+    #[error("Device is already in the group")]
+    GroupMemberAlreadyExists,
+
+    #[error("Device is not in the group")]
+    GroupMemberNotFound,
+
+    #[error("Name {0} is already taken")]
+    NameAlreadyTaken(String),
+
+    #[error("Name is empty")]
+    EmptyName,
+
+    #[error("Position is out of range")]
+    InvalidPosition,
+
+    #[error("Device is unreachable: {0}")]
+    DeviceUnreachable(String),
+
+    #[error("Measurement is not available")]
+    MeasurementNotAvailable,
+
+    #[error("Invalid temperature value")]
+    InvalidTemperature,
+
+    #[error("Invalid thermostat mode")]
+    InvalidThermostatMode,
+
+    #[error("Invalid binary sensor kind")]
+    InvalidBinarySensorKind,
+
+    #[error("Invalid device capability")]
+    InvalidCapability,
 
     #[error("Not yet implemented")]
     NotImplemented,
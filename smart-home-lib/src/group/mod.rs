@@ -0,0 +1,98 @@
+/// Path to the device in the home
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GroupMember {
+    /// Room name
+    pub room: String,
+    /// Device name in the room
+    pub device: String,
+}
+
+impl GroupMember {
+    /// Construct a new group member
+    pub fn new(room: impl Into<String>, device: impl Into<String>) -> Self {
+        Self {
+            room: room.into(),
+            device: device.into(),
+        }
+    }
+}
+
+/// Named set of devices from different rooms of the home
+///
+/// Members are referenced by path, renames and moves inside the home update the paths. A member of
+/// a removed device is skipped until the path is valid again.
+/// ```
+/// use smart_home_lib::{DeviceGroup, GroupMember};
+///
+/// let mut group = DeviceGroup::new("heaters");
+/// assert!(group.add_member(GroupMember::new("bedroom", "heater")));
+/// assert!(!group.add_member(GroupMember::new("bedroom", "heater")));
+/// assert_eq!(group.members().count(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceGroup {
+    /// Name of the group
+    name: String,
+    /// Device paths in the order of adding
+    members: Vec<GroupMember>,
+}
+
+impl DeviceGroup {
+    /// Construct a new empty group
+    pub fn new<T>(name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            members: Vec::new(),
+        }
+    }
+
+    /// Gets group name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add member, returns false if it is already in the group
+    pub fn add_member(&mut self, member: GroupMember) -> bool {
+        if self.members.contains(&member) {
+            return false;
+        }
+
+        self.members.push(member);
+        true
+    }
+
+    /// Remove member, returns false if it is not in the group
+    pub fn remove_member(&mut self, member: &GroupMember) -> bool {
+        let len = self.members.len();
+        self.members.retain(|m| m != member);
+        self.members.len() != len
+    }
+
+    /// Keep only the members for which `keep` returns true
+    pub(crate) fn retain_members(&mut self, keep: impl FnMut(&GroupMember) -> bool) {
+        self.members.retain(keep);
+    }
+
+    /// Get iterator over members
+    pub fn members(&self) -> impl Iterator<Item = &GroupMember> {
+        self.members.iter()
+    }
+
+    /// Rewrite member paths, e.g. after a rename, the paths which became equal are merged
+    pub(crate) fn update_members(&mut self, mut update: impl FnMut(&mut GroupMember)) {
+        let mut members: Vec<GroupMember> = Vec::with_capacity(self.members.len());
+
+        for mut member in self.members.drain(..) {
+            update(&mut member);
+
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+
+        self.members = members;
+    }
+}
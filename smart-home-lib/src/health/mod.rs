@@ -0,0 +1,305 @@
+//! Background device health checks
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use tokio::{sync::Mutex, task::JoinHandle, time};
+
+use crate::{
+    error::{Error, Result},
+    SmartHub,
+};
+
+/// Device health status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HealthStatus {
+    /// Device was not checked yet or can't be checked
+    #[default]
+    Unknown,
+    /// Device answers and its data is fresh
+    Online,
+    /// Device answers with stale data or the last checks failed
+    Degraded,
+    /// Device doesn't answer
+    Offline,
+}
+
+impl HealthStatus {
+    /// Gets status name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Unknown => "unknown",
+            HealthStatus::Online => "online",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Offline => "offline",
+        }
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Device health
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Health {
+    /// Current status
+    pub status: HealthStatus,
+    /// Time of the last answer or data from the device
+    pub last_seen: Option<SystemTime>,
+    /// Error of the last failed check
+    pub last_error: Option<String>,
+    /// Count of the failed checks in a row
+    pub failures: u32,
+}
+
+/// Device health check
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// Check the device, returns the time of the last answer or data from the device
+    async fn probe(&self) -> Result<SystemTime>;
+}
+
+/// Health check settings
+#[derive(Debug, Clone, Copy)]
+pub struct HealthConfig {
+    /// Period of the checks
+    pub period: Duration,
+    /// Data older than this is degraded
+    pub stale_after: Duration,
+    /// Data older than this is offline
+    pub offline_after: Duration,
+    /// Count of the failed checks in a row to consider the device offline
+    pub max_failures: u32,
+    /// Probes which don't answer in this time fail
+    pub probe_timeout: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_secs(10),
+            stale_after: Duration::from_secs(30),
+            offline_after: Duration::from_secs(120),
+            max_failures: 3,
+            probe_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Health {
+    /// Update health by the probe result
+    pub(crate) fn update(&mut self, result: Result<SystemTime>, config: &HealthConfig) {
+        match result {
+            Ok(last_seen) => {
+                let age = SystemTime::now()
+                    .duration_since(last_seen)
+                    .unwrap_or_default();
+
+                self.last_seen = Some(last_seen);
+                self.failures = 0;
+
+                if age <= config.stale_after {
+                    self.status = HealthStatus::Online;
+                    self.last_error = None;
+                } else {
+                    self.status = if age <= config.offline_after {
+                        HealthStatus::Degraded
+                    } else {
+                        HealthStatus::Offline
+                    };
+                    self.last_error = Some(format!("No data for {} s", age.as_secs()));
+                }
+            }
+            Err(e) => {
+                self.failures += 1;
+                self.last_error = Some(e.to_string());
+                self.status = if self.failures >= config.max_failures {
+                    HealthStatus::Offline
+                } else {
+                    HealthStatus::Degraded
+                };
+            }
+        }
+    }
+}
+
+/// Shared health of the device, updated by the checker
+pub(crate) type SharedHealth = Arc<StdMutex<Health>>;
+
+/// Check all devices of the hub once
+///
+/// The hub is locked only to collect the probes, so checks don't block other hub users. Devices
+/// are probed concurrently, so a device which doesn't answer delays the others at most by the
+/// probe timeout.
+pub async fn check_devices(hub: &Mutex<SmartHub>, config: &HealthConfig) {
+    let probes: Vec<_> = hub
+        .lock()
+        .await
+        .iter()
+        .flat_map(|home| home.room_iter())
+        .flat_map(|room| room.device_iter())
+        .filter_map(|device| Some((device.health_probe()?, device.shared_health())))
+        .collect();
+
+    join_all(probes.into_iter().map(|(probe, health)| async move {
+        let result = time::timeout(config.probe_timeout, probe.probe())
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::DeviceUnreachable(format!(
+                    "no answer in {} s",
+                    config.probe_timeout.as_secs_f64()
+                )))
+            });
+        health.lock().expect("health lock").update(result, config);
+    }))
+    .await;
+}
+
+/// Background task which periodically checks health of all devices in the hub
+#[derive(Debug)]
+pub struct HealthChecker {
+    task: JoinHandle<()>,
+}
+
+impl HealthChecker {
+    /// Start the checks
+    pub fn spawn(hub: Arc<Mutex<SmartHub>>, config: HealthConfig) -> Self {
+        let task = tokio::spawn(async move {
+            let mut interval = time::interval(config.period);
+
+            loop {
+                interval.tick().await;
+                check_devices(&hub, &config).await;
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for HealthChecker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{Home, Room, SmartDevice};
+
+    #[test]
+    fn update() {
+        let config = HealthConfig::default();
+        let mut health = Health::default();
+        assert_eq!(health.status, HealthStatus::Unknown);
+
+        let now = SystemTime::now();
+        health.update(Ok(now), &config);
+        assert_eq!(health.status, HealthStatus::Online);
+        assert_eq!(health.last_seen, Some(now));
+        assert_eq!(health.last_error, None);
+
+        health.update(Ok(now - Duration::from_secs(60)), &config);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert!(health.last_error.is_some());
+
+        health.update(Ok(now - Duration::from_secs(600)), &config);
+        assert_eq!(health.status, HealthStatus::Offline);
+
+        health.update(Ok(now), &config);
+        for _ in 1..config.max_failures {
+            health.update(Err(Error::DeviceUnreachable("refused".into())), &config);
+            assert_eq!(health.status, HealthStatus::Degraded);
+        }
+        health.update(Err(Error::DeviceUnreachable("refused".into())), &config);
+        assert_eq!(health.status, HealthStatus::Offline);
+        assert_eq!(health.last_seen, Some(now));
+        assert_eq!(
+            health.last_error.as_deref(),
+            Some("Device is unreachable: refused")
+        );
+    }
+
+    /// Device which never answers the health check
+    #[derive(Debug)]
+    struct Hanging(&'static str);
+
+    impl SmartDevice for Hanging {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            ""
+        }
+
+        fn device_type(&self) -> &str {
+            "hanging"
+        }
+
+        fn health_probe(&self) -> Option<Arc<dyn HealthProbe>> {
+            Some(Arc::new(HangingProbe))
+        }
+    }
+
+    struct HangingProbe;
+
+    #[async_trait]
+    impl HealthProbe for HangingProbe {
+        async fn probe(&self) -> Result<SystemTime> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_timeout() {
+        let hub = Mutex::new(SmartHub::new());
+        {
+            let mut hub = hub.lock().await;
+            let room = hub
+                .add_home(Home::new("home"))
+                .unwrap()
+                .add_room(Room::new("room"))
+                .unwrap();
+            for name in ["first", "second", "third"] {
+                room.add_device(Hanging(name)).unwrap();
+            }
+        }
+        let config = HealthConfig {
+            probe_timeout: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        // the probes run concurrently, so the check takes one timeout
+        time::timeout(Duration::from_millis(250), check_devices(&hub, &config))
+            .await
+            .expect("hanging devices are probed concurrently");
+
+        let hub = hub.lock().await;
+        for device in hub
+            .get_home("home")
+            .unwrap()
+            .room("room")
+            .unwrap()
+            .device_iter()
+        {
+            let health = device.health();
+            assert_eq!(health.status, HealthStatus::Degraded);
+            assert_eq!(
+                health.last_error.as_deref(),
+                Some("Device is unreachable: no answer in 0.1 s")
+            );
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use std::collections::{hash_map::Entry, HashMap};
+use indexmap::{map::Entry, IndexMap};
 
 use crate::{
+    aggregate,
     error::{Error, Result},
     room::Room,
+    Device, DeviceGroup, GroupMember, Id, Location, Metadata,
 };
 
 /// Entry point for smart home control - Home
@@ -20,12 +22,19 @@ use crate::{
 /// home.del_room("Room 1").unwrap();
 /// assert_eq!(home.room_iter().count(), 0);
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Home {
+    /// Stable identifier of the Home
+    id: Id,
     /// Name of the Home
     name: String,
-    /// List of rooms in the current home
-    rooms: HashMap<String, Room>,
+    /// Tags and properties of the Home
+    metadata: Metadata,
+    /// List of rooms in the current home (in the user-defined order), boxed so the room address
+    /// stays the same when other rooms are removed or reordered
+    rooms: IndexMap<String, Box<Room>>,
+    /// Device groups of the home
+    groups: IndexMap<String, DeviceGroup>,
 }
 
 impl Home {
@@ -35,56 +44,427 @@ impl Home {
         T: Into<String>,
     {
         Self {
+            id: Id::new_v4(),
             name: name.into(),
+            metadata: Default::default(),
             rooms: Default::default(),
+            groups: Default::default(),
         }
     }
 
+    /// Use the id instead of the generated one, e.g. to restore the home from a storage
+    pub fn with_id(mut self, id: Id) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Gets a home id
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
     /// Gets a home name
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Sets home name
+    pub(crate) fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Gets home tags and properties
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Gets mutable home tags and properties
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
     /// Add room to the Home
     pub fn add_room(&mut self, room: Room) -> Result<&mut Room> {
         match self.rooms.entry(room.name().to_string()) {
-            Entry::Occupied(_) => Err(Error::RoomAlreadyExists(room)),
-            Entry::Vacant(entry) => Ok(entry.insert(room)),
+            Entry::Occupied(_) => Err(Error::RoomAlreadyExists(Box::new(room))),
+            Entry::Vacant(entry) => Ok(entry.insert(Box::new(room))),
         }
     }
 
-    /// Del room from the Home
+    /// Del room from the Home, its devices leave their groups
     pub fn del_room(&mut self, name: &str) -> Option<Room> {
-        self.rooms.remove(name)
+        let room = self.rooms.shift_remove(name)?;
+
+        for group in self.groups.values_mut() {
+            group.retain_members(|member| member.room != name);
+        }
+
+        Some(*room)
+    }
+
+    /// Rename room in the Home, devices, position and group members stay the same
+    pub fn rename_room(&mut self, name: &str, new_name: &str) -> Result<&mut Room> {
+        if new_name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+
+        if name != new_name && self.rooms.contains_key(new_name) {
+            return Err(Error::NameAlreadyTaken(new_name.to_string()));
+        }
+
+        let (index, _, mut room) = self
+            .rooms
+            .shift_remove_full(name)
+            .ok_or(Error::RoomNotFound)?;
+        room.set_name(new_name);
+        self.rooms.shift_insert(index, new_name.to_string(), room);
+
+        for group in self.groups.values_mut() {
+            group.update_members(|member| {
+                if member.room == name {
+                    member.room = new_name.to_string();
+                }
+            });
+        }
+
+        Ok(&mut self.rooms[index])
+    }
+
+    /// Rename device in the room of the Home, the device stays in its groups
+    pub fn rename_device(&mut self, room: &str, name: &str, new_name: &str) -> Result<&mut Device> {
+        let device = self
+            .rooms
+            .get_mut(room)
+            .ok_or(Error::RoomNotFound)?
+            .rename_device(name, new_name)?;
+        let renamed = GroupMember::new(room, name);
+
+        for group in self.groups.values_mut() {
+            group.update_members(|member| {
+                if *member == renamed {
+                    member.device = new_name.to_string();
+                }
+            });
+        }
+
+        Ok(device)
+    }
+
+    /// Move room to the position in the Home
+    pub fn set_room_position(&mut self, name: &str, position: usize) -> Result<()> {
+        let index = self.rooms.get_index_of(name).ok_or(Error::RoomNotFound)?;
+
+        if position >= self.rooms.len() {
+            return Err(Error::InvalidPosition);
+        }
+
+        self.rooms.move_index(index, position);
+
+        Ok(())
+    }
+
+    /// Move device to another room of the Home, the device keeps its connection and groups
+    pub fn move_device(&mut self, room: &str, device: &str, to_room: &str) -> Result<&mut Device> {
+        let source = self.rooms.get(room).ok_or(Error::RoomNotFound)?;
+        source.device(device).ok_or(Error::DeviceNotFound)?;
+
+        let target = self.rooms.get(to_room).ok_or(Error::RoomNotFound)?;
+
+        if room == to_room {
+            return self
+                .rooms
+                .get_mut(room)
+                .and_then(|room| room.device_mut(device))
+                .ok_or(Error::Internal);
+        }
+
+        if target.device(device).is_some() {
+            return Err(Error::NameAlreadyTaken(device.to_string()));
+        }
+
+        let from = GroupMember::new(room, device);
+
+        for group in self.groups.values_mut() {
+            group.update_members(|member| {
+                if *member == from {
+                    member.room = to_room.to_string();
+                }
+            });
+        }
+
+        let moved = self
+            .rooms
+            .get_mut(room)
+            .and_then(|room| room.del_device(device))
+            .ok_or(Error::Internal)?;
+
+        self.rooms
+            .get_mut(to_room)
+            .ok_or(Error::RoomNotFound)?
+            .add_device(moved)
+            .map_err(|_| Error::Internal)
     }
 
     /// Get room by name
     pub fn room(&self, name: &str) -> Option<&Room> {
-        self.rooms.get(name)
+        self.rooms.get(name).map(|room| &**room)
     }
 
     /// Get mutable room by name
     pub fn room_mut(&mut self, name: &str) -> Option<&mut Room> {
-        self.rooms.get_mut(name)
+        self.rooms.get_mut(name).map(|room| &mut **room)
     }
 
-    /// Get iterator over rooms
+    /// Get room by id
+    pub fn room_by_id(&self, id: Id) -> Option<&Room> {
+        self.room_iter().find(|room| room.id() == id)
+    }
+
+    /// Get mutable room by id
+    pub fn room_by_id_mut(&mut self, id: Id) -> Option<&mut Room> {
+        self.room_iter_mut().find(|room| room.id() == id)
+    }
+
+    /// Get iterator over rooms in the user-defined order
     pub fn room_iter(&self) -> impl Iterator<Item = &Room> {
-        self.rooms.iter().map(|(_, room)| room)
+        self.rooms.values().map(|room| &**room)
     }
 
-    /// Get mutable iterator over rooms
+    /// Get mutable iterator over rooms in the user-defined order
     pub fn room_iter_mut(&mut self) -> impl Iterator<Item = &mut Room> {
-        self.rooms.iter_mut().map(|(_, room)| room)
+        self.rooms.values_mut().map(|room| &mut **room)
+    }
+
+    /// Get floors of the placed rooms in the room order
+    pub fn floors(&self) -> Vec<&str> {
+        let mut floors = Vec::new();
+
+        for location in self.room_iter().filter_map(|room| room.location()) {
+            if !floors.contains(&location.floor.as_str()) {
+                floors.push(location.floor.as_str());
+            }
+        }
+
+        floors
+    }
+
+    /// Get zones of the floor in the room order
+    pub fn zones(&self, floor: &str) -> Vec<&str> {
+        let mut zones = Vec::new();
+
+        for location in self.room_iter().filter_map(|room| room.location()) {
+            if let (true, Some(zone)) = (location.floor == floor, location.zone.as_deref()) {
+                if !zones.contains(&zone) {
+                    zones.push(zone);
+                }
+            }
+        }
+
+        zones
+    }
+
+    /// Get rooms inside the location
+    pub fn rooms_in<'a>(&'a self, location: &'a Location) -> impl Iterator<Item = &'a Room> + 'a {
+        self.room_iter().filter(move |room| {
+            room.location()
+                .is_some_and(|room_location| location.contains(room_location))
+        })
+    }
+
+    /// Average temperature inside the location, None if no device reports it
+    pub async fn average_temperature(&self, location: &Location) -> Option<f64> {
+        aggregate::average_temperature(self.rooms_in(location).flat_map(|room| room.device_iter()))
+            .await
+    }
+
+    /// Total power of the devices inside the location
+    pub async fn total_power(&self, location: &Location) -> f64 {
+        aggregate::total_power(self.rooms_in(location).flat_map(|room| room.device_iter())).await
+    }
+
+    /// Add device group to the Home
+    pub fn add_group(&mut self, group: DeviceGroup) -> Result<&mut DeviceGroup> {
+        match self.groups.entry(group.name().to_string()) {
+            Entry::Occupied(_) => Err(Error::GroupAlreadyExists(Box::new(group))),
+            Entry::Vacant(entry) => Ok(entry.insert(group)),
+        }
+    }
+
+    /// Del device group from the Home, devices stay in their rooms
+    pub fn del_group(&mut self, name: &str) -> Option<DeviceGroup> {
+        self.groups.shift_remove(name)
+    }
+
+    /// Get device group by name
+    pub fn group(&self, name: &str) -> Option<&DeviceGroup> {
+        self.groups.get(name)
+    }
+
+    /// Get iterator over device groups
+    pub fn group_iter(&self) -> impl Iterator<Item = &DeviceGroup> {
+        self.groups.values()
+    }
+
+    /// Add existing device of the Home to the group
+    pub fn add_group_member(&mut self, group: &str, member: GroupMember) -> Result<()> {
+        self.room(&member.room)
+            .ok_or(Error::RoomNotFound)?
+            .device(&member.device)
+            .ok_or(Error::DeviceNotFound)?;
+
+        let group = self.groups.get_mut(group).ok_or(Error::GroupNotFound)?;
+
+        match group.add_member(member) {
+            true => Ok(()),
+            false => Err(Error::GroupMemberAlreadyExists),
+        }
+    }
+
+    /// Del device from the room, the device leaves its groups
+    pub fn del_device(&mut self, room: &str, device: &str) -> Option<Device> {
+        let deleted = self.rooms.get_mut(room)?.del_device(device)?;
+        let member = GroupMember::new(room, device);
+
+        for group in self.groups.values_mut() {
+            group.remove_member(&member);
+        }
+
+        Some(deleted)
+    }
+
+    /// Remove device from the group, the device stays in its room
+    pub fn del_group_member(&mut self, group: &str, member: &GroupMember) -> Result<()> {
+        let group = self.groups.get_mut(group).ok_or(Error::GroupNotFound)?;
+
+        match group.remove_member(member) {
+            true => Ok(()),
+            false => Err(Error::GroupMemberNotFound),
+        }
+    }
+
+    /// Get devices of the group, members with invalid paths are skipped
+    pub fn group_devices(&self, group: &str) -> Result<impl Iterator<Item = &Device>> {
+        let group = self.groups.get(group).ok_or(Error::GroupNotFound)?;
+
+        Ok(group.members().filter_map(|member| {
+            self.room(&member.room)
+                .and_then(|room| room.device(&member.device))
+        }))
+    }
+
+    /// Turn on all switchable devices of the group
+    ///
+    /// All devices are tried, the first error is returned.
+    pub async fn group_on(&self, group: &str) -> Result<()> {
+        aggregate::switch(self.group_devices(group)?, true).await
+    }
+
+    /// Turn off all switchable devices of the group
+    ///
+    /// All devices are tried, the first error is returned.
+    pub async fn group_off(&self, group: &str) -> Result<()> {
+        aggregate::switch(self.group_devices(group)?, false).await
+    }
+
+    /// Average temperature of the group, None if no device reports it
+    pub async fn group_average_temperature(&self, group: &str) -> Result<Option<f64>> {
+        Ok(aggregate::average_temperature(self.group_devices(group)?).await)
+    }
+
+    /// Total power of the group devices
+    pub async fn group_total_power(&self, group: &str) -> Result<f64> {
+        Ok(aggregate::total_power(self.group_devices(group)?).await)
+    }
+
+    /// Get rooms with the tag
+    pub fn rooms_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Room> + 'a {
+        self.room_iter()
+            .filter(move |room| room.metadata().has_tag(tag))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use async_trait::async_trait;
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::room::Room;
+    use crate::{room::Room, PowerMeter, SmartDevice, SmartSocket, Switchable, TemperatureSensor};
+
+    /// Device with fixed measurements
+    #[derive(Debug)]
+    struct Meter {
+        name: String,
+        temperature: f64,
+        power: f64,
+        enabled: AtomicBool,
+    }
+
+    impl Meter {
+        fn new(name: &str, temperature: f64, power: f64) -> Self {
+            Self {
+                name: name.to_string(),
+                temperature,
+                power,
+                enabled: AtomicBool::new(false),
+            }
+        }
+    }
+
+    impl SmartDevice for Meter {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "meter"
+        }
+
+        fn device_type(&self) -> &str {
+            "meter"
+        }
+
+        fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+            Some(self)
+        }
+
+        fn as_power_meter(&self) -> Option<&dyn PowerMeter> {
+            Some(self)
+        }
+
+        fn as_switchable(&self) -> Option<&dyn Switchable> {
+            Some(self)
+        }
+    }
+
+    #[async_trait]
+    impl Switchable for Meter {
+        async fn on(&self) -> Result<()> {
+            self.enabled.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn off(&self) -> Result<()> {
+            self.enabled.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl TemperatureSensor for Meter {
+        async fn current_temperature(&self) -> Result<f64> {
+            Ok(self.temperature)
+        }
+    }
+
+    #[async_trait]
+    impl PowerMeter for Meter {
+        async fn current_power(&self) -> Result<f64> {
+            Ok(self.power)
+        }
+    }
 
     #[test]
     fn example() {
@@ -109,6 +489,252 @@ mod tests {
         assert_eq!(home.room_iter_mut().count(), 0);
     }
 
+    #[tokio::test]
+    async fn rename_and_move() {
+        let mut home = Home::new("home");
+        let bedroom = home.add_room(Room::new("bedroom")).unwrap();
+        bedroom
+            .add_device(SmartSocket::new("heater", "", "http://127.0.0.1:1").await)
+            .unwrap();
+        home.add_room(Room::new("kitchen")).unwrap();
+
+        assert!(matches!(
+            home.rename_room("bedroom", "kitchen"),
+            Err(Error::NameAlreadyTaken(_))
+        ));
+        assert!(matches!(
+            home.rename_room("bedroom", ""),
+            Err(Error::EmptyName)
+        ));
+        let room = home.rename_room("bedroom", "living room").unwrap();
+        assert_eq!(room.name(), "living room");
+        assert_eq!(room.device_iter().count(), 1);
+        assert_eq!(home.room("bedroom"), None);
+
+        let device = home
+            .move_device("living room", "heater", "kitchen")
+            .unwrap();
+        assert_eq!(device.name(), "heater");
+        assert_eq!(home.room("living room").unwrap().device_iter().count(), 0);
+        assert!(home.room("kitchen").unwrap().device("heater").is_some());
+
+        assert!(matches!(
+            home.move_device("kitchen", "heater", "garage"),
+            Err(Error::RoomNotFound)
+        ));
+        assert!(matches!(
+            home.move_device("living room", "heater", "kitchen"),
+            Err(Error::DeviceNotFound)
+        ));
+        assert!(home.room("kitchen").unwrap().device("heater").is_some());
+    }
+
+    #[test]
+    fn ordering() {
+        let names = |home: &Home| -> Vec<String> {
+            home.room_iter()
+                .map(|room| room.name().to_string())
+                .collect()
+        };
+        let mut home = Home::new("home");
+
+        for room in ["kitchen", "bedroom", "attic", "garage"] {
+            home.add_room(Room::new(room)).unwrap();
+        }
+        assert_eq!(names(&home), ["kitchen", "bedroom", "attic", "garage"]);
+
+        home.rename_room("bedroom", "living room").unwrap();
+        home.del_room("attic");
+        assert_eq!(names(&home), ["kitchen", "living room", "garage"]);
+
+        home.set_room_position("garage", 0).unwrap();
+        assert_eq!(names(&home), ["garage", "kitchen", "living room"]);
+        home.set_room_position("garage", 2).unwrap();
+        assert_eq!(names(&home), ["kitchen", "living room", "garage"]);
+
+        assert!(matches!(
+            home.set_room_position("garage", 3),
+            Err(Error::InvalidPosition)
+        ));
+        assert!(matches!(
+            home.set_room_position("attic", 0),
+            Err(Error::RoomNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn zones() {
+        let mut home = Home::new("office");
+
+        for (room, location, temperature, power) in [
+            ("hall", Location::floor("1"), 16.0, 100.0),
+            ("lab", Location::zone("1", "east wing"), 20.0, 500.0),
+            ("server", Location::zone("1", "east wing"), 24.0, 1500.0),
+            ("kitchen", Location::zone("2", "west wing"), 22.0, 2000.0),
+        ] {
+            let room = home.add_room(Room::new(room)).unwrap();
+            room.set_location(Some(location));
+            room.add_device(Meter::new("meter", temperature, power))
+                .unwrap();
+        }
+        home.add_room(Room::new("storage")).unwrap();
+
+        assert_eq!(home.floors(), ["1", "2"]);
+        assert_eq!(home.zones("1"), ["east wing"]);
+        assert_eq!(home.zones("3"), Vec::<&str>::new());
+
+        let floor = Location::floor("1");
+        assert_eq!(
+            home.rooms_in(&floor)
+                .map(|room| room.name())
+                .collect::<Vec<_>>(),
+            ["hall", "lab", "server"]
+        );
+        assert_eq!(home.average_temperature(&floor).await, Some(20.0));
+        assert_eq!(home.total_power(&floor).await, 2100.0);
+
+        let wing = Location::zone("1", "east wing");
+        assert_eq!(home.average_temperature(&wing).await, Some(22.0));
+        assert_eq!(home.total_power(&wing).await, 2000.0);
+
+        let empty = Location::floor("3");
+        assert_eq!(home.rooms_in(&empty).count(), 0);
+        assert_eq!(home.average_temperature(&empty).await, None);
+        assert_eq!(home.total_power(&empty).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn groups() {
+        let enabled = |home: &Home, room: &str| {
+            home.room(room)
+                .unwrap()
+                .device("heater")
+                .unwrap()
+                .downcast_ref::<Meter>()
+                .unwrap()
+                .enabled
+                .load(Ordering::SeqCst)
+        };
+        let mut home = Home::new("home");
+
+        for (room, temperature, power) in [("bedroom", 20.0, 1000.0), ("kitchen", 24.0, 1500.0)] {
+            home.add_room(Room::new(room))
+                .unwrap()
+                .add_device(Meter::new("heater", temperature, power))
+                .unwrap();
+        }
+        home.add_group(DeviceGroup::new("heaters")).unwrap();
+
+        assert!(matches!(
+            home.add_group(DeviceGroup::new("heaters")),
+            Err(Error::GroupAlreadyExists(_))
+        ));
+        assert!(matches!(
+            home.add_group_member("heaters", GroupMember::new("garage", "heater")),
+            Err(Error::RoomNotFound)
+        ));
+        assert!(matches!(
+            home.add_group_member("lights", GroupMember::new("bedroom", "heater")),
+            Err(Error::GroupNotFound)
+        ));
+
+        for room in ["bedroom", "kitchen"] {
+            home.add_group_member("heaters", GroupMember::new(room, "heater"))
+                .unwrap();
+        }
+        assert!(matches!(
+            home.add_group_member("heaters", GroupMember::new("bedroom", "heater")),
+            Err(Error::GroupMemberAlreadyExists)
+        ));
+
+        home.group_on("heaters").await.unwrap();
+        assert!(enabled(&home, "bedroom") && enabled(&home, "kitchen"));
+        home.group_off("heaters").await.unwrap();
+        assert!(!enabled(&home, "bedroom") && !enabled(&home, "kitchen"));
+
+        assert_eq!(
+            home.group_average_temperature("heaters").await.unwrap(),
+            Some(22.0)
+        );
+        assert_eq!(home.group_total_power("heaters").await.unwrap(), 2500.0);
+
+        // device removed behind the Home is skipped
+        home.room_mut("kitchen").unwrap().del_device("heater");
+        assert_eq!(home.group_devices("heaters").unwrap().count(), 1);
+        assert_eq!(home.group_total_power("heaters").await.unwrap(), 1000.0);
+
+        home.del_group_member("heaters", &GroupMember::new("bedroom", "heater"))
+            .unwrap();
+        assert!(matches!(
+            home.del_group_member("heaters", &GroupMember::new("bedroom", "heater")),
+            Err(Error::GroupMemberNotFound)
+        ));
+        assert!(home.del_group("heaters").is_some());
+        assert!(home.group("heaters").is_none());
+    }
+
+    #[tokio::test]
+    async fn group_members_follow_devices() {
+        let members = |home: &Home| {
+            home.group("heaters")
+                .unwrap()
+                .members()
+                .map(|member| format!("{}/{}", member.room, member.device))
+                .collect::<Vec<_>>()
+        };
+        let mut home = Home::new("home");
+
+        for room in ["bedroom", "kitchen"] {
+            home.add_room(Room::new(room))
+                .unwrap()
+                .add_device(Meter::new("heater", 20.0, 1000.0))
+                .unwrap();
+        }
+        home.add_group(DeviceGroup::new("heaters")).unwrap();
+        home.add_group_member("heaters", GroupMember::new("bedroom", "heater"))
+            .unwrap();
+
+        home.rename_room("bedroom", "nursery").unwrap();
+        assert_eq!(members(&home), ["nursery/heater"]);
+
+        home.rename_device("nursery", "heater", "radiator").unwrap();
+        assert_eq!(members(&home), ["nursery/radiator"]);
+        assert!(matches!(
+            home.rename_device("attic", "heater", "radiator"),
+            Err(Error::RoomNotFound)
+        ));
+
+        home.move_device("nursery", "radiator", "kitchen").unwrap();
+        assert_eq!(members(&home), ["kitchen/radiator"]);
+        assert_eq!(home.group_devices("heaters").unwrap().count(), 1);
+
+        // the stale path of the removed device merges with the renamed one
+        home.add_group_member("heaters", GroupMember::new("kitchen", "heater"))
+            .unwrap();
+        home.room_mut("kitchen").unwrap().del_device("heater");
+        home.rename_device("kitchen", "radiator", "heater").unwrap();
+        assert_eq!(members(&home), ["kitchen/heater"]);
+
+        // a new device with the name of the deleted one is not in the group
+        assert!(home.del_device("kitchen", "heater").is_some());
+        assert!(home.del_device("kitchen", "heater").is_none());
+        assert!(home.del_device("attic", "heater").is_none());
+        home.room_mut("kitchen")
+            .unwrap()
+            .add_device(Meter::new("heater", 20.0, 1000.0))
+            .unwrap();
+        assert_eq!(members(&home), Vec::<String>::new());
+
+        home.add_group_member("heaters", GroupMember::new("kitchen", "heater"))
+            .unwrap();
+        home.del_room("kitchen").unwrap();
+        home.add_room(Room::new("kitchen"))
+            .unwrap()
+            .add_device(Meter::new("heater", 20.0, 1000.0))
+            .unwrap();
+        assert_eq!(members(&home), Vec::<String>::new());
+    }
+
     #[test]
     fn init() {
         let home = Home::new("home");
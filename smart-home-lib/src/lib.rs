@@ -1,29 +1,51 @@
 //! This is a prototype library for Smart Home Control
 
+pub mod aggregate;
 mod device;
 pub mod error;
+mod group;
+pub mod health;
 mod home;
+mod location;
+mod metadata;
+mod query;
 mod room;
 
 type Name = String;
 
-use std::collections::{
-    hash_map::Entry::{Occupied, Vacant},
-    HashMap,
-};
+/// Stable identifier of the home, room or device
+pub type Id = uuid::Uuid;
 
+// use self::device::hardcoded_devices::{ExampleSocket, ExampleThermometer};
+pub use async_trait::async_trait;
 use error::{Error, Result};
+use indexmap::{
+    map::Entry::{Occupied, Vacant},
+    IndexMap,
+};
 
-// use self::device::hardcoded_devices::{ExampleSocket, ExampleThermometer};
 pub use crate::{
-    device::{Device, SmartDevice, SmartSocket, SmartThermometer},
+    device::{
+        BinarySensorEvent, BinarySensorKind, BinaryState, Capability, Device, HumiditySensor,
+        Measurements, PowerMeter, PressureSensor, SmartBinarySensor, SmartDevice, SmartMultiSensor,
+        SmartSocket, SmartThermometer, SmartThermostat, SocketSecurity, SocketTls, Switchable,
+        TemperatureControl, TemperatureSensor, ThermostatMode,
+    },
+    group::{DeviceGroup, GroupMember},
+    health::{Health, HealthChecker, HealthConfig, HealthProbe, HealthStatus},
     home::Home,
+    location::Location,
+    metadata::Metadata,
+    query::{DevicePath, DeviceQuery},
     room::Room,
 };
 
-#[derive(Default)]
+/// Homes of the user, clones share the device implementations like [`Device`] clones do
+#[derive(Default, Clone)]
 pub struct SmartHub {
-    home_list: HashMap<Name, Home>,
+    /// Homes in the user-defined order, boxed so the home address stays the same when other homes
+    /// are removed or reordered
+    home_list: IndexMap<Name, Box<Home>>,
 }
 
 impl SmartHub {
@@ -39,24 +61,471 @@ impl SmartHub {
 
     pub fn add_home(&mut self, home: Home) -> Result<&mut Home> {
         match self.home_list.entry(home.name().to_string()) {
-            Occupied(_) => Err(Error::HomeAlreadyExists(home)),
-            Vacant(entry) => Ok(entry.insert(home)),
+            Occupied(_) => Err(Error::HomeAlreadyExists(Box::new(home))),
+            Vacant(entry) => Ok(entry.insert(Box::new(home))),
         }
     }
 
     pub fn del_home(&mut self, name: &str) -> Option<Home> {
-        self.home_list.remove(name)
+        self.home_list.shift_remove(name).map(|home| *home)
+    }
+
+    /// Rename home, rooms, devices and position stay the same
+    pub fn rename_home(&mut self, name: &str, new_name: &str) -> Result<&mut Home> {
+        if new_name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+
+        if name != new_name && self.home_list.contains_key(new_name) {
+            return Err(Error::NameAlreadyTaken(new_name.to_string()));
+        }
+
+        let (index, _, mut home) = self
+            .home_list
+            .shift_remove_full(name)
+            .ok_or(Error::HomeNotFound)?;
+        home.set_name(new_name);
+        self.home_list
+            .shift_insert(index, new_name.to_string(), home);
+
+        Ok(&mut self.home_list[index])
+    }
+
+    /// Move home to the position in the hub
+    pub fn set_home_position(&mut self, name: &str, position: usize) -> Result<()> {
+        let index = self
+            .home_list
+            .get_index_of(name)
+            .ok_or(Error::HomeNotFound)?;
+
+        if position >= self.home_list.len() {
+            return Err(Error::InvalidPosition);
+        }
+
+        self.home_list.move_index(index, position);
+
+        Ok(())
+    }
+
+    /// Move device to another room (possibly in another home), the device keeps its connection
+    ///
+    /// The device stays in its groups inside the home and leaves them when it moves to another one.
+    pub fn move_device(
+        &mut self,
+        (home, room, device): (&str, &str, &str),
+        (to_home, to_room): (&str, &str),
+    ) -> Result<&mut Device> {
+        if home == to_home {
+            return self
+                .get_home_mut(home)
+                .ok_or(Error::HomeNotFound)?
+                .move_device(room, device, to_room);
+        }
+
+        self.get_home(home)
+            .ok_or(Error::HomeNotFound)?
+            .room(room)
+            .ok_or(Error::RoomNotFound)?
+            .device(device)
+            .ok_or(Error::DeviceNotFound)?;
+
+        let target = self
+            .get_home(to_home)
+            .ok_or(Error::HomeNotFound)?
+            .room(to_room)
+            .ok_or(Error::RoomNotFound)?;
+
+        if target.device(device).is_some() {
+            return Err(Error::NameAlreadyTaken(device.to_string()));
+        }
+
+        let moved = self
+            .get_home_mut(home)
+            .and_then(|home| home.del_device(room, device))
+            .ok_or(Error::Internal)?;
+
+        self.get_home_mut(to_home)
+            .and_then(|home| home.room_mut(to_room))
+            .ok_or(Error::Internal)?
+            .add_device(moved)
+            .map_err(|_| Error::Internal)
     }
 
     pub fn get_home(&self, name: &str) -> Option<&Home> {
-        self.home_list.get(name)
+        self.home_list.get(name).map(|home| &**home)
     }
 
     pub fn get_home_mut(&mut self, name: &str) -> Option<&mut Home> {
-        self.home_list.get_mut(name)
+        self.home_list.get_mut(name).map(|home| &mut **home)
     }
 
+    /// Get iterator over homes in the user-defined order
     pub fn iter(&self) -> impl Iterator<Item = &Home> {
-        self.home_list.values()
+        self.home_list.values().map(|home| &**home)
+    }
+
+    /// Get homes with the tag
+    pub fn homes_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Home> + 'a {
+        self.iter().filter(move |home| home.metadata().has_tag(tag))
+    }
+
+    /// Get home by id
+    pub fn home_by_id(&self, id: Id) -> Option<&Home> {
+        self.iter().find(|home| home.id() == id)
+    }
+
+    /// Get mutable home by id
+    pub fn home_by_id_mut(&mut self, id: Id) -> Option<&mut Home> {
+        self.home_list
+            .values_mut()
+            .map(|home| &mut **home)
+            .find(|home| home.id() == id)
+    }
+
+    /// Get room by id with its home
+    pub fn room_by_id(&self, id: Id) -> Option<(&Home, &Room)> {
+        self.iter()
+            .find_map(|home| home.room_by_id(id).map(|room| (home, room)))
+    }
+
+    /// Get device by id with its home and room
+    pub fn device_by_id(&self, id: Id) -> Option<DevicePath<'_>> {
+        self.iter().find_map(|home| {
+            home.room_iter().find_map(|room| {
+                room.device_by_id(id)
+                    .map(|device| DevicePath { home, room, device })
+            })
+        })
+    }
+
+    /// Find devices matching the query across all homes
+    pub fn find_devices<'a>(
+        &'a self,
+        query: &'a DeviceQuery,
+    ) -> impl Iterator<Item = DevicePath<'a>> + 'a {
+        self.iter().flat_map(move |home| {
+            home.room_iter().flat_map(move |room| {
+                room.device_iter()
+                    .filter(move |device| query.matches(device))
+                    .map(move |device| DevicePath { home, room, device })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn find_devices() {
+        let mut hub = SmartHub::new();
+
+        for home_name in ["home 1", "home 2"] {
+            let home = hub.add_home(Home::new(home_name)).unwrap();
+            let room = home.add_room(Room::new("bedroom")).unwrap();
+            room.add_device(SmartSocket::new("bedroom heater", "", "http://127.0.0.1:1").await)
+                .unwrap();
+            room.add_device(SmartThermometer::new("bedroom thermometer", "", "0.0.0.0:85").await)
+                .unwrap();
+        }
+
+        assert_eq!(hub.find_devices(&DeviceQuery::new()).count(), 4);
+
+        let query = DeviceQuery::new().with_capability(Capability::Power);
+        let mut found: Vec<_> = hub
+            .find_devices(&query)
+            .map(|path| (path.home.name(), path.room.name(), path.device.name()))
+            .collect();
+        found.sort_unstable();
+        assert_eq!(
+            found,
+            vec![
+                ("home 1", "bedroom", "bedroom heater"),
+                ("home 2", "bedroom", "bedroom heater"),
+            ]
+        );
+
+        let query = DeviceQuery::new().with_device_type("thermometer");
+        assert_eq!(hub.find_devices(&query).count(), 2);
+
+        let query = DeviceQuery::new().with_name("*thermo*");
+        assert_eq!(hub.find_devices(&query).count(), 2);
+
+        let query = DeviceQuery::new()
+            .with_name("*thermo*")
+            .with_capability(Capability::Switchable);
+        assert_eq!(hub.find_devices(&query).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn tags() {
+        let mut hub = SmartHub::new();
+        hub.add_home(Home::new("home"))
+            .unwrap()
+            .metadata_mut()
+            .add_tag("main");
+        hub.add_home(Home::new("cottage")).unwrap();
+
+        let home = hub.get_home_mut("home").unwrap();
+        let room = home.add_room(Room::new("basement")).unwrap();
+        room.metadata_mut().add_tag("critical");
+        room.metadata_mut().set("floor", "-1");
+        room.add_device(SmartSocket::new("pump", "", "http://127.0.0.1:1").await)
+            .unwrap()
+            .metadata_mut()
+            .add_tag("critical");
+        room.add_device(SmartSocket::new("lamp", "", "http://127.0.0.1:1").await)
+            .unwrap();
+        home.add_room(Room::new("kitchen")).unwrap();
+
+        assert_eq!(
+            hub.homes_with_tag("main")
+                .map(|home| home.name())
+                .collect::<Vec<_>>(),
+            ["home"]
+        );
+
+        let home = hub.get_home("home").unwrap();
+        assert_eq!(
+            home.rooms_with_tag("critical")
+                .map(|room| room.name())
+                .collect::<Vec<_>>(),
+            ["basement"]
+        );
+
+        let room = home.room("basement").unwrap();
+        assert_eq!(room.metadata().get("floor"), Some("-1"));
+        assert_eq!(
+            room.devices_with_tag("critical")
+                .map(|device| device.name())
+                .collect::<Vec<_>>(),
+            ["pump"]
+        );
+
+        let query = DeviceQuery::new().with_tag("critical");
+        assert_eq!(
+            hub.find_devices(&query)
+                .map(|path| path.device.name())
+                .collect::<Vec<_>>(),
+            ["pump"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rename_and_move() {
+        let mut hub = SmartHub::new();
+        hub.add_home(Home::new("home 1"))
+            .unwrap()
+            .add_room(Room::new("bedroom"))
+            .unwrap()
+            .add_device(SmartSocket::new("heater", "", "http://127.0.0.1:1").await)
+            .unwrap();
+        hub.add_home(Home::new("home 2"))
+            .unwrap()
+            .add_room(Room::new("garage"))
+            .unwrap();
+
+        assert!(matches!(
+            hub.rename_home("home 1", "home 2"),
+            Err(Error::NameAlreadyTaken(_))
+        ));
+        assert!(matches!(
+            hub.rename_home("home 1", ""),
+            Err(Error::EmptyName)
+        ));
+        assert!(matches!(
+            hub.rename_home("NOT FOUND", "home 3"),
+            Err(Error::HomeNotFound)
+        ));
+        assert_eq!(
+            hub.rename_home("home 1", "cottage").unwrap().name(),
+            "cottage"
+        );
+        assert!(hub.get_home("home 1").is_none());
+
+        let cottage = hub.get_home_mut("cottage").unwrap();
+        cottage.add_group(DeviceGroup::new("heaters")).unwrap();
+        cottage
+            .add_group_member("heaters", GroupMember::new("bedroom", "heater"))
+            .unwrap();
+
+        hub.move_device(("cottage", "bedroom", "heater"), ("home 2", "garage"))
+            .unwrap();
+        // the device leaves the groups of the old home
+        assert_eq!(
+            hub.get_home("cottage")
+                .unwrap()
+                .group("heaters")
+                .unwrap()
+                .members()
+                .count(),
+            0
+        );
+        assert_eq!(
+            hub.get_home("cottage")
+                .unwrap()
+                .room("bedroom")
+                .unwrap()
+                .device_iter()
+                .count(),
+            0
+        );
+        assert!(hub
+            .get_home("home 2")
+            .unwrap()
+            .room("garage")
+            .unwrap()
+            .device("heater")
+            .is_some());
+
+        assert!(matches!(
+            hub.move_device(("home 2", "garage", "heater"), ("cottage", "attic")),
+            Err(Error::RoomNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn ordering() {
+        let mut hub = SmartHub::new();
+        for home in ["home 3", "home 1", "home 2"] {
+            hub.add_home(Home::new(home)).unwrap();
+        }
+
+        let room = hub
+            .get_home_mut("home 1")
+            .unwrap()
+            .add_room(Room::new("bedroom"))
+            .unwrap();
+        for device in ["lamp", "heater", "kettle"] {
+            room.add_device(SmartSocket::new(device, "", "http://127.0.0.1:1").await)
+                .unwrap();
+        }
+        room.set_device_position("kettle", 0).unwrap();
+        room.rename_device("heater", "radiator").unwrap();
+        assert!(matches!(
+            room.set_device_position("kettle", 3),
+            Err(Error::InvalidPosition)
+        ));
+        assert_eq!(
+            room.device_iter().map(|d| d.name()).collect::<Vec<_>>(),
+            ["kettle", "lamp", "radiator"]
+        );
+
+        hub.set_home_position("home 3", 2).unwrap();
+        hub.rename_home("home 1", "cottage").unwrap();
+        assert_eq!(
+            hub.iter().map(|home| home.name()).collect::<Vec<_>>(),
+            ["cottage", "home 2", "home 3"]
+        );
+
+        hub.move_device(("cottage", "bedroom", "lamp"), ("cottage", "bedroom"))
+            .unwrap();
+        assert_eq!(
+            hub.get_home("cottage")
+                .unwrap()
+                .room("bedroom")
+                .unwrap()
+                .device_iter()
+                .map(|d| d.name())
+                .collect::<Vec<_>>(),
+            ["kettle", "lamp", "radiator"]
+        );
+    }
+
+    #[tokio::test]
+    async fn health() {
+        let hub = tokio::sync::Mutex::new(SmartHub::new());
+        {
+            let mut hub = hub.lock().await;
+            let room = hub
+                .add_home(Home::new("home"))
+                .unwrap()
+                .add_room(Room::new("bedroom"))
+                .unwrap();
+            room.add_device(SmartSocket::new("heater", "", "http://127.0.0.1:1").await)
+                .unwrap();
+            room.add_device(SmartThermometer::new("thermometer", "", "0.0.0.0:86").await)
+                .unwrap();
+            room.add_device(SmartMultiSensor::new("sensor", "", "0.0.0.0:87").await)
+                .unwrap();
+        }
+
+        let config = HealthConfig::default();
+        health::check_devices(&hub, &config).await;
+
+        let hub = hub.lock().await;
+        let room = hub.get_home("home").unwrap().room("bedroom").unwrap();
+
+        let health = room.device("heater").unwrap().health();
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(health.last_seen, None);
+        assert!(matches!(
+            health.last_error.as_deref(),
+            Some(e) if e.starts_with("Device is unreachable")
+        ));
+
+        let health = room.device("thermometer").unwrap().health();
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(
+            health.last_error.as_deref(),
+            Some("Measurement is not available")
+        );
+
+        let health = room.device("sensor").unwrap().health();
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(
+            health.last_error.as_deref(),
+            Some("Measurement is not available")
+        );
+    }
+
+    #[tokio::test]
+    async fn ids() {
+        let mut hub = SmartHub::new();
+        let home = hub.add_home(Home::new("home")).unwrap();
+        let home_id = home.id();
+        let room = home.add_room(Room::new("bedroom")).unwrap();
+        let room_id = room.id();
+        let device = room
+            .add_device(SmartSocket::new("heater", "", "http://127.0.0.1:1").await)
+            .unwrap();
+        let device_id = device.id();
+
+        assert_ne!(home_id, room_id);
+        assert_ne!(room_id, device_id);
+
+        hub.rename_home("home", "cottage").unwrap();
+        hub.get_home_mut("cottage")
+            .unwrap()
+            .rename_room("bedroom", "kitchen")
+            .unwrap()
+            .rename_device("heater", "kettle")
+            .unwrap();
+
+        assert_eq!(hub.home_by_id(home_id).unwrap().name(), "cottage");
+        assert_eq!(hub.home_by_id_mut(home_id).unwrap().name(), "cottage");
+
+        let (home, room) = hub.room_by_id(room_id).unwrap();
+        assert_eq!((home.name(), room.name()), ("cottage", "kitchen"));
+
+        let path = hub.device_by_id(device_id).unwrap();
+        assert_eq!(
+            (path.home.name(), path.room.name(), path.device.name()),
+            ("cottage", "kitchen", "kettle")
+        );
+
+        assert!(hub.home_by_id(device_id).is_none());
+        assert!(hub.room_by_id(home_id).is_none());
+        assert!(hub.device_by_id(room_id).is_none());
+
+        let restored = Home::new("restored").with_id(home_id);
+        assert_eq!(restored.id(), home_id);
+        assert_eq!(Room::new("restored").with_id(room_id).id(), room_id);
+        let restored = Device::new(SmartSocket::new("restored", "", "http://127.0.0.1:1").await)
+            .with_id(device_id);
+        assert_eq!(restored.id(), device_id);
     }
 }
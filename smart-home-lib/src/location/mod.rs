@@ -0,0 +1,44 @@
+/// Place in the home hierarchy (floor → zone → room)
+/// ```
+/// use smart_home_lib::Location;
+///
+/// let floor = Location::floor("1");
+/// let wing = Location::zone("1", "east wing");
+///
+/// assert!(floor.contains(&wing));
+/// assert!(!wing.contains(&floor));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// Floor name
+    pub floor: String,
+    /// Zone name on the floor, the whole floor if missing
+    pub zone: Option<String>,
+}
+
+impl Location {
+    /// Construct a location of the whole floor
+    pub fn floor(floor: impl Into<String>) -> Self {
+        Self {
+            floor: floor.into(),
+            zone: None,
+        }
+    }
+
+    /// Construct a location of the zone on the floor
+    pub fn zone(floor: impl Into<String>, zone: impl Into<String>) -> Self {
+        Self {
+            floor: floor.into(),
+            zone: Some(zone.into()),
+        }
+    }
+
+    /// Check whether the other location is inside this one
+    pub fn contains(&self, other: &Location) -> bool {
+        self.floor == other.floor
+            && self
+                .zone
+                .as_deref()
+                .is_none_or(|zone| other.zone.as_deref() == Some(zone))
+    }
+}
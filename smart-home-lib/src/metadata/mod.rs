@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// User-defined tags and key/value properties of a home, room or device
+/// ```
+/// use smart_home_lib::Metadata;
+///
+/// let mut metadata = Metadata::new();
+/// metadata.add_tag("critical");
+/// metadata.set("floor", "2");
+///
+/// assert!(metadata.has_tag("critical"));
+/// assert_eq!(metadata.get("floor"), Some("2"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    tags: BTreeSet<String>,
+    properties: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Construct empty metadata
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the tag is set
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    /// Add tag, returns false if the tag is already set
+    pub fn add_tag(&mut self, tag: impl Into<String>) -> bool {
+        self.tags.insert(tag.into())
+    }
+
+    /// Remove tag, returns false if the tag is not set
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        self.tags.remove(tag)
+    }
+
+    /// Replace all tags
+    pub fn set_tags<I, T>(&mut self, tags: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+    }
+
+    /// Get iterator over tags in the alphabetical order
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    /// Get property value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// Set property value, returns the previous value
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.properties.insert(key.into(), value.into())
+    }
+
+    /// Remove property, returns the previous value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.properties.remove(key)
+    }
+
+    /// Replace all properties
+    pub fn set_properties<I, K, V>(&mut self, properties: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.properties = properties
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+    }
+
+    /// Get iterator over properties in the alphabetical order of keys
+    pub fn properties(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn tags_and_properties() {
+        let mut metadata = Metadata::new();
+        assert!(!metadata.has_tag("critical"));
+        assert_eq!(metadata.get("floor"), None);
+
+        assert!(metadata.add_tag("critical"));
+        assert!(!metadata.add_tag("critical"));
+        metadata.add_tag("basement");
+        assert_eq!(
+            metadata.tags().collect::<Vec<_>>(),
+            ["basement", "critical"]
+        );
+        assert!(metadata.remove_tag("basement"));
+        assert!(!metadata.remove_tag("basement"));
+
+        assert_eq!(metadata.set("floor", "1"), None);
+        assert_eq!(metadata.set("floor", "2"), Some("1".to_string()));
+        metadata.set("area", "north");
+        assert_eq!(
+            metadata.properties().collect::<Vec<_>>(),
+            [("area", "north"), ("floor", "2")]
+        );
+        assert_eq!(metadata.remove("area"), Some("north".to_string()));
+
+        metadata.set_tags(["a", "b"]);
+        metadata.set_properties([("k", "v")]);
+        assert_eq!(metadata.tags().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(metadata.properties().collect::<Vec<_>>(), [("k", "v")]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Capability, Device, Home, Room};
+
+/// Path to the device in the hub
+#[derive(Debug, Clone, Copy)]
+pub struct DevicePath<'a> {
+    /// Home of the device
+    pub home: &'a Home,
+    /// Room of the device
+    pub room: &'a Room,
+    /// Device itself
+    pub device: &'a Device,
+}
+
+/// Device filter, all conditions must match
+/// ```
+/// use smart_home_lib::{Capability, DeviceQuery};
+///
+/// let query = DeviceQuery::new()
+///     .with_capability(Capability::Temperature)
+///     .with_name("bed*");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DeviceQuery {
+    capability: Option<Capability>,
+    device_type: Option<String>,
+    name: Option<String>,
+    tag: Option<String>,
+}
+
+impl DeviceQuery {
+    /// Construct a query which matches all devices
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match devices with the capability
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
+    /// Match devices of the type
+    pub fn with_device_type(mut self, device_type: impl Into<String>) -> Self {
+        self.device_type = Some(device_type.into());
+        self
+    }
+
+    /// Match devices by name pattern (`*` matches any sequence, `?` matches any character)
+    pub fn with_name(mut self, pattern: impl Into<String>) -> Self {
+        self.name = Some(pattern.into());
+        self
+    }
+
+    /// Match devices with the tag
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Check whether device matches the query
+    pub fn matches(&self, device: &Device) -> bool {
+        self.capability
+            .is_none_or(|capability| device.has_capability(capability))
+            && self
+                .device_type
+                .as_deref()
+                .is_none_or(|device_type| device.device_type() == device_type)
+            && self
+                .name
+                .as_deref()
+                .is_none_or(|pattern| glob_match(pattern, device.name()))
+            && self
+                .tag
+                .as_deref()
+                .is_none_or(|tag| device.metadata().has_tag(tag))
+    }
+}
+
+/// Match text against the pattern with `*` and `?` wildcards
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in the pattern and the matched text position
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the last `*` consume one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("socket", "socket"));
+        assert!(!glob_match("socket", "sockets"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("bed*", "bedroom heater"));
+        assert!(glob_match("*heater", "bedroom heater"));
+        assert!(glob_match("*room*", "bedroom heater"));
+        assert!(glob_match("so?ket", "socket"));
+        assert!(!glob_match("so?ket", "soket"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("a*b*c", "aXXbYY"));
+    }
+}
@@ -1,8 +1,14 @@
-use std::collections::{hash_map::Entry, HashMap};
+use indexmap::{map::Entry, IndexMap};
 
 /// Room management
-use crate::device::{Device, SmartDevice, SmartSocket, SmartThermometer};
-use crate::error::{Error, Result};
+use crate::device::{
+    Capability, Device, SmartBinarySensor, SmartDevice, SmartMultiSensor, SmartSocket,
+    SmartThermometer, SmartThermostat,
+};
+use crate::{
+    error::{Error, Result},
+    Id, Location, Metadata,
+};
 
 /// A room in the Home
 /// ```
@@ -13,12 +19,26 @@ use crate::error::{Error, Result};
 /// assert_eq!(room.name(), "Room 1");
 /// assert_eq!(room.device_iter().count(), 0);
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Room {
+    /// Stable identifier of the room
+    id: Id,
     /// Name of the room
     name: String,
-    /// List of devices in the current room
-    devices: HashMap<String, Device>,
+    /// Tags and properties of the room
+    metadata: Metadata,
+    /// Place of the room in the home hierarchy
+    location: Option<Location>,
+    /// List of devices in the current room (in the user-defined order), boxed so the device
+    /// address stays the same when other devices are removed or reordered
+    devices: IndexMap<String, Box<Device>>,
+}
+
+/// Rooms are equal if they have the same name and devices
+impl PartialEq for Room {
+    fn eq(&self, other: &Room) -> bool {
+        self.name == other.name && self.devices == other.devices
+    }
 }
 
 impl Room {
@@ -28,66 +48,188 @@ impl Room {
         T: Into<String>,
     {
         Self {
+            id: Id::new_v4(),
             name: name.into(),
+            metadata: Default::default(),
+            location: None,
             devices: Default::default(),
         }
     }
 
+    /// Use the id instead of the generated one, e.g. to restore the room from a storage
+    pub fn with_id(mut self, id: Id) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Gets room id
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
     /// Gets room name
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Sets room name
+    pub(crate) fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Gets room tags and properties
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Gets mutable room tags and properties
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// Gets room place in the home hierarchy, None if the room is not placed
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Place the room on the floor or in the zone, None to remove it from the hierarchy
+    pub fn set_location(&mut self, location: Option<Location>) {
+        self.location = location;
+    }
+
     /// Add device to the Room
     pub fn add_device<T>(&mut self, device: T) -> Result<&mut Device>
     where
-        T: SmartDevice + Into<Device>,
+        T: Into<Device>,
     {
+        let device = device.into();
+
         match self.devices.entry(device.name().to_string()) {
-            Entry::Occupied(_) => Err(Error::DeviceAlreadyExists(device.into())),
-            Entry::Vacant(entry) => Ok(entry.insert(device.into())),
+            Entry::Occupied(_) => Err(Error::DeviceAlreadyExists(Box::new(device))),
+            Entry::Vacant(entry) => Ok(entry.insert(Box::new(device))),
         }
     }
 
     /// Del device from the Room
+    ///
+    /// Use [`crate::Home::del_device`] to remove the device from its groups as well.
     pub fn del_device(&mut self, name: &str) -> Option<Device> {
-        self.devices.remove(name)
+        self.devices.shift_remove(name).map(|device| *device)
+    }
+
+    /// Rename device in the Room, the device keeps its connection and position
+    ///
+    /// Use [`crate::Home::rename_device`] to keep the device in its groups.
+    pub fn rename_device(&mut self, name: &str, new_name: &str) -> Result<&mut Device> {
+        if new_name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+
+        if name != new_name && self.devices.contains_key(new_name) {
+            return Err(Error::NameAlreadyTaken(new_name.to_string()));
+        }
+
+        let (index, _, mut device) = self
+            .devices
+            .shift_remove_full(name)
+            .ok_or(Error::DeviceNotFound)?;
+        device.set_name(new_name);
+        self.devices
+            .shift_insert(index, new_name.to_string(), device);
+
+        Ok(&mut self.devices[index])
+    }
+
+    /// Move device to the position in the Room
+    pub fn set_device_position(&mut self, name: &str, position: usize) -> Result<()> {
+        let index = self
+            .devices
+            .get_index_of(name)
+            .ok_or(Error::DeviceNotFound)?;
+
+        if position >= self.devices.len() {
+            return Err(Error::InvalidPosition);
+        }
+
+        self.devices.move_index(index, position);
+
+        Ok(())
     }
 
     /// Get device by name
     pub fn device(&self, name: &str) -> Option<&Device> {
-        self.devices.get(name)
+        self.devices.get(name).map(|device| &**device)
     }
 
     /// Get mutable device by name
     pub fn device_mut(&mut self, name: &str) -> Option<&mut Device> {
-        self.devices.get_mut(name)
+        self.devices.get_mut(name).map(|device| &mut **device)
+    }
+
+    /// Get device by id
+    pub fn device_by_id(&self, id: Id) -> Option<&Device> {
+        self.device_iter().find(|device| device.id() == id)
     }
 
-    /// Get device iterator in the current room
+    /// Get mutable device by id
+    pub fn device_by_id_mut(&mut self, id: Id) -> Option<&mut Device> {
+        self.device_iter_mut().find(|device| device.id() == id)
+    }
+
+    /// Get device iterator in the current room in the user-defined order
     pub fn device_iter(&self) -> impl Iterator<Item = &Device> {
-        self.devices.iter().map(|(_, device)| device)
+        self.devices.values().map(|device| &**device)
     }
 
-    /// Get mutable device iterator in the current room
+    /// Get mutable device iterator in the current room in the user-defined order
     pub fn device_iter_mut(&mut self) -> impl Iterator<Item = &mut Device> {
-        self.devices.iter_mut().map(|(_, device)| device)
+        self.devices.values_mut().map(|device| &mut **device)
+    }
+
+    /// Get devices of the implementation `T`
+    pub fn devices_of<T>(&self) -> impl Iterator<Item = &T>
+    where
+        T: SmartDevice,
+    {
+        self.device_iter()
+            .filter_map(|device| device.downcast_ref::<T>())
+    }
+
+    /// Get devices with the capability
+    pub fn devices_with(&self, capability: Capability) -> impl Iterator<Item = &Device> {
+        self.device_iter()
+            .filter(move |device| device.has_capability(capability))
+    }
+
+    /// Get devices with the tag
+    pub fn devices_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Device> + 'a {
+        self.device_iter()
+            .filter(move |device| device.metadata().has_tag(tag))
     }
 
     /// Get socket devices
     pub fn socket_devices(&self) -> impl Iterator<Item = &SmartSocket> {
-        self.device_iter().filter_map(|device| match device {
-            Device::Socket(socket) => Some(socket),
-            _ => None,
-        })
+        self.devices_of()
     }
 
     /// Get thermometer devices
     pub fn thermometer_devices(&self) -> impl Iterator<Item = &SmartThermometer> {
-        self.device_iter().filter_map(|device| match device {
-            Device::Thermometer(thermometer) => Some(thermometer),
-            _ => None,
-        })
+        self.devices_of()
+    }
+
+    /// Get multi-sensor devices
+    pub fn sensor_devices(&self) -> impl Iterator<Item = &SmartMultiSensor> {
+        self.devices_of()
+    }
+
+    /// Get thermostat devices
+    pub fn thermostat_devices(&self) -> impl Iterator<Item = &SmartThermostat> {
+        self.devices_of()
+    }
+
+    /// Get binary sensor devices
+    pub fn binary_sensor_devices(&self) -> impl Iterator<Item = &SmartBinarySensor> {
+        self.devices_of()
     }
 }
 
@@ -110,6 +252,10 @@ mod tests {
         SmartSocket::new("smart socket", "Handmade socket", "localhost:81").await
     }
 
+    async fn get_predefined_sensor() -> SmartMultiSensor {
+        SmartMultiSensor::new("smart sensor", "Handmade sensor", "localhost:82").await
+    }
+
     #[tokio::test]
     async fn example() {
         let mut room = Room::new("room");
@@ -121,7 +267,7 @@ mod tests {
         assert_eq!(room.device_iter().count(), 1);
         assert_eq!(
             room.device("smart thermometer"),
-            Some(&Device::Thermometer(get_predefined_thermometer().await))
+            Some(&Device::new(get_predefined_thermometer().await))
         );
         assert!(room.device_iter().any(|d| d.name() == "smart thermometer"));
         assert!(room
@@ -133,7 +279,7 @@ mod tests {
 
         assert_eq!(
             smart_device,
-            r#"Some(Thermometer(SmartThermometer { name: "smart thermometer", description: "Handmade thermometer" }))"#
+            r#"Some(SmartThermometer { name: "smart thermometer", description: "Handmade thermometer" })"#
         );
 
         room.add_device(get_predefined_socket().await).unwrap();
@@ -147,18 +293,40 @@ mod tests {
         assert_eq!(room.device_iter_mut().count(), 2);
         assert_eq!(
             room.device("smart socket"),
-            Some(&Device::Socket(get_predefined_socket().await))
+            Some(&Device::new(get_predefined_socket().await))
         );
         let smart_device = room.device_iter().find(|d| d.name() == "smart socket");
         let smart_device = format!("{:?}", smart_device);
 
         assert_eq!(
             smart_device,
-            r#"Some(Socket(SmartSocket { name: "smart socket", description: "Handmade socket" }))"#
+            r#"Some(SmartSocket { name: "smart socket", description: "Handmade socket" })"#
         );
 
         assert_eq!(room.thermometer_devices().count(), 1);
         assert_eq!(room.socket_devices().count(), 1);
+        assert_eq!(room.sensor_devices().count(), 0);
+
+        room.add_device(get_predefined_sensor().await).unwrap();
+        assert_eq!(room.sensor_devices().count(), 1);
+        assert_eq!(room.thermometer_devices().count(), 1);
+        assert!(room.sensor_devices().any(|s| s.name() == "smart sensor"));
+
+        let thermostat = SmartThermostat::new(
+            "smart thermostat",
+            "Handmade thermostat",
+            get_predefined_thermometer().await,
+            get_predefined_socket().await,
+        )
+        .await;
+        room.add_device(thermostat).unwrap();
+        assert_eq!(room.thermostat_devices().count(), 1);
+        assert_eq!(room.socket_devices().count(), 1);
+        assert_eq!(room.device_iter().count(), 4);
+        assert_eq!(room.devices_with(Capability::Temperature).count(), 3);
+        assert_eq!(room.devices_with(Capability::Switchable).count(), 1);
+        assert_eq!(room.devices_with(Capability::TemperatureControl).count(), 1);
+        room.del_device("smart thermostat").unwrap();
 
         let deleted_device = room.del_device("smart socket");
         assert!(deleted_device.is_some());
@@ -166,6 +334,40 @@ mod tests {
 
         assert_eq!(deleted_device.name(), "smart socket");
         assert_eq!(deleted_device.description(), "Handmade socket");
-        assert!(matches!(deleted_device, Device::Socket(_)));
+        assert!(deleted_device.is::<SmartSocket>());
+    }
+
+    #[tokio::test]
+    async fn rename() {
+        let mut room = Room::new("room");
+        room.add_device(get_predefined_socket().await).unwrap();
+        room.add_device(get_predefined_thermometer().await).unwrap();
+
+        // a pending request holds a clone, the device is renamed anyway
+        let pending = room.device("smart socket").unwrap().clone();
+        let device = room.rename_device("smart socket", "heater").unwrap();
+        assert_eq!(device.name(), "heater");
+        assert_eq!(room.device("smart socket"), None);
+        assert!(room.device("heater").unwrap().is::<SmartSocket>());
+        assert_eq!(pending.name(), "smart socket");
+        drop(pending);
+
+        assert!(matches!(
+            room.rename_device("heater", "smart thermometer"),
+            Err(Error::NameAlreadyTaken(_))
+        ));
+        assert!(matches!(
+            room.rename_device("heater", ""),
+            Err(Error::EmptyName)
+        ));
+        assert!(room.device("heater").is_some());
+
+        assert!(matches!(
+            room.rename_device("NOT FOUND", "lamp"),
+            Err(Error::DeviceNotFound)
+        ));
+
+        room.rename_device("heater", "heater").unwrap();
+        assert_eq!(room.device_iter().count(), 2);
     }
 }
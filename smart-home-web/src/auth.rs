@@ -0,0 +1,227 @@
+use std::{
+    future::{ready, Ready},
+    ops::Deref,
+};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, FromRequest, HttpMessage, HttpRequest,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, WebResult},
+    home_list::GlobalContext,
+};
+
+/// Paths available without the credentials
+const PUBLIC_PATHS: &[&str] = &["/openapi.json"];
+
+/// Access level, every role has the rights of the previous ones
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read homes, rooms, devices and readings
+    Viewer,
+    /// Switch sockets and device groups and control thermostats
+    Operator,
+    /// Change homes, rooms, devices and groups
+    Admin,
+}
+
+/// Static API key of the config file
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    pub key: String,
+    pub role: Role,
+    /// Homes available with the key, all homes if missing
+    #[serde(default)]
+    pub homes: Option<Vec<String>>,
+}
+
+/// Claims of the JWT signed with HS256
+#[derive(Deserialize)]
+struct Claims {
+    role: Role,
+    #[serde(default)]
+    homes: Option<Vec<String>>,
+}
+
+/// Authentication settings, all requests are allowed if there are no keys and no JWT secret
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+    /// Secret of the HS256 signed JWT
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+}
+
+/// Authenticated client of the request
+#[derive(Clone, Debug)]
+pub struct Principal {
+    role: Role,
+    homes: Option<Vec<String>>,
+}
+
+impl Principal {
+    /// Client of the server without authentication
+    fn anonymous() -> Self {
+        Self {
+            role: Role::Admin,
+            homes: None,
+        }
+    }
+
+    /// Check if the home is available to the client
+    pub fn can_access(&self, home: &str) -> bool {
+        self.homes
+            .as_ref()
+            .is_none_or(|homes| homes.iter().any(|h| h == home))
+    }
+
+    /// Check if the client is not limited to some homes
+    pub fn has_all_homes(&self) -> bool {
+        self.homes.is_none()
+    }
+
+    /// Check the home permission
+    pub fn check_home(&self, home: &str) -> WebResult<()> {
+        if self.can_access(home) {
+            Ok(())
+        } else {
+            Err(Error::Forbidden)
+        }
+    }
+
+    fn authorize(&self, role: Role, home: Option<&str>) -> WebResult<()> {
+        if self.role < role {
+            return Err(Error::Forbidden);
+        }
+
+        match home {
+            Some(home) => self.check_home(home),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compare the secrets in the constant time
+fn secret_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+impl AuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty() || self.jwt_secret.is_some()
+    }
+
+    /// Find the client of the API key or the JWT
+    fn authenticate(&self, token: &str) -> Option<Principal> {
+        if let Some(key) = self.api_keys.iter().find(|key| secret_eq(&key.key, token)) {
+            return Some(Principal {
+                role: key.role,
+                homes: key.homes.clone(),
+            });
+        }
+
+        let secret = self.jwt_secret.as_ref()?;
+        let claims = jsonwebtoken::decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .ok()?
+        .claims;
+
+        Some(Principal {
+            role: claims.role,
+            homes: claims.homes,
+        })
+    }
+}
+
+/// Middleware to authenticate the `Authorization: Bearer <API key or JWT>` header
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let auth = &req
+        .app_data::<web::Data<GlobalContext>>()
+        .ok_or(Error::Internal)?
+        .auth;
+
+    let principal = if !auth.is_enabled() || PUBLIC_PATHS.contains(&req.path()) {
+        Principal::anonymous()
+    } else {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| auth.authenticate(token.trim()))
+            .ok_or(Error::Unauthorized)?
+    };
+
+    req.extensions_mut().insert(principal);
+
+    next.call(req).await
+}
+
+/// Extractors of the principal with the role, the `{home}` of the path is checked too
+macro_rules! role_extractor {
+    ($(#[$meta:meta])* $name:ident, $role:expr) => {
+        $(#[$meta])*
+        pub struct $name(pub Principal);
+
+        impl Deref for $name {
+            type Target = Principal;
+
+            fn deref(&self) -> &Principal {
+                &self.0
+            }
+        }
+
+        impl FromRequest for $name {
+            type Error = Error;
+            type Future = Ready<WebResult<Self>>;
+
+            fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+                ready(
+                    req.extensions()
+                        .get::<Principal>()
+                        .ok_or(Error::Unauthorized)
+                        .and_then(|principal| {
+                            principal.authorize($role, req.match_info().get("home"))?;
+                            Ok(Self(principal.clone()))
+                        }),
+                )
+            }
+        }
+    };
+}
+
+role_extractor!(
+    /// Client allowed to read
+    Viewer,
+    Role::Viewer
+);
+role_extractor!(
+    /// Client allowed to control devices
+    Operator,
+    Role::Operator
+);
+role_extractor!(
+    /// Client allowed to change the topology
+    Admin,
+    Role::Admin
+);
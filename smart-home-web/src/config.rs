@@ -0,0 +1,224 @@
+use std::{env, fmt, fs, path::PathBuf, str::FromStr, time::Duration};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::auth::AuthConfig;
+
+const DEFAULT_BIND: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 4080;
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Environment variables of the command line flags
+const ENV_HELP: &str = "Environment:
+  SMART_HOME_CONFIG, SMART_HOME_BIND, SMART_HOME_PORT, SMART_HOME_WORKERS,
+  SMART_HOME_LOG_LEVEL, SMART_HOME_STORE, SMART_HOME_TOPOLOGY, SMART_HOME_JWT_SECRET,
+  SMART_HOME_METRICS_INTERVAL are used for the flags missing on the command line";
+
+/// Lookup of the environment variables
+type Env = dyn Fn(&str) -> Option<String>;
+
+/// Take the environment variable if the setting is missing on the command line
+fn fill<T>(value: &mut Option<T>, env: &Env, name: &str) -> Result<(), String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    if let (None, Some(raw)) = (&value, env(name)) {
+        *value = Some(raw.parse().map_err(|e| format!("{}: {}", name, e))?);
+    }
+
+    Ok(())
+}
+
+/// Smart Home web server
+///
+/// Settings are taken from the command line, then from the environment, then from the config file.
+#[derive(Parser)]
+#[command(after_help = ENV_HELP)]
+struct Args {
+    /// TOML config file with the same settings
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Address to listen on [default: 127.0.0.1]
+    #[arg(long, value_name = "ADDR")]
+    bind: Option<String>,
+    /// Port to listen on [default: 4080]
+    #[arg(long)]
+    port: Option<u16>,
+    /// Count of the worker threads [default: count of CPUs]
+    #[arg(long)]
+    workers: Option<usize>,
+    /// Log filter, e.g. `info` or `web_server=debug` [default: `RUST_LOG` or info]
+    #[arg(long, value_name = "FILTER")]
+    log_level: Option<String>,
+    /// Store homes in the file (SQLite database for `.db`, `.sqlite` and `.sqlite3` files, JSON
+    /// otherwise), homes are kept only in memory if missing
+    #[arg(long, value_name = "PATH")]
+    store: Option<PathBuf>,
+    /// JSON file with homes to create on startup if there are no stored homes
+    #[arg(long, value_name = "PATH")]
+    topology: Option<PathBuf>,
+    /// Secret of the HS256 signed JWT, API keys are set in the config file
+    #[arg(long, value_name = "SECRET")]
+    jwt_secret: Option<String>,
+    /// Read the devices for the metrics in the background every SECONDS instead of on every
+    /// `/metrics` scrape
+    #[arg(long, value_name = "SECONDS")]
+    metrics_interval: Option<u64>,
+}
+
+impl Args {
+    /// Fill the settings missing on the command line from the environment
+    fn fill_from_env(&mut self, env: &Env) -> Result<(), String> {
+        fill(&mut self.config, env, "SMART_HOME_CONFIG")?;
+        fill(&mut self.bind, env, "SMART_HOME_BIND")?;
+        fill(&mut self.port, env, "SMART_HOME_PORT")?;
+        fill(&mut self.workers, env, "SMART_HOME_WORKERS")?;
+        fill(&mut self.log_level, env, "SMART_HOME_LOG_LEVEL")?;
+        fill(&mut self.store, env, "SMART_HOME_STORE")?;
+        fill(&mut self.topology, env, "SMART_HOME_TOPOLOGY")?;
+        fill(&mut self.jwt_secret, env, "SMART_HOME_JWT_SECRET")?;
+        fill(
+            &mut self.metrics_interval,
+            env,
+            "SMART_HOME_METRICS_INTERVAL",
+        )
+    }
+}
+
+/// Settings of the config file, all are optional
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    bind: Option<String>,
+    port: Option<u16>,
+    workers: Option<usize>,
+    log_level: Option<String>,
+    store: Option<PathBuf>,
+    topology: Option<PathBuf>,
+    metrics_interval: Option<u64>,
+    #[serde(default)]
+    auth: AuthConfig,
+}
+
+/// Web server settings
+pub struct Config {
+    pub bind: String,
+    pub port: u16,
+    pub workers: Option<usize>,
+    pub log_level: String,
+    pub store: Option<PathBuf>,
+    pub topology: Option<PathBuf>,
+    /// Period of the background metrics collector, devices are read on every scrape if missing
+    pub metrics_interval: Option<Duration>,
+    /// Requests are not authenticated if it is empty
+    pub auth: AuthConfig,
+}
+
+impl Config {
+    /// Parse the command line, the environment and the config file
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_args(Args::parse(), &|name| env::var(name).ok())
+    }
+
+    /// Complete the parsed command line with the environment and the config file
+    fn from_args(mut args: Args, env: &Env) -> Result<Self, Box<dyn std::error::Error>> {
+        args.fill_from_env(env)?;
+        let mut file: FileConfig = match &args.config {
+            Some(path) => toml::from_str(&fs::read_to_string(path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => FileConfig::default(),
+        };
+
+        if let Some(jwt_secret) = args.jwt_secret {
+            file.auth.jwt_secret = Some(jwt_secret);
+        }
+
+        Ok(Self {
+            bind: args
+                .bind
+                .or(file.bind)
+                .unwrap_or_else(|| DEFAULT_BIND.to_string()),
+            port: args.port.or(file.port).unwrap_or(DEFAULT_PORT),
+            workers: args.workers.or(file.workers),
+            log_level: args
+                .log_level
+                .or(file.log_level)
+                .or_else(|| env("RUST_LOG"))
+                .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()),
+            store: args.store.or(file.store),
+            topology: args.topology.or(file.topology),
+            metrics_interval: args
+                .metrics_interval
+                .or(file.metrics_interval)
+                .map(Duration::from_secs),
+            auth: file.auth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::TempPath;
+
+    /// Environment with the given variables only
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let path = TempPath::new("config.toml");
+        fs::write(
+            &path.0,
+            "bind = \"0.0.0.0\"\nport = 1000\nworkers = 1\nlog_level = \"warn\"\n",
+        )
+        .unwrap();
+        let vars = env(&[
+            ("SMART_HOME_PORT", "2000"),
+            ("SMART_HOME_WORKERS", "2"),
+            ("RUST_LOG", "debug"),
+        ]);
+
+        let args = Args::parse_from([
+            "web-server".as_ref(),
+            "--config".as_ref(),
+            path.0.as_os_str(),
+            "--port".as_ref(),
+            "3000".as_ref(),
+        ]);
+        let config = Config::from_args(args, &vars).unwrap();
+
+        // command line over environment over file
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.bind, "0.0.0.0");
+        assert_eq!(config.log_level, "warn");
+
+        // `RUST_LOG` is used only if the level is not set otherwise
+        let config = Config::from_args(
+            Args::parse_from(["web-server"]),
+            &env(&[("RUST_LOG", "debug")]),
+        )
+        .unwrap();
+
+        assert_eq!(config.log_level, "debug");
+        assert_eq!(config.bind, DEFAULT_BIND);
+        assert_eq!(config.port, DEFAULT_PORT);
+
+        let config = Config::from_args(Args::parse_from(["web-server"]), &env(&[])).unwrap();
+        assert_eq!(config.log_level, DEFAULT_LOG_LEVEL);
+
+        let error = Config::from_args(
+            Args::parse_from(["web-server"]),
+            &env(&[("SMART_HOME_PORT", "http")]),
+        );
+        assert!(error.is_err());
+    }
+}
@@ -1,50 +1,146 @@
-use actix_web::{delete, get, http::StatusCode, post, put, web, web::Json, HttpResponse};
-use smart_home_lib::{Device, SmartSocket, SmartThermometer};
+use actix_web::{delete, get, http::Uri, patch, post, web, web::Json, HttpRequest, HttpResponse};
+use smart_home_lib::{
+    error::Error as LibError, Device, SmartSocket, SmartThermometer, SmartThermostat,
+    ThermostatMode,
+};
 
 use crate::{
-    error::{Error, WebResult},
-    home_list::GlobalContext,
-    types::{WebSocket, WebSocketResult, WebThermometer, WebThermometerResult},
+    auth::{Admin, Operator, Viewer},
+    error::{device_request, Error, ErrorResponse, WebResult},
+    expand::Expand,
+    home_list::{created, GlobalContext},
+    types::{
+        WebEvent, WebEventKind, WebExpandQuery, WebMoveDevice, WebPosition, WebSocket,
+        WebSocketResult, WebTagFilter, WebThermometer, WebThermometerResult, WebThermostat,
+        WebThermostatMode, WebThermostatState, WebThermostatTarget, WebUpdate,
+    },
     WebDevice,
 };
 
+/// Socket address must be the `http` or `https` URI of the gRPC server
+pub fn check_socket_addr(addr: &str) -> WebResult<()> {
+    let uri = addr.parse::<Uri>().map_err(|_| Error::InvalidAddress)?;
+
+    match (uri.scheme_str(), uri.authority()) {
+        (Some("http" | "https"), Some(_)) => Ok(()),
+        _ => Err(Error::InvalidAddress),
+    }
+}
+
+/// Thermometer address must be the `host:port` to listen on
+pub fn check_listen_addr(addr: &str) -> WebResult<()> {
+    match addr.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(()),
+        _ => Err(Error::InvalidAddress),
+    }
+}
+
+/// Device name must not be empty, as on rename
+fn check_name(name: &str) -> WebResult<()> {
+    match name.is_empty() {
+        true => Err(Error::EmptyName),
+        false => Ok(()),
+    }
+}
+
+/// List devices of the room, optionally only with the tag
+#[utoipa::path(
+    tag = "device",
+    params(WebTagFilter, WebExpandQuery),
+    responses(
+        (status = 200, description = "Devices in the user-defined order", body = Vec<WebDevice>),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/{room}/")]
 async fn read_device_list(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String, String)>,
+    filter: web::Query<WebTagFilter>,
+    expand: web::Query<WebExpandQuery>,
 ) -> WebResult<Json<Vec<WebDevice>>> {
+    let expand = Expand::parse(&expand)?;
     let home_list = ctx.home_list.lock().await;
     let (home, room) = path.into_inner();
     let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
     let room = home.room(&room).ok_or(Error::RoomNotFound)?;
 
-    Ok(Json(room.device_iter().map(|d| d.into()).collect()))
+    let devices: Vec<&Device> = match &filter.tag {
+        Some(tag) => room.devices_with_tag(tag).collect(),
+        None => room.device_iter().collect(),
+    };
+
+    let pending = expand.devices(devices);
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
+/// Get the device
+#[utoipa::path(
+    tag = "device",
+    params(WebExpandQuery),
+    responses(
+        (status = 200, description = "Device", body = WebDevice),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/{room}/{device}")]
 async fn read_device(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String, String, String)>,
+    expand: web::Query<WebExpandQuery>,
 ) -> WebResult<Json<WebDevice>> {
+    let expand = Expand::parse(&expand)?;
     let home_list = ctx.home_list.lock().await;
     let (home, room, device) = path.into_inner();
     let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
     let room = home.room(&room).ok_or(Error::RoomNotFound)?;
     let device = room.device(&device).ok_or(Error::DeviceNotFound)?;
 
-    Ok(Json(device.into()))
+    let pending = expand.device(device);
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
-#[put("/{home}/{room}/thermometer")]
+/// Connect the UDP thermometer
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 201, description = "Created device, `Location` is its URL", body = WebDevice),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 409, description = "Device is already exists", body = ErrorResponse),
+        (status = 422, description = "Empty name or invalid thermometer address", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/thermometer")]
 async fn create_thermometer(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     thermometer: web::Json<WebThermometer>,
-) -> WebResult<Json<WebDevice>> {
+) -> WebResult<HttpResponse> {
+    check_name(&thermometer.name)?;
+    check_listen_addr(&thermometer.server_addr)?;
+    let event = WebEvent::device(
+        WebEventKind::DeviceCreated,
+        &path.0,
+        &path.1,
+        &thermometer.name,
+    );
     let mut home_list = ctx.home_list.lock().await;
-    let (home, room) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
+    let previous = home_list.clone();
+    let (home_name, room_name) = path.into_inner();
+    let home = home_list
+        .get_home_mut(&home_name)
+        .ok_or(Error::HomeNotFound)?;
+    let room = home.room_mut(&room_name).ok_or(Error::RoomNotFound)?;
     let thermometer = thermometer.into_inner();
     let device = room
         .add_device(
@@ -57,120 +153,544 @@ async fn create_thermometer(
         )
         .map_err(|_| Error::DeviceAlreadyExists)?;
 
-    Ok(Json(device.into()))
+    let response = created(
+        &req,
+        "read_device",
+        [home_name.as_str(), room_name.as_str(), device.name()],
+        WebDevice::from(&*device),
+    )?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
 }
 
+/// Read the current temperature
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Current temperature", body = WebThermometerResult),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device has no temperature sensor", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+        (status = 503, description = "Device has no measurement yet", body = ErrorResponse),
+        (status = 504, description = "Device did not answer in time", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/{room}/{thermometer}/current_temperature")]
 async fn get_current_temperature(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String, String, String)>,
 ) -> WebResult<Json<WebThermometerResult>> {
-    let mut home_list = ctx.home_list.lock().await;
     let (home, room, thermometer) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
-    let thermometer = room.device(&thermometer).ok_or(Error::DeviceNotFound)?;
-
-    if let Device::Thermometer(thermometer) = thermometer {
-        Ok(Json(WebThermometerResult {
-            current_temperature: thermometer
-                .current_temperature()
-                .await
-                .map_err(|_| Error::Internal)?,
-        }))
-    } else {
-        Err(Error::DeviceNotCompatible)
-    }
+    let device = ctx.device(&home, &room, &thermometer).await?;
+    let thermometer = device
+        .as_temperature_sensor()
+        .ok_or(Error::DeviceNotCompatible)?;
+
+    Ok(Json(WebThermometerResult {
+        current_temperature: device_request(thermometer.current_temperature()).await?,
+    }))
 }
 
+/// Read the current power
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Current power", body = WebSocketResult),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device has no power meter", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+        (status = 504, description = "Device did not answer in time", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/{room}/{socket}/current_power")]
 async fn get_current_power(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String, String, String)>,
 ) -> WebResult<Json<WebSocketResult>> {
-    let mut home_list = ctx.home_list.lock().await;
     let (home, room, socket) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
-    let socket = room.device(&socket).ok_or(Error::DeviceNotFound)?;
-
-    if let Device::Socket(socket) = socket {
-        Ok(Json(WebSocketResult {
-            current_power: socket.current_power().await.map_err(|_| Error::Internal)?,
-        }))
-    } else {
-        Err(Error::DeviceNotCompatible)
-    }
+    let device = ctx.device(&home, &room, &socket).await?;
+    let socket = device.as_power_meter().ok_or(Error::DeviceNotCompatible)?;
+
+    Ok(Json(WebSocketResult {
+        current_power: device_request(socket.current_power()).await?,
+    }))
 }
 
+/// Turn the device on
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 204, description = "Device is turned on"),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is not switchable", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+        (status = 504, description = "Device did not answer in time", body = ErrorResponse),
+    )
+)]
 #[post("/{home}/{room}/{socket}/on")]
 async fn socket_on(
     ctx: web::Data<GlobalContext>,
+    _auth: Operator,
     path: web::Path<(String, String, String)>,
 ) -> WebResult<HttpResponse> {
-    let mut home_list = ctx.home_list.lock().await;
+    let event = WebEvent::device(WebEventKind::SocketOn, &path.0, &path.1, &path.2);
     let (home, room, socket) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
-    let socket = room.device(&socket).ok_or(Error::DeviceNotFound)?;
+    let device = ctx.device(&home, &room, &socket).await?;
+    let socket = device.as_switchable().ok_or(Error::DeviceNotCompatible)?;
 
-    if let Device::Socket(socket) = socket {
-        socket.on().await.map_err(|_| Error::Internal)?;
+    device_request(socket.on()).await?;
 
-        Ok(HttpResponse::new(StatusCode::OK))
-    } else {
-        Err(Error::DeviceNotCompatible)
-    }
+    ctx.publish(event);
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
+/// Turn the device off
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 204, description = "Device is turned off"),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is not switchable", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+        (status = 504, description = "Device did not answer in time", body = ErrorResponse),
+    )
+)]
 #[post("/{home}/{room}/{socket}/off")]
 async fn socket_off(
     ctx: web::Data<GlobalContext>,
+    _auth: Operator,
     path: web::Path<(String, String, String)>,
 ) -> WebResult<HttpResponse> {
-    let mut home_list = ctx.home_list.lock().await;
+    let event = WebEvent::device(WebEventKind::SocketOff, &path.0, &path.1, &path.2);
     let (home, room, socket) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
-    let socket = room.device(&socket).ok_or(Error::DeviceNotFound)?;
+    let device = ctx.device(&home, &room, &socket).await?;
+    let socket = device.as_switchable().ok_or(Error::DeviceNotCompatible)?;
 
-    if let Device::Socket(socket) = socket {
-        socket.off().await.map_err(|_| Error::Internal)?;
+    device_request(socket.off()).await?;
 
-        Ok(HttpResponse::new(StatusCode::OK))
-    } else {
-        Err(Error::DeviceNotCompatible)
-    }
+    ctx.publish(event);
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[put("/{home}/{room}/socket")]
+/// Connect the gRPC socket
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 201, description = "Created device, `Location` is its URL", body = WebDevice),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 409, description = "Device is already exists", body = ErrorResponse),
+        (status = 422, description = "Empty name or invalid socket address", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/socket")]
 async fn create_socket(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     socket: web::Json<WebSocket>,
-) -> WebResult<Json<WebDevice>> {
+) -> WebResult<HttpResponse> {
+    check_name(&socket.name)?;
+    check_socket_addr(&socket.server_addr)?;
+    let event = WebEvent::device(WebEventKind::DeviceCreated, &path.0, &path.1, &socket.name);
     let mut home_list = ctx.home_list.lock().await;
-    let (home, room) = path.into_inner();
-    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
+    let previous = home_list.clone();
+    let (home_name, room_name) = path.into_inner();
+    let home = home_list
+        .get_home_mut(&home_name)
+        .ok_or(Error::HomeNotFound)?;
+    let room = home.room_mut(&room_name).ok_or(Error::RoomNotFound)?;
     let socket = socket.into_inner();
     let device = room
         .add_device(SmartSocket::new(socket.name, socket.description, socket.server_addr).await)
         .map_err(|_| Error::DeviceAlreadyExists)?;
 
-    Ok(Json(device.into()))
+    let response = created(
+        &req,
+        "read_device",
+        [home_name.as_str(), room_name.as_str(), device.name()],
+        WebDevice::from(&*device),
+    )?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
 }
 
-#[delete("/{home}/{room}/{device}")]
-async fn delete_device(
+/// Create the thermostat from the thermometer and the heater socket
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 201, description = "Created device, `Location` is its URL", body = WebDevice),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 409, description = "Device is already exists", body = ErrorResponse),
+        (status = 422, description = "Empty name or invalid thermometer or heater address", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/thermostat")]
+async fn create_thermostat(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    thermostat: web::Json<WebThermostat>,
+) -> WebResult<HttpResponse> {
+    check_name(&thermostat.name)?;
+    check_listen_addr(&thermostat.thermometer_addr)?;
+    check_socket_addr(&thermostat.heater_addr)?;
+    let event = WebEvent::device(
+        WebEventKind::DeviceCreated,
+        &path.0,
+        &path.1,
+        &thermostat.name,
+    );
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home_name, room_name) = path.into_inner();
+    let home = home_list
+        .get_home_mut(&home_name)
+        .ok_or(Error::HomeNotFound)?;
+    let room = home.room_mut(&room_name).ok_or(Error::RoomNotFound)?;
+    let thermostat = thermostat.into_inner();
+    let thermometer = SmartThermometer::new(
+        thermostat.name.as_str(),
+        thermostat.description.as_str(),
+        thermostat.thermometer_addr,
+    )
+    .await;
+    let heater = SmartSocket::new(
+        thermostat.name.as_str(),
+        thermostat.description.as_str(),
+        thermostat.heater_addr,
+    )
+    .await;
+    let device = room
+        .add_device(
+            SmartThermostat::new(thermostat.name, thermostat.description, thermometer, heater)
+                .await,
+        )
+        .map_err(|_| Error::DeviceAlreadyExists)?;
+
+    let response = created(
+        &req,
+        "read_device",
+        [home_name.as_str(), room_name.as_str(), device.name()],
+        WebDevice::from(&*device),
+    )?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Read the thermostat state
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Thermostat state", body = WebThermostatState),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is not a thermostat", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+        (status = 503, description = "Device has no measurement yet", body = ErrorResponse),
+        (status = 504, description = "Device did not answer in time", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{room}/{thermostat}/state")]
+async fn get_thermostat_state(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String, String)>,
+) -> WebResult<Json<WebThermostatState>> {
+    let (home, room, thermostat) = path.into_inner();
+    let device = ctx.device(&home, &room, &thermostat).await?;
+    let thermostat = device
+        .downcast_ref::<SmartThermostat>()
+        .ok_or(Error::DeviceNotCompatible)?;
+
+    Ok(Json(WebThermostatState {
+        current_temperature: device_request(thermostat.current_temperature()).await?,
+        target_temperature: thermostat.target_temperature().await,
+        mode: thermostat.mode().await.to_string(),
+        heating: thermostat.is_heating().await,
+    }))
+}
+
+/// Set the target temperature of the thermostat
+///
+/// The setting is kept even if the heater is unreachable now, the thermostat switches it later.
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 204, description = "Target temperature is set"),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is not a thermostat", body = ErrorResponse),
+        (status = 422, description = "Invalid temperature value", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/{thermostat}/target_temperature")]
+async fn set_thermostat_target(
     ctx: web::Data<GlobalContext>,
+    _auth: Operator,
     path: web::Path<(String, String, String)>,
+    target: web::Json<WebThermostatTarget>,
+) -> WebResult<HttpResponse> {
+    let (home, room, thermostat) = path.into_inner();
+    let device = ctx.device(&home, &room, &thermostat).await?;
+    let thermostat = device
+        .downcast_ref::<SmartThermostat>()
+        .ok_or(Error::DeviceNotCompatible)?;
+
+    let previous = thermostat.target_temperature().await;
+    thermostat
+        .set_target_temperature(target.target_temperature)
+        .await?;
+
+    // the hub is locked again only to store the new settings
+    let home_list = ctx.home_list.lock().await;
+    if let Err(e) = ctx.save(&home_list).await {
+        // the previous value is valid, so it is always restored
+        let _ = thermostat.set_target_temperature(previous).await;
+        return Err(e);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Set the thermostat mode
+///
+/// The mode is kept even if the heater is unreachable now, the thermostat switches it later.
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 204, description = "Mode is set"),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is not a thermostat", body = ErrorResponse),
+        (status = 422, description = "Invalid thermostat mode", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/{thermostat}/mode")]
+async fn set_thermostat_mode(
+    ctx: web::Data<GlobalContext>,
+    _auth: Operator,
+    path: web::Path<(String, String, String)>,
+    mode: web::Json<WebThermostatMode>,
+) -> WebResult<HttpResponse> {
+    let (home, room, thermostat) = path.into_inner();
+    let device = ctx.device(&home, &room, &thermostat).await?;
+    let thermostat = device
+        .downcast_ref::<SmartThermostat>()
+        .ok_or(Error::DeviceNotCompatible)?;
+    let mode: ThermostatMode = mode
+        .mode
+        .parse()
+        .map_err(|_| Error::InvalidThermostatMode)?;
+
+    let previous = thermostat.mode().await;
+    thermostat.set_mode(mode).await;
+
+    // the hub is locked again only to store the new settings
+    let home_list = ctx.home_list.lock().await;
+    if let Err(e) = ctx.save(&home_list).await {
+        thermostat.set_mode(previous).await;
+        return Err(e);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Rename the device or replace its tags and properties
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Updated device", body = WebDevice),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/{home}/{room}/{device}")]
+async fn update_device(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String, String)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebDevice>> {
+    let (home, room, device) = path.into_inner();
+
+    update_device_by_name(&ctx, &home, &room, device, update.into_inner()).await
+}
+
+/// Update of the device found by the name or the id
+pub async fn update_device_by_name(
+    ctx: &GlobalContext,
+    home: &str,
+    room: &str,
+    mut device: String,
+    mut update: WebUpdate,
+) -> WebResult<Json<WebDevice>> {
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let home = home_list.get_home_mut(home).ok_or(Error::HomeNotFound)?;
+    let home_name = home.name().to_string();
+    let mut events = Vec::new();
+
+    if let Some(name) = update.name.take() {
+        home.rename_device(room, &device, &name)
+            .map_err(|e| match e {
+                LibError::NameAlreadyTaken(_) => Error::DeviceAlreadyExists,
+                LibError::EmptyName => Error::EmptyName,
+                LibError::RoomNotFound => Error::RoomNotFound,
+                _ => Error::DeviceNotFound,
+            })?;
+        events.push(
+            WebEvent::device(WebEventKind::DeviceRenamed, &home_name, room, &device).renamed(&name),
+        );
+        device = name;
+    }
+
+    let device = home
+        .room_mut(room)
+        .ok_or(Error::RoomNotFound)?
+        .device_mut(&device)
+        .ok_or(Error::DeviceNotFound)?;
+    if update.apply(device.metadata_mut()) {
+        events.push(WebEvent::device(
+            WebEventKind::DeviceUpdated,
+            &home_name,
+            room,
+            device.name(),
+        ));
+    }
+
+    let response = Json(device.into());
+    ctx.persist(&mut home_list, previous).await?;
+    events.into_iter().for_each(|event| ctx.publish(event));
+
+    Ok(response)
+}
+
+/// Move the device to another room
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Moved device", body = WebDevice),
+        (status = 404, description = "Device or the target room is not found", body = ErrorResponse),
+        (status = 409, description = "Device is already exists in the target room", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/{device}/move")]
+async fn move_device(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String, String, String)>,
+    target: web::Json<WebMoveDevice>,
 ) -> WebResult<Json<WebDevice>> {
     let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home, room, device) = path.into_inner();
+    let target = target.into_inner();
+    let to_home = target.home.unwrap_or_else(|| home.clone());
+    principal.check_home(&to_home)?;
+    let events = [
+        WebEvent::device(WebEventKind::DeviceDeleted, &home, &room, &device),
+        WebEvent::device(WebEventKind::DeviceCreated, &to_home, &target.room, &device),
+    ];
+
+    let device = home_list
+        .move_device((&home, &room, &device), (&to_home, &target.room))
+        .map_err(|e| match e {
+            LibError::HomeNotFound => Error::HomeNotFound,
+            LibError::RoomNotFound => Error::RoomNotFound,
+            LibError::DeviceNotFound => Error::DeviceNotFound,
+            LibError::NameAlreadyTaken(_) => Error::DeviceAlreadyExists,
+            _ => Error::Internal,
+        })?;
+
+    let response = Json(device.into());
+    ctx.persist(&mut home_list, previous).await?;
+    events.into_iter().for_each(|event| ctx.publish(event));
+
+    Ok(response)
+}
+
+/// Move the device in the list
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Devices in the new order", body = Vec<WebDevice>),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+        (status = 422, description = "Position is out of range", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/{device}/position")]
+async fn set_device_position(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String, String)>,
+    position: web::Json<WebPosition>,
+) -> WebResult<Json<Vec<WebDevice>>> {
+    let event = WebEvent::device(WebEventKind::DeviceUpdated, &path.0, &path.1, &path.2);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
     let (home, room, device) = path.into_inner();
     let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
     let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
-    let device = room.del_device(&device).ok_or(Error::DeviceNotFound)?;
 
-    Ok(Json(device.into()))
+    room.set_device_position(&device, position.position)
+        .map_err(|e| match e {
+            LibError::InvalidPosition => Error::InvalidPosition,
+            _ => Error::DeviceNotFound,
+        })?;
+
+    let response = Json(room.device_iter().map(|d| d.into()).collect());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Delete the device
+#[utoipa::path(
+    tag = "device",
+    responses(
+        (status = 200, description = "Deleted device", body = WebDevice),
+        (status = 404, description = "Home, room or device is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/{home}/{room}/{device}")]
+async fn delete_device(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String, String)>,
+) -> WebResult<Json<WebDevice>> {
+    let (home, room, device) = path.into_inner();
+
+    delete_device_by_name(&ctx, &home, &room, &device).await
+}
+
+/// Deletion of the device found by the name or the id
+pub async fn delete_device_by_name(
+    ctx: &GlobalContext,
+    home: &str,
+    room: &str,
+    device: &str,
+) -> WebResult<Json<WebDevice>> {
+    let event = WebEvent::device(WebEventKind::DeviceDeleted, home, room, device);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let home = home_list.get_home_mut(home).ok_or(Error::HomeNotFound)?;
+    home.room(room).ok_or(Error::RoomNotFound)?;
+    let device = home.del_device(room, device).ok_or(Error::DeviceNotFound)?;
+
+    let response = Json(device.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
 }
@@ -1,9 +1,19 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use std::{future::Future, time::Duration};
+
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::{header, StatusCode},
+    HttpRequest, HttpResponse, ResponseError,
+};
 use serde::Serialize;
+use smart_home_lib::error::{Error as LibError, Result as LibResult};
 use thiserror::Error;
+use tokio::time;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
-struct ErrorResponse {
+/// Body of the error responses
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
     code: u16,
     error: String,
     message: String,
@@ -25,8 +35,50 @@ pub enum Error {
     DeviceNotFound,
     #[error("Device is already exists")]
     DeviceAlreadyExists,
+    #[error("Device group is not found")]
+    GroupNotFound,
+    #[error("Device group is already exists")]
+    GroupAlreadyExists,
+    #[error("Device is not in the group")]
+    GroupMemberNotFound,
+    #[error("Device is already in the group")]
+    GroupMemberAlreadyExists,
     #[error("Device type is not compatible")]
     DeviceNotCompatible,
+    #[error("Invalid temperature value")]
+    InvalidTemperature,
+    #[error("Invalid thermostat mode")]
+    InvalidThermostatMode,
+    #[error("Invalid device capability")]
+    InvalidCapability,
+    #[error("Invalid id")]
+    InvalidId,
+    #[error("Position is out of range")]
+    InvalidPosition,
+    #[error("Zone requires a floor")]
+    InvalidLocation,
+    #[error("Invalid device address")]
+    InvalidAddress,
+    #[error("Name is empty")]
+    EmptyName,
+    #[error("Failed to store the change")]
+    Storage,
+    #[error("Valid API key or token is required")]
+    Unauthorized,
+    #[error("Access is denied")]
+    Forbidden,
+    #[error("Id is already in use")]
+    IdAlreadyExists,
+    #[error("Device is unreachable")]
+    DeviceUnreachable,
+    #[error("Device did not answer in time")]
+    DeviceTimeout,
+    #[error("Device has no measurement yet")]
+    MeasurementNotAvailable,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Invalid request body: {0}")]
+    InvalidBody(String),
 }
 
 impl Error {
@@ -39,23 +91,92 @@ impl Error {
             Error::RoomAlreadyExists => "RoomAlreadyExists",
             Error::DeviceNotFound => "DeviceNotFound",
             Error::DeviceAlreadyExists => "DeviceAlreadyExists",
+            Error::GroupNotFound => "GroupNotFound",
+            Error::GroupAlreadyExists => "GroupAlreadyExists",
+            Error::GroupMemberNotFound => "GroupMemberNotFound",
+            Error::GroupMemberAlreadyExists => "GroupMemberAlreadyExists",
             Error::DeviceNotCompatible => "DeviceNotCompatible",
+            Error::InvalidTemperature => "InvalidTemperature",
+            Error::InvalidThermostatMode => "InvalidThermostatMode",
+            Error::InvalidCapability => "InvalidCapability",
+            Error::InvalidId => "InvalidId",
+            Error::InvalidPosition => "InvalidPosition",
+            Error::InvalidLocation => "InvalidLocation",
+            Error::InvalidAddress => "InvalidAddress",
+            Error::EmptyName => "EmptyName",
+            Error::Storage => "StorageError",
+            Error::Unauthorized => "Unauthorized",
+            Error::Forbidden => "Forbidden",
+            Error::IdAlreadyExists => "IdAlreadyExists",
+            Error::DeviceUnreachable => "DeviceUnreachable",
+            Error::DeviceTimeout => "DeviceTimeout",
+            Error::MeasurementNotAvailable => "MeasurementNotAvailable",
+            Error::InvalidRequest(_) => "InvalidRequest",
+            Error::InvalidBody(_) => "InvalidBody",
         }
         .to_string()
     }
 }
 
+impl From<LibError> for Error {
+    fn from(e: LibError) -> Self {
+        match e {
+            LibError::HomeNotFound => Error::HomeNotFound,
+            LibError::RoomNotFound => Error::RoomNotFound,
+            LibError::DeviceNotFound => Error::DeviceNotFound,
+            LibError::GroupNotFound => Error::GroupNotFound,
+            LibError::GroupAlreadyExists(_) => Error::GroupAlreadyExists,
+            LibError::GroupMemberNotFound => Error::GroupMemberNotFound,
+            LibError::GroupMemberAlreadyExists => Error::GroupMemberAlreadyExists,
+            LibError::HomeAlreadyExists(_) => Error::HomeAlreadyExists,
+            LibError::RoomAlreadyExists(_) => Error::RoomAlreadyExists,
+            LibError::DeviceAlreadyExists(_) => Error::DeviceAlreadyExists,
+            LibError::InvalidPosition => Error::InvalidPosition,
+            LibError::EmptyName => Error::EmptyName,
+            LibError::InvalidTemperature => Error::InvalidTemperature,
+            LibError::InvalidThermostatMode => Error::InvalidThermostatMode,
+            LibError::DeviceUnreachable(e) => {
+                log::warn!("Device is unreachable: {}", e);
+                Error::DeviceUnreachable
+            }
+            LibError::MeasurementNotAvailable => Error::MeasurementNotAvailable,
+            _ => Error::Internal,
+        }
+    }
+}
+
 impl ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Error::Internal => StatusCode::INTERNAL_SERVER_ERROR,
             Error::HomeNotFound => StatusCode::NOT_FOUND,
-            Error::HomeAlreadyExists => StatusCode::FORBIDDEN,
+            Error::HomeAlreadyExists => StatusCode::CONFLICT,
             Error::RoomNotFound => StatusCode::NOT_FOUND,
-            Error::RoomAlreadyExists => StatusCode::FORBIDDEN,
+            Error::RoomAlreadyExists => StatusCode::CONFLICT,
             Error::DeviceNotFound => StatusCode::NOT_FOUND,
-            Error::DeviceAlreadyExists => StatusCode::FORBIDDEN,
-            Error::DeviceNotCompatible => StatusCode::FORBIDDEN,
+            Error::DeviceAlreadyExists => StatusCode::CONFLICT,
+            Error::GroupNotFound => StatusCode::NOT_FOUND,
+            Error::GroupAlreadyExists => StatusCode::CONFLICT,
+            Error::GroupMemberNotFound => StatusCode::NOT_FOUND,
+            Error::GroupMemberAlreadyExists => StatusCode::CONFLICT,
+            Error::DeviceNotCompatible => StatusCode::CONFLICT,
+            Error::InvalidTemperature => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidThermostatMode => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidCapability => StatusCode::BAD_REQUEST,
+            Error::InvalidId => StatusCode::BAD_REQUEST,
+            Error::InvalidPosition => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidLocation => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidAddress => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::EmptyName => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Storage => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden => StatusCode::FORBIDDEN,
+            Error::IdAlreadyExists => StatusCode::CONFLICT,
+            Error::DeviceUnreachable => StatusCode::BAD_GATEWAY,
+            Error::DeviceTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Error::MeasurementNotAvailable => StatusCode::SERVICE_UNAVAILABLE,
+            Error::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Error::InvalidBody(_) => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 
@@ -66,8 +187,42 @@ impl ResponseError for Error {
             message: self.to_string(),
             error: self.name(),
         };
-        HttpResponse::build(status_code).json(error_response)
+        let mut response = HttpResponse::build(status_code);
+
+        if let Error::Unauthorized = self {
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+
+        response.json(error_response)
     }
 }
 
 pub type WebResult<T> = Result<T, Error>;
+
+/// Time to wait for the device answer
+const DEVICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait for the device request, transport failures are reported as the gateway errors
+pub async fn device_request<T>(request: impl Future<Output = LibResult<T>>) -> WebResult<T> {
+    time::timeout(DEVICE_TIMEOUT, request)
+        .await
+        .map_err(|_| Error::DeviceTimeout)?
+        .map_err(Error::from)
+}
+
+/// Report the malformed JSON as 400 and the well-formed JSON with invalid values as 422
+pub fn json_error(e: JsonPayloadError, _: &HttpRequest) -> actix_web::Error {
+    match e {
+        JsonPayloadError::Deserialize(e) if e.is_data() => Error::InvalidBody(e.to_string()),
+        e => Error::InvalidRequest(e.to_string()),
+    }
+    .into()
+}
+
+pub fn query_error(e: QueryPayloadError, _: &HttpRequest) -> actix_web::Error {
+    Error::InvalidRequest(e.to_string()).into()
+}
+
+pub fn path_error(e: PathError, _: &HttpRequest) -> actix_web::Error {
+    Error::InvalidRequest(e.to_string()).into()
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use actix_web::{get, web, web::Bytes, HttpResponse};
+use futures::future::join_all;
+use tokio::time;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+use crate::{
+    auth::Viewer,
+    error::device_request,
+    home_list::GlobalContext,
+    types::{WebEvent, WebEventFilter, WebEventKind},
+};
+
+/// Period of the device readings
+const READING_PERIOD: Duration = Duration::from_secs(5);
+
+/// Encode the event as the Server-Sent Events message
+fn sse_message(event: &WebEvent) -> Option<Bytes> {
+    let data = serde_json::to_string(event).ok()?;
+
+    Some(Bytes::from(format!(
+        "event: {}\ndata: {}\n\n",
+        event.event.as_str(),
+        data
+    )))
+}
+
+/// Subscribe to the topology changes and device readings
+#[utoipa::path(
+    tag = "events",
+    params(WebEventFilter),
+    responses(
+        (status = 200, description = "Server-sent events, `data` is the JSON event", body = WebEvent, content_type = "text/event-stream"),
+    )
+)]
+#[get("/events")]
+async fn read_events(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    filter: web::Query<WebEventFilter>,
+) -> HttpResponse {
+    let filter = filter.into_inner();
+    // lagged subscribers miss the dropped events, but keep receiving the new ones
+    let events = BroadcastStream::new(ctx.events.subscribe())
+        .filter_map(Result::ok)
+        .filter(move |event| filter.matches(event) && principal.can_access(&event.home))
+        .filter_map(|event| sse_message(&event))
+        .map(Ok::<_, actix_web::Error>);
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+/// Read temperature and power of all devices once
+///
+/// Devices are read concurrently, so a hung device delays the round at most by the device timeout.
+pub async fn read_devices(ctx: &GlobalContext) {
+    let devices = ctx.all_devices().await;
+
+    join_all(devices.iter().map(|(home, room, device)| async move {
+        let current_temperature = match device.as_temperature_sensor() {
+            Some(sensor) => device_request(sensor.current_temperature()).await.ok(),
+            None => None,
+        };
+        let current_power = match device.as_power_meter() {
+            Some(meter) => device_request(meter.current_power()).await.ok(),
+            None => None,
+        };
+
+        if current_temperature.is_none() && current_power.is_none() {
+            return;
+        }
+
+        ctx.publish(WebEvent {
+            current_temperature,
+            current_power,
+            ..WebEvent::device(WebEventKind::Reading, home, room, device.name())
+        });
+    }))
+    .await;
+}
+
+/// Periodically publish device readings while there are subscribers
+pub fn spawn_reading_publisher(ctx: GlobalContext) {
+    tokio::spawn(async move {
+        let mut interval = time::interval(READING_PERIOD);
+
+        loop {
+            interval.tick().await;
+
+            if ctx.events.receiver_count() > 0 {
+                read_devices(&ctx).await;
+            }
+        }
+    });
+}
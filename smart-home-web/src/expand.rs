@@ -0,0 +1,228 @@
+use std::future::Future;
+
+use futures::future::join_all;
+use smart_home_lib::{error::Result as LibResult, Device, Home, Room};
+
+use crate::{
+    error::{device_request, Error, WebResult},
+    types::{WebDevice, WebDeviceState, WebExpandQuery, WebHome, WebRoom},
+};
+
+/// Nested objects of the representation requested with `?expand=`
+#[derive(Clone, Copy, Default)]
+pub struct Expand {
+    rooms: bool,
+    devices: bool,
+    state: bool,
+}
+
+impl Expand {
+    /// Parse the comma-separated list, every level includes the previous ones
+    pub fn parse(query: &WebExpandQuery) -> WebResult<Self> {
+        let mut expand = Expand::default();
+
+        for value in query.expand.iter().flat_map(|expand| expand.split(',')) {
+            match value.trim() {
+                "" => {}
+                "rooms" => expand.rooms = true,
+                "devices" => expand.devices = true,
+                "state" => expand.state = true,
+                value => {
+                    return Err(Error::InvalidRequest(format!(
+                        "unknown expand value `{}`, expected rooms, devices or state",
+                        value
+                    )))
+                }
+            }
+        }
+
+        expand.devices |= expand.state;
+        expand.rooms |= expand.devices;
+
+        Ok(expand)
+    }
+
+    /// Representation of the home with the requested nested objects
+    pub fn home(self, home: &Home) -> Pending<WebHome> {
+        Pending::build(|pending| self.web_home(home, pending))
+    }
+
+    pub fn homes<'a>(self, homes: impl IntoIterator<Item = &'a Home>) -> Pending<Vec<WebHome>> {
+        Pending::build(|pending| {
+            homes
+                .into_iter()
+                .map(|home| self.web_home(home, pending))
+                .collect()
+        })
+    }
+
+    /// Representation of the room with the requested nested objects
+    pub fn room(self, room: &Room) -> Pending<WebRoom> {
+        Pending::build(|pending| self.web_room(room, pending))
+    }
+
+    pub fn rooms<'a>(self, rooms: impl IntoIterator<Item = &'a Room>) -> Pending<Vec<WebRoom>> {
+        Pending::build(|pending| {
+            rooms
+                .into_iter()
+                .map(|room| self.web_room(room, pending))
+                .collect()
+        })
+    }
+
+    /// Representation of the device with the live state if requested
+    pub fn device(self, device: &Device) -> Pending<WebDevice> {
+        Pending::build(|pending| self.web_device(device, pending))
+    }
+
+    pub fn devices<'a>(
+        self,
+        devices: impl IntoIterator<Item = &'a Device>,
+    ) -> Pending<Vec<WebDevice>> {
+        Pending::build(|pending| {
+            devices
+                .into_iter()
+                .map(|device| self.web_device(device, pending))
+                .collect()
+        })
+    }
+
+    fn web_home(self, home: &Home, pending: &mut Vec<Device>) -> WebHome {
+        WebHome {
+            rooms: self.rooms.then(|| {
+                home.room_iter()
+                    .map(|room| self.web_room(room, pending))
+                    .collect()
+            }),
+            ..home.into()
+        }
+    }
+
+    fn web_room(self, room: &Room, pending: &mut Vec<Device>) -> WebRoom {
+        WebRoom {
+            devices: self.devices.then(|| {
+                room.device_iter()
+                    .map(|device| self.web_device(device, pending))
+                    .collect()
+            }),
+            ..room.into()
+        }
+    }
+
+    fn web_device(self, device: &Device, pending: &mut Vec<Device>) -> WebDevice {
+        if self.state {
+            pending.push(device.clone());
+        }
+
+        device.into()
+    }
+}
+
+/// Representation built while the hub is locked, the live state of the devices is read by
+/// [`Pending::resolve`] after the hub is released
+pub struct Pending<T> {
+    value: T,
+    /// Devices to read in the order of [`DeviceTree::for_each_device`]
+    devices: Vec<Device>,
+}
+
+impl<T: DeviceTree> Pending<T> {
+    fn build(build: impl FnOnce(&mut Vec<Device>) -> T) -> Self {
+        let mut devices = Vec::new();
+        let value = build(&mut devices);
+
+        Self { value, devices }
+    }
+
+    /// Read the state of all devices concurrently
+    pub async fn resolve(self) -> T {
+        let Pending { mut value, devices } = self;
+        let mut states = join_all(devices.iter().map(state)).await.into_iter();
+
+        value.for_each_device(&mut |device| device.state = states.next());
+
+        value
+    }
+}
+
+/// Representation with nested devices
+pub trait DeviceTree {
+    fn for_each_device(&mut self, f: &mut dyn FnMut(&mut WebDevice));
+}
+
+impl DeviceTree for WebDevice {
+    fn for_each_device(&mut self, f: &mut dyn FnMut(&mut WebDevice)) {
+        f(self)
+    }
+}
+
+impl DeviceTree for WebRoom {
+    fn for_each_device(&mut self, f: &mut dyn FnMut(&mut WebDevice)) {
+        self.devices
+            .iter_mut()
+            .for_each(|devices| devices.for_each_device(f))
+    }
+}
+
+impl DeviceTree for WebHome {
+    fn for_each_device(&mut self, f: &mut dyn FnMut(&mut WebDevice)) {
+        self.rooms
+            .iter_mut()
+            .for_each(|rooms| rooms.for_each_device(f))
+    }
+}
+
+impl<T: DeviceTree> DeviceTree for Vec<T> {
+    fn for_each_device(&mut self, f: &mut dyn FnMut(&mut WebDevice)) {
+        self.iter_mut().for_each(|item| item.for_each_device(f))
+    }
+}
+
+/// Value of the supported capability, missing if the device doesn't respond
+async fn reading<T>(request: Option<impl Future<Output = LibResult<T>>>) -> Option<T> {
+    device_request(request?).await.ok()
+}
+
+/// Query all readings of the device concurrently
+async fn state(device: &Device) -> WebDeviceState {
+    let (
+        current_temperature,
+        current_power,
+        current_humidity,
+        current_pressure,
+        active,
+        target_temperature,
+    ) = tokio::join!(
+        reading(
+            device
+                .as_temperature_sensor()
+                .map(|sensor| sensor.current_temperature())
+        ),
+        reading(device.as_power_meter().map(|meter| meter.current_power())),
+        reading(
+            device
+                .as_humidity_sensor()
+                .map(|sensor| sensor.current_humidity())
+        ),
+        reading(
+            device
+                .as_pressure_sensor()
+                .map(|sensor| sensor.current_pressure())
+        ),
+        reading(device.as_binary_state().map(|state| state.is_active())),
+        reading(
+            device
+                .as_temperature_control()
+                .map(|control| control.target_temperature())
+        ),
+    );
+
+    WebDeviceState {
+        current_temperature,
+        current_power,
+        current_humidity,
+        current_pressure,
+        active,
+        target_temperature,
+    }
+}
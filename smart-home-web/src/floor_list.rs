@@ -0,0 +1,143 @@
+use actix_web::{get, web, web::Json};
+use smart_home_lib::{aggregate, Device, Home, Location};
+
+use crate::{
+    auth::Viewer,
+    error::{Error, ErrorResponse, WebResult},
+    home_list::GlobalContext,
+    types::{WebFloor, WebSummary},
+    WebRoom,
+};
+
+fn rooms_in(home: &Home, location: &Location) -> Vec<WebRoom> {
+    home.rooms_in(location).map(|room| room.into()).collect()
+}
+
+/// Aggregate readings of the devices inside the location, the hub is locked only to clone them
+async fn summary(ctx: &GlobalContext, home: &str, location: &Location) -> WebResult<WebSummary> {
+    let devices: Vec<Device> = {
+        let home_list = ctx.home_list.lock().await;
+        let home = home_list.get_home(home).ok_or(Error::HomeNotFound)?;
+
+        home.rooms_in(location)
+            .flat_map(|room| room.device_iter())
+            .cloned()
+            .collect()
+    };
+
+    Ok(WebSummary {
+        average_temperature: aggregate::average_temperature(devices.iter()).await,
+        total_power: aggregate::total_power(devices.iter()).await,
+    })
+}
+
+/// List floors of the home with their zones
+#[utoipa::path(
+    tag = "floor",
+    responses(
+        (status = 200, description = "Floors", body = Vec<WebFloor>),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/")]
+async fn read_floor_list(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<Vec<WebFloor>>> {
+    let home_list = ctx.home_list.lock().await;
+    let (home,) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+
+    Ok(Json(
+        home.floors()
+            .into_iter()
+            .map(|floor| WebFloor {
+                name: floor.to_string(),
+                zones: home.zones(floor).into_iter().map(String::from).collect(),
+            })
+            .collect(),
+    ))
+}
+
+/// List rooms on the floor
+#[utoipa::path(
+    tag = "floor",
+    responses(
+        (status = 200, description = "Rooms on the floor", body = Vec<WebRoom>),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{floor}/")]
+async fn read_floor_rooms(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<Vec<WebRoom>>> {
+    let home_list = ctx.home_list.lock().await;
+    let (home, floor) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+
+    Ok(Json(rooms_in(home, &Location::floor(floor))))
+}
+
+/// Aggregate readings of the floor
+#[utoipa::path(
+    tag = "floor",
+    responses(
+        (status = 200, description = "Floor summary", body = WebSummary),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{floor}/summary")]
+async fn read_floor_summary(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<WebSummary>> {
+    let (home, floor) = path.into_inner();
+
+    Ok(Json(summary(&ctx, &home, &Location::floor(floor)).await?))
+}
+
+/// List rooms in the zone
+#[utoipa::path(
+    tag = "floor",
+    responses(
+        (status = 200, description = "Rooms in the zone", body = Vec<WebRoom>),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{floor}/{zone}/")]
+async fn read_zone_rooms(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String, String)>,
+) -> WebResult<Json<Vec<WebRoom>>> {
+    let home_list = ctx.home_list.lock().await;
+    let (home, floor, zone) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+
+    Ok(Json(rooms_in(home, &Location::zone(floor, zone))))
+}
+
+/// Aggregate readings of the zone
+#[utoipa::path(
+    tag = "floor",
+    responses(
+        (status = 200, description = "Zone summary", body = WebSummary),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{floor}/{zone}/summary")]
+async fn read_zone_summary(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String, String)>,
+) -> WebResult<Json<WebSummary>> {
+    let (home, floor, zone) = path.into_inner();
+
+    Ok(Json(
+        summary(&ctx, &home, &Location::zone(floor, zone)).await?,
+    ))
+}
@@ -0,0 +1,265 @@
+use actix_web::{delete, get, post, put, web, web::Json, HttpRequest, HttpResponse};
+use smart_home_lib::{aggregate, error::Error as LibError, Device, DeviceGroup, GroupMember};
+
+use crate::{
+    auth::{Admin, Operator, Viewer},
+    error::{Error, ErrorResponse, WebResult},
+    home_list::{created, GlobalContext},
+    types::{WebEvent, WebEventKind, WebGroup, WebSummary},
+};
+
+/// Clone the group devices, so they are queried after the hub is released
+async fn group_devices(ctx: &GlobalContext, home: &str, group: &str) -> WebResult<Vec<Device>> {
+    let home_list = ctx.home_list.lock().await;
+    let home = home_list.get_home(home).ok_or(Error::HomeNotFound)?;
+
+    let devices = home.group_devices(group)?.cloned().collect();
+
+    Ok(devices)
+}
+
+/// List device groups of the home
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Device groups", body = Vec<WebGroup>),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/")]
+async fn read_group_list(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<Vec<WebGroup>>> {
+    let home_list = ctx.home_list.lock().await;
+    let (home,) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+
+    Ok(Json(home.group_iter().map(|group| group.into()).collect()))
+}
+
+/// Get the device group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Device group", body = WebGroup),
+        (status = 404, description = "Home or group is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{group}")]
+async fn read_group(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<WebGroup>> {
+    let home_list = ctx.home_list.lock().await;
+    let (home, group) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+    let group = home.group(&group).ok_or(Error::GroupNotFound)?;
+
+    Ok(Json(group.into()))
+}
+
+/// Create an empty device group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 201, description = "Created group, `Location` is its URL", body = WebGroup),
+        (status = 200, description = "Group is already exists", body = WebGroup),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 409, description = "Device group is already exists", body = ErrorResponse),
+    )
+)]
+#[put("/{home}/{group}")]
+async fn create_group(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> WebResult<HttpResponse> {
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home_name, name) = path.into_inner();
+    let home = home_list
+        .get_home_mut(&home_name)
+        .ok_or(Error::HomeNotFound)?;
+
+    if let Some(group) = home.group(&name) {
+        return Ok(HttpResponse::Ok().json(WebGroup::from(group)));
+    }
+
+    let group = home.add_group(DeviceGroup::new(name.as_str()))?;
+
+    let response = created(
+        &req,
+        "read_group",
+        [&home_name, &name],
+        WebGroup::from(&*group),
+    )?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(WebEvent::group(
+        WebEventKind::GroupCreated,
+        &home_name,
+        &name,
+    ));
+
+    Ok(response)
+}
+
+/// Delete the device group, devices are kept
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Deleted group", body = WebGroup),
+        (status = 404, description = "Home or group is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/{home}/{group}")]
+async fn delete_group(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<WebGroup>> {
+    let event = WebEvent::group(WebEventKind::GroupDeleted, &path.0, &path.1);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home, group) = path.into_inner();
+    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
+    let group = home.del_group(&group).ok_or(Error::GroupNotFound)?;
+
+    let response = Json((&group).into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Add the device to the group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Updated group", body = WebGroup),
+        (status = 404, description = "Home, group, room or device is not found", body = ErrorResponse),
+        (status = 409, description = "Device is already in the group", body = ErrorResponse),
+    )
+)]
+#[put("/{home}/{group}/{room}/{device}")]
+async fn add_group_member(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String, String, String)>,
+) -> WebResult<Json<WebGroup>> {
+    let event = WebEvent::group(WebEventKind::GroupUpdated, &path.0, &path.1);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home, group, room, device) = path.into_inner();
+    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
+
+    // the repeated request keeps the member
+    match home.add_group_member(&group, GroupMember::new(room, device)) {
+        Ok(_) | Err(LibError::GroupMemberAlreadyExists) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let response = Json(home.group(&group).ok_or(Error::Internal)?.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Remove the device from the group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Updated group", body = WebGroup),
+        (status = 404, description = "Home, group or member is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/{home}/{group}/{room}/{device}")]
+async fn delete_group_member(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String, String, String)>,
+) -> WebResult<Json<WebGroup>> {
+    let event = WebEvent::group(WebEventKind::GroupUpdated, &path.0, &path.1);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home, group, room, device) = path.into_inner();
+    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
+    home.del_group_member(&group, &GroupMember::new(room, device))?;
+
+    let response = Json(home.group(&group).ok_or(Error::Internal)?.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Turn on all switchable devices of the group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 204, description = "Devices are turned on"),
+        (status = 404, description = "Home or group is not found", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{group}/on")]
+async fn group_on(
+    ctx: web::Data<GlobalContext>,
+    _auth: Operator,
+    path: web::Path<(String, String)>,
+) -> WebResult<HttpResponse> {
+    let (home, group) = path.into_inner();
+    let devices = group_devices(&ctx, &home, &group).await?;
+    aggregate::switch(devices.iter(), true).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Turn off all switchable devices of the group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 204, description = "Devices are turned off"),
+        (status = 404, description = "Home or group is not found", body = ErrorResponse),
+        (status = 502, description = "Device is unreachable", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{group}/off")]
+async fn group_off(
+    ctx: web::Data<GlobalContext>,
+    _auth: Operator,
+    path: web::Path<(String, String)>,
+) -> WebResult<HttpResponse> {
+    let (home, group) = path.into_inner();
+    let devices = group_devices(&ctx, &home, &group).await?;
+    aggregate::switch(devices.iter(), false).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Aggregate readings of the group
+#[utoipa::path(
+    tag = "group",
+    responses(
+        (status = 200, description = "Group summary", body = WebSummary),
+        (status = 404, description = "Home or group is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/{group}/summary")]
+async fn read_group_summary(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<WebSummary>> {
+    let (home, group) = path.into_inner();
+    let devices = group_devices(&ctx, &home, &group).await?;
+
+    Ok(Json(WebSummary {
+        average_temperature: aggregate::average_temperature(devices.iter()).await,
+        total_power: aggregate::total_power(devices.iter()).await,
+    }))
+}
@@ -1,77 +1,368 @@
 use std::sync::Arc;
 
-use actix_web::{delete, get, put, web, web::Json};
-use smart_home_lib::{Home, SmartHub};
-use tokio::sync::Mutex;
+use actix_web::{
+    delete, get, http::header, patch, post, put, web, web::Json, HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+use smart_home_lib::{error::Error as LibError, Device, Home, SmartHub};
+use tokio::sync::{broadcast, Mutex};
 
 use crate::{
-    error::{Error, WebResult},
+    auth::{Admin, AuthConfig, Principal, Viewer},
+    error::{Error, ErrorResponse, WebResult},
+    expand::Expand,
+    metrics::Metrics,
+    snapshot::HubSnapshot,
+    storage::Storage,
+    types::{WebEvent, WebEventKind, WebExpandQuery, WebPosition, WebTagFilter, WebUpdate},
     WebHome,
 };
 
-#[derive(Default)]
+/// Count of the events kept for the slow subscribers
+const EVENT_CAPACITY: usize = 256;
+
 pub struct GlobalContext {
     pub home_list: Arc<Mutex<SmartHub>>,
+    pub storage: Option<Arc<dyn Storage>>,
+    pub events: broadcast::Sender<WebEvent>,
+    pub auth: Arc<AuthConfig>,
+    pub metrics: Arc<Metrics>,
+}
+
+impl Default for GlobalContext {
+    fn default() -> Self {
+        Self {
+            home_list: Default::default(),
+            storage: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            auth: Default::default(),
+            metrics: Default::default(),
+        }
+    }
 }
 
 impl Clone for GlobalContext {
     fn clone(&self) -> Self {
         Self {
             home_list: self.home_list.clone(),
+            storage: self.storage.clone(),
+            events: self.events.clone(),
+            auth: self.auth.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
 
-#[get("/")]
-async fn read_home_list(ctx: web::Data<GlobalContext>) -> Json<Vec<WebHome>> {
-    let home_list = ctx.home_list.lock().await;
+impl GlobalContext {
+    /// Write the changed hub through to the storage, call it with the hub still locked so
+    /// concurrent changes are stored in order. The hub is reset to `previous` (the clone taken
+    /// before the change) if it can't be stored, so the served state matches the stored one.
+    pub async fn persist(&self, home_list: &mut SmartHub, previous: SmartHub) -> WebResult<()> {
+        let result = self.save(home_list).await;
+
+        if result.is_err() {
+            *home_list = previous;
+        }
+        result
+    }
+
+    /// Write the hub to the storage as is
+    pub async fn save(&self, home_list: &SmartHub) -> WebResult<()> {
+        let storage = match &self.storage {
+            Some(storage) => storage.clone(),
+            None => return Ok(()),
+        };
+        let snapshot = HubSnapshot::capture(home_list).await;
+
+        web::block(move || storage.save(&snapshot))
+            .await
+            .map_err(|_| Error::Internal)?
+            .map_err(|e| {
+                log::error!("Failed to store the hub: {}", e);
+                Error::Storage
+            })
+    }
+
+    /// Clone the device from the locked hub, so the device is queried after the hub is released
+    /// and slow devices don't block other requests
+    pub async fn device(&self, home: &str, room: &str, device: &str) -> WebResult<Device> {
+        let home_list = self.home_list.lock().await;
+
+        home_list
+            .get_home(home)
+            .ok_or(Error::HomeNotFound)?
+            .room(room)
+            .ok_or(Error::RoomNotFound)?
+            .device(device)
+            .ok_or(Error::DeviceNotFound)
+            .cloned()
+    }
+
+    /// Clone all devices of the hub with their home and room names
+    pub async fn all_devices(&self) -> Vec<(String, String, Device)> {
+        let home_list = self.home_list.lock().await;
 
-    Json(
         home_list
             .iter()
-            .map(|home| WebHome {
-                name: home.name().to_owned(),
+            .flat_map(|home| {
+                home.room_iter().flat_map(move |room| {
+                    room.device_iter().map(move |device| {
+                        (
+                            home.name().to_string(),
+                            room.name().to_string(),
+                            device.clone(),
+                        )
+                    })
+                })
             })
-            .collect(),
+            .collect()
+    }
+
+    /// Send the event to the subscribers
+    pub fn publish(&self, event: WebEvent) {
+        // there may be no subscribers
+        let _ = self.events.send(event);
+    }
+}
+
+/// 201 Created response with the location of the new resource
+pub fn created<U, I>(
+    req: &HttpRequest,
+    resource: &str,
+    elements: U,
+    body: impl Serialize,
+) -> WebResult<HttpResponse>
+where
+    U: IntoIterator<Item = I>,
+    I: AsRef<str>,
+{
+    let location = req
+        .url_for(resource, elements)
+        .map_err(|_| Error::Internal)?;
+
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, location.as_str()))
+        .json(body))
+}
+
+/// List homes, optionally only with the tag
+#[utoipa::path(
+    tag = "home",
+    params(WebTagFilter, WebExpandQuery),
+    responses(
+        (status = 200, description = "Homes in the user-defined order", body = Vec<WebHome>),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
     )
+)]
+#[get("/")]
+async fn read_home_list(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    filter: web::Query<WebTagFilter>,
+    expand: web::Query<WebExpandQuery>,
+) -> WebResult<Json<Vec<WebHome>>> {
+    let expand = Expand::parse(&expand)?;
+    let home_list = ctx.home_list.lock().await;
+
+    let homes: Vec<&Home> = match &filter.tag {
+        Some(tag) => home_list.homes_with_tag(tag).collect(),
+        None => home_list.iter().collect(),
+    };
+
+    let pending = expand.homes(
+        homes
+            .into_iter()
+            .filter(|home| principal.can_access(home.name())),
+    );
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
+/// Get the home
+#[utoipa::path(
+    tag = "home",
+    params(WebExpandQuery),
+    responses(
+        (status = 200, description = "Home", body = WebHome),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
 #[get("/{home}")]
 async fn read_home(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String,)>,
+    expand: web::Query<WebExpandQuery>,
 ) -> WebResult<Json<WebHome>> {
+    let expand = Expand::parse(&expand)?;
     let home_list = ctx.home_list.lock().await;
     let (name,) = path.into_inner();
     let home = home_list.get_home(&name).ok_or(Error::HomeNotFound)?;
 
-    Ok(Json(home.into()))
+    let pending = expand.home(home);
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
+/// Create an empty home
+#[utoipa::path(
+    tag = "home",
+    responses(
+        (status = 201, description = "Created home, `Location` is its URL", body = WebHome),
+        (status = 200, description = "Home is already exists", body = WebHome),
+        (status = 409, description = "Home is already exists", body = ErrorResponse),
+    )
+)]
 #[put("/{home}")]
 async fn create_home(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
     path: web::Path<(String,)>,
+) -> WebResult<HttpResponse> {
+    let event = WebEvent::home(WebEventKind::HomeCreated, &path.0);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (name,) = path.into_inner();
+
+    if let Some(home) = home_list.get_home(&name) {
+        return Ok(HttpResponse::Ok().json(WebHome::from(home)));
+    }
+
+    let home = home_list.add_home(Home::new(&name))?;
+
+    let response = created(&req, "read_home", [&name], WebHome::from(home))?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Rename the home or replace its tags and properties
+#[utoipa::path(
+    tag = "home",
+    responses(
+        (status = 200, description = "Updated home", body = WebHome),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 409, description = "Home with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/{home}")]
+async fn update_home(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebHome>> {
+    let (home,) = path.into_inner();
+
+    update_home_by_name(&ctx, &principal, home, update.into_inner()).await
+}
+
+/// Update of the home found by the name or the id
+pub async fn update_home_by_name(
+    ctx: &GlobalContext,
+    principal: &Principal,
+    mut home: String,
+    mut update: WebUpdate,
 ) -> WebResult<Json<WebHome>> {
     let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let mut events = Vec::new();
+
+    if let Some(name) = update.name.take() {
+        // a client limited to some homes can't rename the home out of them
+        principal.check_home(&name)?;
+        home_list.rename_home(&home, &name).map_err(|e| match e {
+            LibError::NameAlreadyTaken(_) => Error::HomeAlreadyExists,
+            LibError::EmptyName => Error::EmptyName,
+            _ => Error::HomeNotFound,
+        })?;
+        events.push(WebEvent::home(WebEventKind::HomeRenamed, &home).renamed(&name));
+        home = name;
+    }
+
+    let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
+    if update.apply(home.metadata_mut()) {
+        events.push(WebEvent::home(WebEventKind::HomeUpdated, home.name()));
+    }
+
+    let response = Json(home.into());
+    ctx.persist(&mut home_list, previous).await?;
+    events.into_iter().for_each(|event| ctx.publish(event));
+
+    Ok(response)
+}
+
+/// Move the home in the list
+#[utoipa::path(
+    tag = "home",
+    responses(
+        (status = 200, description = "Homes in the new order", body = Vec<WebHome>),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 422, description = "Position is out of range", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/position")]
+async fn set_home_position(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String,)>,
+    position: web::Json<WebPosition>,
+) -> WebResult<Json<Vec<WebHome>>> {
+    let event = WebEvent::home(WebEventKind::HomeUpdated, &path.0);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
     let (home,) = path.into_inner();
 
-    let home = home_list
-        .add_home(Home::new(&home))
-        .map_err(|_| Error::HomeAlreadyExists)?;
+    home_list
+        .set_home_position(&home, position.position)
+        .map_err(|e| match e {
+            LibError::InvalidPosition => Error::InvalidPosition,
+            _ => Error::HomeNotFound,
+        })?;
+
+    let response = Json(home_list.iter().map(|home| home.into()).collect());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
 
-    Ok(Json(home.into()))
+    Ok(response)
 }
 
+/// Delete the home with all rooms and devices
+#[utoipa::path(
+    tag = "home",
+    responses(
+        (status = 200, description = "Deleted home", body = WebHome),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
 #[delete("/{home}")]
 async fn delete_home(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
     path: web::Path<(String,)>,
 ) -> WebResult<Json<WebHome>> {
-    let home_list = &mut ctx.home_list.lock().await;
     let (name,) = path.into_inner();
 
-    let home = home_list.del_home(&name).ok_or(Error::HomeNotFound)?;
+    delete_home_by_name(&ctx, &name).await
+}
+
+/// Deletion of the home found by the name or the id
+pub async fn delete_home_by_name(ctx: &GlobalContext, name: &str) -> WebResult<Json<WebHome>> {
+    let event = WebEvent::home(WebEventKind::HomeDeleted, name);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+
+    let home = home_list.del_home(name).ok_or(Error::HomeNotFound)?;
+
+    let response = Json(home.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
 
-    Ok(Json(home.into()))
+    Ok(response)
 }
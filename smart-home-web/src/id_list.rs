@@ -0,0 +1,272 @@
+use actix_web::{delete, get, patch, web, web::Json};
+use smart_home_lib::Id;
+
+use crate::{
+    auth::{Admin, Principal, Viewer},
+    device_list::{delete_device_by_name, update_device_by_name},
+    error::{Error, ErrorResponse, WebResult},
+    home_list::{delete_home_by_name, update_home_by_name, GlobalContext},
+    room_list::{delete_room_by_name, update_room_by_name},
+    types::{WebDevicePath, WebRoomPath, WebUpdate},
+    WebDevice, WebHome, WebRoom,
+};
+
+fn parse_id(id: &str) -> WebResult<Id> {
+    Id::parse_str(id).map_err(|_| Error::InvalidId)
+}
+
+/// Name of the home with the id, the home must be available to the client
+async fn home_name(ctx: &GlobalContext, principal: &Principal, id: &str) -> WebResult<String> {
+    let home_list = ctx.home_list.lock().await;
+    let home = home_list
+        .home_by_id(parse_id(id)?)
+        .ok_or(Error::HomeNotFound)?;
+    principal.check_home(home.name())?;
+
+    Ok(home.name().to_string())
+}
+
+/// Home and room names of the room with the id
+async fn room_names(
+    ctx: &GlobalContext,
+    principal: &Principal,
+    id: &str,
+) -> WebResult<(String, String)> {
+    let home_list = ctx.home_list.lock().await;
+    let (home, room) = home_list
+        .room_by_id(parse_id(id)?)
+        .ok_or(Error::RoomNotFound)?;
+    principal.check_home(home.name())?;
+
+    Ok((home.name().to_string(), room.name().to_string()))
+}
+
+/// Home, room and device names of the device with the id
+async fn device_names(
+    ctx: &GlobalContext,
+    principal: &Principal,
+    id: &str,
+) -> WebResult<(String, String, String)> {
+    let home_list = ctx.home_list.lock().await;
+    let path = home_list
+        .device_by_id(parse_id(id)?)
+        .ok_or(Error::DeviceNotFound)?;
+    principal.check_home(path.home.name())?;
+
+    Ok((
+        path.home.name().to_string(),
+        path.room.name().to_string(),
+        path.device.name().to_string(),
+    ))
+}
+
+/// Get the home by id
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Home", body = WebHome),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+    )
+)]
+#[get("/home/{id}")]
+async fn read_home_by_id(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebHome>> {
+    let home_list = ctx.home_list.lock().await;
+    let (id,) = path.into_inner();
+    let home = home_list
+        .home_by_id(parse_id(&id)?)
+        .ok_or(Error::HomeNotFound)?;
+    principal.check_home(home.name())?;
+
+    Ok(Json(home.into()))
+}
+
+/// Get the room by id with its home
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Room", body = WebRoomPath),
+        (status = 404, description = "Room is not found", body = ErrorResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+    )
+)]
+#[get("/room/{id}")]
+async fn read_room_by_id(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebRoomPath>> {
+    let home_list = ctx.home_list.lock().await;
+    let (id,) = path.into_inner();
+    let (home, room) = home_list
+        .room_by_id(parse_id(&id)?)
+        .ok_or(Error::RoomNotFound)?;
+    principal.check_home(home.name())?;
+
+    Ok(Json(WebRoomPath {
+        home: home.name().to_string(),
+        room: room.into(),
+    }))
+}
+
+/// Get the device by id with its home and room
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Device", body = WebDevicePath),
+        (status = 404, description = "Device is not found", body = ErrorResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+    )
+)]
+#[get("/device/{id}")]
+async fn read_device_by_id(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebDevicePath>> {
+    let home_list = ctx.home_list.lock().await;
+    let (id,) = path.into_inner();
+    let path = home_list
+        .device_by_id(parse_id(&id)?)
+        .ok_or(Error::DeviceNotFound)?;
+    principal.check_home(path.home.name())?;
+
+    Ok(Json(WebDevicePath {
+        home: path.home.name().to_string(),
+        room: path.room.name().to_string(),
+        device: path.device.into(),
+    }))
+}
+
+/// Rename the home by id or replace its tags and properties
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Updated home", body = WebHome),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 409, description = "Home with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/home/{id}")]
+async fn update_home_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebHome>> {
+    let home = home_name(&ctx, &principal, &path.0).await?;
+
+    update_home_by_name(&ctx, &principal, home, update.into_inner()).await
+}
+
+/// Delete the home by id with all rooms and devices
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Deleted home", body = WebHome),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/home/{id}")]
+async fn delete_home_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebHome>> {
+    let home = home_name(&ctx, &principal, &path.0).await?;
+
+    delete_home_by_name(&ctx, &home).await
+}
+
+/// Rename the room by id or replace its tags and properties
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Updated room", body = WebRoom),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Room is not found", body = ErrorResponse),
+        (status = 409, description = "Room with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/room/{id}")]
+async fn update_room_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebRoom>> {
+    let (home, room) = room_names(&ctx, &principal, &path.0).await?;
+
+    update_room_by_name(&ctx, &home, room, update.into_inner()).await
+}
+
+/// Delete the room by id with all devices
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Deleted room", body = WebRoom),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Room is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/room/{id}")]
+async fn delete_room_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebRoom>> {
+    let (home, room) = room_names(&ctx, &principal, &path.0).await?;
+
+    delete_room_by_name(&ctx, &home, &room).await
+}
+
+/// Rename the device by id or replace its tags and properties
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Updated device", body = WebDevice),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Device is not found", body = ErrorResponse),
+        (status = 409, description = "Device with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/device/{id}")]
+async fn update_device_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebDevice>> {
+    let (home, room, device) = device_names(&ctx, &principal, &path.0).await?;
+
+    update_device_by_name(&ctx, &home, &room, device, update.into_inner()).await
+}
+
+/// Delete the device by id
+#[utoipa::path(
+    tag = "id",
+    responses(
+        (status = 200, description = "Deleted device", body = WebDevice),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 404, description = "Device is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/device/{id}")]
+async fn delete_device_by_id(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    path: web::Path<(String,)>,
+) -> WebResult<Json<WebDevice>> {
+    let (home, room, device) = device_names(&ctx, &principal, &path.0).await?;
+
+    delete_device_by_name(&ctx, &home, &room, &device).await
+}
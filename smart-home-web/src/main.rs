@@ -1,52 +1,139 @@
-use actix_web::{web, App, HttpServer};
+use std::{fs, path::Path, sync::Arc};
+
+use actix_web::{middleware::from_fn, web, App, HttpServer};
+use smart_home_lib::{HealthChecker, HealthConfig};
+use snapshot::HubSnapshot;
+use tokio::sync::Mutex;
 use types::{WebDevice, WebHome, WebRoom};
+use utoipa::OpenApi;
+use utoipa_actix_web::AppExt;
 
 use self::{
-    device_list::{
-        create_socket, create_thermometer, delete_device, get_current_power,
-        get_current_temperature, read_device, read_device_list, socket_off, socket_on,
-    },
-    home_list::{create_home, delete_home, read_home, read_home_list, GlobalContext},
-    room_list::{create_room, delete_room, read_room, read_room_list},
+    auth::authenticate,
+    config::Config,
+    events::spawn_reading_publisher,
+    home_list::GlobalContext,
+    metrics::{spawn_metrics_collector, track_requests, Metrics},
+    openapi::{configure, ApiDoc},
 };
 
+mod auth;
+mod config;
 mod device_list;
 mod error;
+mod events;
+mod expand;
+mod floor_list;
+mod group_list;
 mod home_list;
+mod id_list;
+mod metrics;
+mod openapi;
 mod room_list;
+mod search;
+mod snapshot;
+mod storage;
+#[cfg(test)]
+mod tests;
+mod topology;
 mod types;
 
+/// Load the stored homes and keep the storage for the write-through
+async fn open_storage(path: &Path) -> Result<GlobalContext, Box<dyn std::error::Error>> {
+    let storage = storage::open(path)?;
+    let home_list = storage.load()?.restore().await?;
+
+    log::info!(
+        "Loaded {} homes from {}",
+        home_list.iter().count(),
+        path.display()
+    );
+
+    Ok(GlobalContext {
+        home_list: Arc::new(Mutex::new(home_list)),
+        storage: Some(Arc::from(storage)),
+        ..Default::default()
+    })
+}
+
+/// Create homes from the topology file if there are no homes yet
+async fn apply_topology(
+    ctx: &GlobalContext,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut home_list = ctx.home_list.lock().await;
+
+    if home_list.iter().next().is_some() {
+        log::info!("Homes are already stored, skip {}", path.display());
+        return Ok(());
+    }
+
+    let topology: HubSnapshot = serde_json::from_slice(&fs::read(path)?)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    *home_list = topology.restore().await?;
+    ctx.save(&home_list).await?;
+
+    log::info!(
+        "Created {} homes from {}",
+        home_list.iter().count(),
+        path.display()
+    );
+
+    Ok(())
+}
+
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init();
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+
+    env_logger::Builder::new()
+        .parse_filters(&config.log_level)
+        .init();
+
+    let mut ctx = match &config.store {
+        Some(path) => open_storage(path).await?,
+        None => GlobalContext::default(),
+    };
+
+    if !config.auth.is_enabled() {
+        log::warn!("Authentication is disabled, any client has the admin role");
+    }
+    ctx.auth = Arc::new(config.auth.clone());
+    ctx.metrics = Arc::new(Metrics::new(config.metrics_interval.is_none()));
 
-    let ctx = GlobalContext::default();
+    if let Some(path) = &config.topology {
+        apply_topology(&ctx, path).await?;
+    }
+    let _health_checker = HealthChecker::spawn(ctx.home_list.clone(), HealthConfig::default());
+    spawn_reading_publisher(ctx.clone());
+    if let Some(period) = config.metrics_interval {
+        spawn_metrics_collector(ctx.clone(), period);
+    }
 
-    HttpServer::new(move || {
+    let mut server = HttpServer::new(move || {
         let ctx = ctx.clone();
 
-        App::new().app_data(web::Data::new(ctx)).service(
-            web::scope("/home")
-                .service(read_home_list)
-                .service(read_home)
-                .service(create_home)
-                .service(delete_home)
-                .service(read_room_list)
-                .service(read_room)
-                .service(create_room)
-                .service(delete_room)
-                .service(read_device_list)
-                .service(read_device)
-                .service(create_thermometer)
-                .service(create_socket)
-                .service(delete_device)
-                .service(get_current_temperature)
-                .service(get_current_power)
-                .service(socket_on)
-                .service(socket_off),
-        )
-    })
-    .bind(("127.0.0.1", 4080))?
-    .run()
-    .await
+        let (app, document) = App::new()
+            .into_utoipa_app()
+            .openapi(ApiDoc::openapi())
+            .app_data(web::Data::new(ctx))
+            .configure(configure)
+            .split_for_parts();
+
+        app.app_data(web::Data::new(document))
+            .wrap(from_fn(authenticate))
+            .wrap(from_fn(track_requests))
+    });
+
+    if let Some(workers) = config.workers {
+        server = server.workers(workers);
+    }
+
+    log::info!("Listen on {}:{}", config.bind, config.port);
+    server
+        .bind((config.bind.as_str(), config.port))?
+        .run()
+        .await?;
+
+    Ok(())
 }
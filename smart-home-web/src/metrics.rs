@@ -0,0 +1,237 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    middleware::Next,
+    web, HttpResponse,
+};
+use futures::future::join_all;
+use prometheus::{Encoder, GaugeVec, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder};
+use smart_home_lib::{Device, HealthStatus};
+use tokio::time;
+
+use crate::{
+    auth::Viewer,
+    error::{device_request, Error, ErrorResponse, WebResult},
+    home_list::GlobalContext,
+};
+
+/// Labels of the device gauges
+const DEVICE_LABELS: &[&str] = &["home", "room", "device"];
+
+/// Device gauges and server metrics in the Prometheus format
+pub struct Metrics {
+    registry: Registry,
+    /// Devices are read on every scrape if there is no background collector
+    collect_on_scrape: bool,
+    /// Held while the device gauges are replaced or gathered, so overlapping scrapes and the
+    /// background collector never see them half-filled
+    gauges_lock: Mutex<()>,
+    temperature: GaugeVec,
+    power: GaugeVec,
+    socket_on: GaugeVec,
+    device_up: GaugeVec,
+    http_request_duration: HistogramVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl Metrics {
+    pub fn new(collect_on_scrape: bool) -> Self {
+        let registry = Registry::new();
+        let device_gauge = |name: &str, help: &str| {
+            let gauge = GaugeVec::new(Opts::new(name, help), DEVICE_LABELS).expect("valid gauge");
+            registry
+                .register(Box::new(gauge.clone()))
+                .expect("unique gauge");
+            gauge
+        };
+
+        let temperature = device_gauge(
+            "smart_home_temperature_celsius",
+            "Current temperature of the device",
+        );
+        let power = device_gauge("smart_home_power_watts", "Current power of the device");
+        let socket_on = device_gauge(
+            "smart_home_socket_on",
+            "1 if the socket uses power, 0 otherwise; the socket protocol has no on/off state, so \
+             a socket which is on but draws no power is reported as off",
+        );
+        let device_up = device_gauge(
+            "smart_home_device_up",
+            "1 if the health checker reports the device online, 0 otherwise",
+        );
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "smart_home_http_request_duration_seconds",
+                "Latency of the HTTP requests",
+            ),
+            &["method", "path", "status"],
+        )
+        .expect("valid histogram");
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .expect("unique histogram");
+
+        Self {
+            registry,
+            collect_on_scrape,
+            gauges_lock: Mutex::new(()),
+            temperature,
+            power,
+            socket_on,
+            device_up,
+            http_request_duration,
+        }
+    }
+
+    /// Encode all metrics in the text format
+    fn encode(&self) -> WebResult<(String, Vec<u8>)> {
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        let metrics = {
+            let _gauges = self.gauges_lock.lock().expect("gauges lock");
+            self.registry.gather()
+        };
+
+        encoder.encode(&metrics, &mut buffer).map_err(|e| {
+            log::error!("Failed to encode metrics: {}", e);
+            Error::Internal
+        })?;
+
+        Ok((encoder.format_type().to_string(), buffer))
+    }
+
+    /// Replace the device gauges with the current readings, removed devices disappear
+    pub async fn collect_devices(&self, devices: &[(String, String, Device)]) {
+        let readings = join_all(devices.iter().map(|(home, room, device)| async move {
+            let temperature = match device.as_temperature_sensor() {
+                Some(sensor) => device_request(sensor.current_temperature()).await.ok(),
+                None => None,
+            };
+            let power = match device.as_power_meter() {
+                Some(meter) => device_request(meter.current_power()).await.ok(),
+                None => None,
+            };
+
+            (
+                [home.as_str(), room.as_str(), device.name()],
+                device.as_switchable().is_some(),
+                device.health().status == HealthStatus::Online,
+                temperature,
+                power,
+            )
+        }))
+        .await;
+
+        let _gauges = self.gauges_lock.lock().expect("gauges lock");
+        self.temperature.reset();
+        self.power.reset();
+        self.socket_on.reset();
+        self.device_up.reset();
+
+        for (labels, switchable, up, temperature, power) in readings {
+            self.device_up
+                .with_label_values(&labels)
+                .set(if up { 1.0 } else { 0.0 });
+
+            if let Some(temperature) = temperature {
+                self.temperature.with_label_values(&labels).set(temperature);
+            }
+            if let Some(power) = power {
+                self.power.with_label_values(&labels).set(power);
+
+                // the socket protocol has no state, but the socket uses no power while off
+                if switchable {
+                    self.socket_on
+                        .with_label_values(&labels)
+                        .set(if power > 0.0 { 1.0 } else { 0.0 });
+                }
+            }
+        }
+    }
+
+    fn observe_request(&self, method: &str, path: &str, status: u16, elapsed: Duration) {
+        self.http_request_duration
+            .with_label_values(&[method, path, &status.to_string()])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Middleware to measure the latency of the requests, the path is labeled with the route pattern
+/// to keep the count of the series small
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let ctx = req.app_data::<web::Data<GlobalContext>>().cloned();
+    let method = req.method().to_string();
+    let path = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let res = next.call(req).await;
+
+    if let Some(ctx) = ctx {
+        let status = match &res {
+            Ok(res) => res.status(),
+            Err(e) => e.as_response_error().status_code(),
+        };
+
+        ctx.metrics
+            .observe_request(&method, &path, status.as_u16(), started.elapsed());
+    }
+
+    res
+}
+
+/// Scrape the device gauges and the server metrics, the device gauges are available only to the
+/// clients with access to all homes
+#[utoipa::path(
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 403, description = "Client has access only to some homes", body = ErrorResponse),
+    )
+)]
+#[get("/metrics")]
+async fn read_metrics(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+) -> WebResult<HttpResponse> {
+    if !principal.has_all_homes() {
+        return Err(Error::Forbidden);
+    }
+
+    if ctx.metrics.collect_on_scrape {
+        ctx.metrics.collect_devices(&ctx.all_devices().await).await;
+    }
+
+    let (content_type, body) = ctx.metrics.encode()?;
+
+    Ok(HttpResponse::Ok().content_type(content_type).body(body))
+}
+
+/// Periodically read the devices, so the scrapes don't query them
+pub fn spawn_metrics_collector(ctx: GlobalContext, period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            ctx.metrics.collect_devices(&ctx.all_devices().await).await;
+        }
+    });
+}
@@ -0,0 +1,237 @@
+use actix_web::{get, web, web::Json};
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiDocument,
+    },
+    Modify, OpenApi,
+};
+use utoipa_actix_web::{scope, service_config::ServiceConfig};
+
+use crate::{
+    device_list::{
+        create_socket, create_thermometer, create_thermostat, delete_device, get_current_power,
+        get_current_temperature, get_thermostat_state, move_device, read_device, read_device_list,
+        set_device_position, set_thermostat_mode, set_thermostat_target, socket_off, socket_on,
+        update_device,
+    },
+    error::{json_error, path_error, query_error},
+    events::read_events,
+    floor_list::{
+        read_floor_list, read_floor_rooms, read_floor_summary, read_zone_rooms, read_zone_summary,
+    },
+    group_list::{
+        add_group_member, create_group, delete_group, delete_group_member, group_off, group_on,
+        read_group, read_group_list, read_group_summary,
+    },
+    home_list::{
+        create_home, delete_home, read_home, read_home_list, set_home_position, update_home,
+    },
+    id_list::{
+        delete_device_by_id, delete_home_by_id, delete_room_by_id, read_device_by_id,
+        read_home_by_id, read_room_by_id, update_device_by_id, update_home_by_id,
+        update_room_by_id,
+    },
+    metrics::read_metrics,
+    room_list::{
+        create_room, delete_room, read_room, read_room_list, set_room_location, set_room_position,
+        update_room,
+    },
+    search::search_devices,
+    topology::{export_home, import_home},
+};
+
+/// Static part of the document, paths and schemas are collected from the registered services
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Smart Home",
+        description = "Homes, rooms and devices of the smart home"
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = []))
+)]
+pub struct ApiDoc;
+
+/// API key or JWT in the `Authorization` header, required if the authentication is configured
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
+
+/// Register all documented services
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(json_error))
+        .app_data(web::QueryConfig::default().error_handler(query_error))
+        .app_data(web::PathConfig::default().error_handler(path_error))
+        .service(read_openapi)
+        .service(search_devices)
+        .service(read_events)
+        .service(read_metrics)
+        .service(
+            scope::scope("/id")
+                .service(read_home_by_id)
+                .service(update_home_by_id)
+                .service(delete_home_by_id)
+                .service(read_room_by_id)
+                .service(update_room_by_id)
+                .service(delete_room_by_id)
+                .service(read_device_by_id)
+                .service(update_device_by_id)
+                .service(delete_device_by_id),
+        )
+        .service(
+            scope::scope("/floor")
+                .service(read_floor_list)
+                .service(read_floor_rooms)
+                .service(read_floor_summary)
+                .service(read_zone_rooms)
+                .service(read_zone_summary),
+        )
+        .service(
+            scope::scope("/group")
+                .service(read_group_list)
+                .service(read_group)
+                .service(create_group)
+                .service(delete_group)
+                .service(add_group_member)
+                .service(delete_group_member)
+                .service(group_on)
+                .service(group_off)
+                .service(read_group_summary),
+        )
+        .service(
+            scope::scope("/home")
+                .service(import_home)
+                .service(read_home_list)
+                .service(read_home)
+                .service(create_home)
+                .service(update_home)
+                .service(set_home_position)
+                .service(delete_home)
+                .service(read_room_list)
+                // literal paths go before `{room}` and `{device}`, so they aren't taken for names
+                // and the request metrics get their pattern
+                .service(export_home)
+                .service(read_room)
+                .service(create_room)
+                .service(update_room)
+                .service(set_room_position)
+                .service(set_room_location)
+                .service(delete_room)
+                .service(create_thermometer)
+                .service(create_socket)
+                .service(create_thermostat)
+                .service(read_device_list)
+                .service(read_device)
+                .service(update_device)
+                .service(move_device)
+                .service(set_device_position)
+                .service(delete_device)
+                .service(get_current_temperature)
+                .service(get_current_power)
+                .service(socket_on)
+                .service(socket_off)
+                .service(get_thermostat_state)
+                .service(set_thermostat_target)
+                .service(set_thermostat_mode),
+        );
+}
+
+/// OpenAPI document of the API
+///
+/// The document is added to the app data after it is collected from the services.
+#[utoipa::path(
+    tag = "openapi",
+    responses(
+        (status = 200, description = "OpenAPI 3 document", body = Object, content_type = "application/json"),
+    )
+)]
+#[get("/openapi.json")]
+async fn read_openapi(document: web::Data<OpenApiDocument>) -> Json<OpenApiDocument> {
+    Json(document.as_ref().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{Method, StatusCode},
+        test, App, HttpResponse,
+    };
+    use utoipa_actix_web::AppExt;
+
+    use super::*;
+    use crate::home_list::GlobalContext;
+
+    /// Services are documented when they are registered by [`configure`], check that every
+    /// documented operation is routed to a service and has a successful response
+    #[actix_web::test]
+    async fn every_documented_operation_is_routed() {
+        let (app, document) = App::new()
+            .into_utoipa_app()
+            .openapi(ApiDoc::openapi())
+            .app_data(web::Data::new(GlobalContext::default()))
+            .configure(configure)
+            .split_for_parts();
+        // unmatched requests get the status which the services never return
+        let app = test::init_service(
+            app.app_data(web::Data::new(document.clone()))
+                .default_service(web::to(|| async {
+                    HttpResponse::NotImplemented().finish()
+                })),
+        )
+        .await;
+
+        assert!(document.paths.paths.contains_key("/openapi.json"));
+
+        for (pattern, item) in &document.paths.paths {
+            let uri = pattern.replace(['{', '}'], "");
+            let methods = [
+                (Method::GET, &item.get),
+                (Method::PUT, &item.put),
+                (Method::POST, &item.post),
+                (Method::PATCH, &item.patch),
+                (Method::DELETE, &item.delete),
+            ];
+
+            for (method, operation) in methods {
+                let operation = match operation {
+                    Some(operation) => operation,
+                    None => continue,
+                };
+                let request = test::TestRequest::default()
+                    .method(method.clone())
+                    .uri(&uri)
+                    .to_request();
+                let response = test::call_service(&app, request).await;
+
+                assert_ne!(
+                    response.status(),
+                    StatusCode::NOT_IMPLEMENTED,
+                    "{} {} is not routed",
+                    method,
+                    pattern
+                );
+                assert!(
+                    operation
+                        .responses
+                        .responses
+                        .keys()
+                        .any(|status| status.starts_with('2')),
+                    "{} {} has no successful response",
+                    method,
+                    pattern
+                );
+            }
+        }
+    }
+}
@@ -1,68 +1,291 @@
-use actix_web::{delete, get, put, web, web::Json};
-use smart_home_lib::Room;
+use actix_web::{delete, get, patch, post, put, web, web::Json, HttpRequest, HttpResponse};
+use smart_home_lib::{error::Error as LibError, Location, Room};
 
 use crate::{
-    error::{Error, WebResult},
-    home_list::GlobalContext,
+    auth::{Admin, Viewer},
+    error::{Error, ErrorResponse, WebResult},
+    expand::Expand,
+    home_list::{created, GlobalContext},
+    types::{
+        WebEvent, WebEventKind, WebExpandQuery, WebLocation, WebPosition, WebTagFilter, WebUpdate,
+    },
     WebRoom,
 };
 
+/// List rooms of the home, optionally only with the tag
+#[utoipa::path(
+    tag = "room",
+    params(WebTagFilter, WebExpandQuery),
+    responses(
+        (status = 200, description = "Rooms in the user-defined order", body = Vec<WebRoom>),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/")]
 async fn read_room_list(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String,)>,
+    filter: web::Query<WebTagFilter>,
+    expand: web::Query<WebExpandQuery>,
 ) -> WebResult<Json<Vec<WebRoom>>> {
+    let expand = Expand::parse(&expand)?;
     let home_list = ctx.home_list.lock().await;
     let (home,) = path.into_inner();
     let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
 
-    Ok(Json(
-        home.room_iter()
-            .map(|room| WebRoom {
-                name: room.name().to_string(),
-            })
-            .collect(),
-    ))
+    let rooms: Vec<&Room> = match &filter.tag {
+        Some(tag) => home.rooms_with_tag(tag).collect(),
+        None => home.room_iter().collect(),
+    };
+
+    let pending = expand.rooms(rooms);
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
+/// Get the room
+#[utoipa::path(
+    tag = "room",
+    params(WebExpandQuery),
+    responses(
+        (status = 200, description = "Room", body = WebRoom),
+        (status = 400, description = "Unknown `expand` value", body = ErrorResponse),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+    )
+)]
 #[get("/{home}/{room}")]
 async fn read_room(
     ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
     path: web::Path<(String, String)>,
+    expand: web::Query<WebExpandQuery>,
 ) -> WebResult<Json<WebRoom>> {
+    let expand = Expand::parse(&expand)?;
     let home_list = ctx.home_list.lock().await;
     let (home, room) = path.into_inner();
     let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
     let room = home.room(&room).ok_or(Error::RoomNotFound)?;
 
-    Ok(Json(room.into()))
+    let pending = expand.room(room);
+    drop(home_list);
+
+    Ok(Json(pending.resolve().await))
 }
 
+/// Create an empty room
+#[utoipa::path(
+    tag = "room",
+    responses(
+        (status = 201, description = "Created room, `Location` is its URL", body = WebRoom),
+        (status = 200, description = "Room is already exists", body = WebRoom),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+        (status = 409, description = "Room is already exists", body = ErrorResponse),
+    )
+)]
 #[put("/{home}/{room}")]
 async fn create_room(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> WebResult<HttpResponse> {
+    let event = WebEvent::room(WebEventKind::RoomCreated, &path.0, &path.1);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let (home_name, name) = path.into_inner();
+    let home = home_list
+        .get_home_mut(&home_name)
+        .ok_or(Error::HomeNotFound)?;
+
+    if let Some(room) = home.room(&name) {
+        return Ok(HttpResponse::Ok().json(WebRoom::from(room)));
+    }
+
+    let room = home.add_room(Room::new(&name))?;
+
+    let response = created(&req, "read_room", [&home_name, &name], WebRoom::from(room))?;
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Rename the room or replace its tags and properties
+#[utoipa::path(
+    tag = "room",
+    responses(
+        (status = 200, description = "Updated room", body = WebRoom),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 409, description = "Room with the new name is already exists", body = ErrorResponse),
+        (status = 422, description = "New name is empty", body = ErrorResponse),
+    )
+)]
+#[patch("/{home}/{room}")]
+async fn update_room(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
     path: web::Path<(String, String)>,
+    update: web::Json<WebUpdate>,
+) -> WebResult<Json<WebRoom>> {
+    let (home, room) = path.into_inner();
+
+    update_room_by_name(&ctx, &home, room, update.into_inner()).await
+}
+
+/// Update of the room found by the name or the id
+pub async fn update_room_by_name(
+    ctx: &GlobalContext,
+    home: &str,
+    mut room: String,
+    mut update: WebUpdate,
 ) -> WebResult<Json<WebRoom>> {
     let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let home = home_list.get_home_mut(home).ok_or(Error::HomeNotFound)?;
+    let home_name = home.name().to_string();
+    let mut events = Vec::new();
+
+    if let Some(name) = update.name.take() {
+        home.rename_room(&room, &name).map_err(|e| match e {
+            LibError::NameAlreadyTaken(_) => Error::RoomAlreadyExists,
+            LibError::EmptyName => Error::EmptyName,
+            _ => Error::RoomNotFound,
+        })?;
+        events.push(WebEvent::room(WebEventKind::RoomRenamed, &home_name, &room).renamed(&name));
+        room = name;
+    }
+
+    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
+    if update.apply(room.metadata_mut()) {
+        events.push(WebEvent::room(
+            WebEventKind::RoomUpdated,
+            &home_name,
+            room.name(),
+        ));
+    }
+
+    let response = Json(room.into());
+    ctx.persist(&mut home_list, previous).await?;
+    events.into_iter().for_each(|event| ctx.publish(event));
+
+    Ok(response)
+}
+
+/// Move the room in the list
+#[utoipa::path(
+    tag = "room",
+    responses(
+        (status = 200, description = "Rooms in the new order", body = Vec<WebRoom>),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 422, description = "Position is out of range", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/position")]
+async fn set_room_position(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String)>,
+    position: web::Json<WebPosition>,
+) -> WebResult<Json<Vec<WebRoom>>> {
+    let event = WebEvent::room(WebEventKind::RoomUpdated, &path.0, &path.1);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
     let (home, room) = path.into_inner();
     let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
 
-    let room = home
-        .add_room(Room::new(&room))
-        .map_err(|_| Error::RoomAlreadyExists)?;
+    home.set_room_position(&room, position.position)
+        .map_err(|e| match e {
+            LibError::InvalidPosition => Error::InvalidPosition,
+            _ => Error::RoomNotFound,
+        })?;
 
-    Ok(Json(room.into()))
+    let response = Json(home.room_iter().map(|room| room.into()).collect());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
 }
 
-#[delete("/{home}/{room}")]
-async fn delete_room(
+/// Place the room on the floor and zone
+#[utoipa::path(
+    tag = "room",
+    responses(
+        (status = 200, description = "Updated room", body = WebRoom),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+        (status = 422, description = "Zone requires a floor", body = ErrorResponse),
+    )
+)]
+#[post("/{home}/{room}/location")]
+async fn set_room_location(
     ctx: web::Data<GlobalContext>,
+    _auth: Admin,
     path: web::Path<(String, String)>,
+    location: web::Json<WebLocation>,
 ) -> WebResult<Json<WebRoom>> {
+    let event = WebEvent::room(WebEventKind::RoomUpdated, &path.0, &path.1);
     let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
     let (home, room) = path.into_inner();
+    let location = match location.into_inner() {
+        WebLocation {
+            floor: Some(floor),
+            zone,
+        } => Some(Location { floor, zone }),
+        WebLocation {
+            floor: None,
+            zone: None,
+        } => None,
+        WebLocation { floor: None, .. } => return Err(Error::InvalidLocation),
+    };
+
     let home = home_list.get_home_mut(&home).ok_or(Error::HomeNotFound)?;
-    let room = home.del_room(&room).ok_or(Error::RoomNotFound)?;
+    let room = home.room_mut(&room).ok_or(Error::RoomNotFound)?;
+    room.set_location(location);
+
+    let response = Json(room.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
+
+/// Delete the room with all devices
+#[utoipa::path(
+    tag = "room",
+    responses(
+        (status = 200, description = "Deleted room", body = WebRoom),
+        (status = 404, description = "Home or room is not found", body = ErrorResponse),
+    )
+)]
+#[delete("/{home}/{room}")]
+async fn delete_room(
+    ctx: web::Data<GlobalContext>,
+    _auth: Admin,
+    path: web::Path<(String, String)>,
+) -> WebResult<Json<WebRoom>> {
+    let (home, room) = path.into_inner();
+
+    delete_room_by_name(&ctx, &home, &room).await
+}
+
+/// Deletion of the room found by the name or the id
+pub async fn delete_room_by_name(
+    ctx: &GlobalContext,
+    home: &str,
+    room: &str,
+) -> WebResult<Json<WebRoom>> {
+    let event = WebEvent::room(WebEventKind::RoomDeleted, home, room);
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+    let home = home_list.get_home_mut(home).ok_or(Error::HomeNotFound)?;
+    let room = home.del_room(room).ok_or(Error::RoomNotFound)?;
+
+    let response = Json(room.into());
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
 
-    Ok(Json(room.into()))
+    Ok(response)
 }
@@ -0,0 +1,58 @@
+use actix_web::{get, web, web::Json};
+use smart_home_lib::{Capability, DeviceQuery};
+
+use crate::{
+    auth::Viewer,
+    error::{Error, ErrorResponse, WebResult},
+    home_list::GlobalContext,
+    types::{WebDevicePath, WebDeviceSearch},
+};
+
+/// Find devices in all homes
+#[utoipa::path(
+    tag = "search",
+    params(WebDeviceSearch),
+    responses(
+        (status = 200, description = "Found devices", body = Vec<WebDevicePath>),
+        (status = 400, description = "Invalid device capability", body = ErrorResponse),
+    )
+)]
+#[get("/search")]
+async fn search_devices(
+    ctx: web::Data<GlobalContext>,
+    Viewer(principal): Viewer,
+    search: web::Query<WebDeviceSearch>,
+) -> WebResult<Json<Vec<WebDevicePath>>> {
+    let home_list = ctx.home_list.lock().await;
+    let search = search.into_inner();
+    let mut query = DeviceQuery::new();
+
+    if let Some(capability) = search.capability {
+        let capability: Capability = capability.parse().map_err(|_| Error::InvalidCapability)?;
+        query = query.with_capability(capability);
+    }
+
+    if let Some(device_type) = search.device_type {
+        query = query.with_device_type(device_type);
+    }
+
+    if let Some(name) = search.name {
+        query = query.with_name(name);
+    }
+
+    if let Some(tag) = search.tag {
+        query = query.with_tag(tag);
+    }
+
+    Ok(Json(
+        home_list
+            .find_devices(&query)
+            .filter(|path| principal.can_access(path.home.name()))
+            .map(|path| WebDevicePath {
+                home: path.home.name().to_string(),
+                room: path.room.name().to_string(),
+                device: path.device.into(),
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,525 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use smart_home_lib::{
+    BinarySensorKind, Device, DeviceGroup, GroupMember, Home, Id, Location, Metadata, Room,
+    SmartBinarySensor, SmartHub, SmartMultiSensor, SmartSocket, SmartThermometer, SmartThermostat,
+    ThermostatMode,
+};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Invalid id {0}")]
+    InvalidId(String),
+    #[error("Invalid thermostat mode {0}")]
+    InvalidThermostatMode(String),
+    #[error("Invalid thermostat settings of {0}")]
+    InvalidThermostat(String),
+    #[error("Invalid binary sensor kind {0}")]
+    InvalidBinarySensorKind(String),
+    #[error("Duplicate name {0}")]
+    DuplicateName(String),
+    #[error("Duplicate id {0}")]
+    DuplicateId(String),
+    #[error("Name is empty")]
+    EmptyName,
+    #[error("Invalid member {room}/{device} of the group {group}")]
+    InvalidGroupMember {
+        group: String,
+        room: String,
+        device: String,
+    },
+}
+
+/// Serializable state of the whole hub, lists keep the user-defined order
+///
+/// Ids, descriptions, metadata and thermostat settings may be omitted in hand-written topologies.
+#[derive(Serialize, Deserialize, Default)]
+pub struct HubSnapshot {
+    pub homes: Vec<HomeSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct HomeSnapshot {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(flatten)]
+    pub metadata: MetadataSnapshot,
+    #[serde(default)]
+    pub rooms: Vec<RoomSnapshot>,
+    #[serde(default)]
+    pub groups: Vec<GroupSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RoomSnapshot {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub floor: Option<String>,
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(flatten)]
+    pub metadata: MetadataSnapshot,
+    #[serde(default)]
+    pub devices: Vec<DeviceSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DeviceSnapshot {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(flatten)]
+    pub kind: DeviceKindSnapshot,
+    #[serde(flatten)]
+    pub metadata: MetadataSnapshot,
+}
+
+/// Settings to reconnect the device
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "device_type", rename_all = "snake_case")]
+pub enum DeviceKindSnapshot {
+    Thermometer {
+        server_addr: String,
+    },
+    Socket {
+        server_addr: String,
+    },
+    Thermostat {
+        thermometer_addr: String,
+        heater_addr: String,
+        #[serde(default)]
+        target_temperature: Option<f64>,
+        #[serde(default)]
+        hysteresis: Option<f64>,
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    MultiSensor {
+        server_addr: String,
+    },
+    BinarySensor {
+        server_addr: String,
+        kind: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GroupSnapshot {
+    pub name: String,
+    #[serde(default)]
+    pub members: Vec<GroupMemberSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GroupMemberSnapshot {
+    pub room: String,
+    pub device: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+pub struct MetadataSnapshot {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl From<&Metadata> for MetadataSnapshot {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            tags: metadata.tags().map(String::from).collect(),
+            properties: metadata
+                .properties()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl MetadataSnapshot {
+    fn restore(self, metadata: &mut Metadata) {
+        metadata.set_tags(self.tags);
+        metadata.set_properties(self.properties);
+    }
+}
+
+/// Parse the stored id, a new id is generated if it is missing
+fn parse_id(id: Option<&str>) -> Result<Id, SnapshotError> {
+    match id {
+        Some(id) => Id::parse_str(id).map_err(|_| SnapshotError::InvalidId(id.to_string())),
+        None => Ok(Id::new_v4()),
+    }
+}
+
+impl HubSnapshot {
+    /// Capture the hub state, devices which can't be restored are skipped
+    pub async fn capture(hub: &SmartHub) -> Self {
+        let mut homes = Vec::new();
+
+        for home in hub.iter() {
+            homes.push(HomeSnapshot::capture(home).await);
+        }
+
+        Self { homes }
+    }
+
+    /// Reconnect all devices and build the hub
+    pub async fn restore(self) -> Result<SmartHub, SnapshotError> {
+        let mut hub = SmartHub::new();
+
+        for home in self.homes {
+            let name = home.name.clone();
+            hub.add_home(home.restore().await?)
+                .map_err(|_| SnapshotError::DuplicateName(name))?;
+        }
+
+        Ok(hub)
+    }
+}
+
+impl HomeSnapshot {
+    pub async fn capture(home: &Home) -> Self {
+        let mut rooms = Vec::new();
+
+        for room in home.room_iter() {
+            rooms.push(RoomSnapshot::capture(room).await);
+        }
+
+        Self {
+            id: Some(home.id().to_string()),
+            name: home.name().to_string(),
+            metadata: home.metadata().into(),
+            rooms,
+            groups: home.group_iter().map(GroupSnapshot::capture).collect(),
+        }
+    }
+
+    /// Check names, ids, group members and thermostat settings without connecting the devices,
+    /// so an invalid home is rejected before its devices are started
+    pub fn validate(&self) -> Result<(), SnapshotError> {
+        fn check_name<'a>(
+            names: &mut HashSet<&'a str>,
+            name: &'a str,
+        ) -> Result<(), SnapshotError> {
+            if name.is_empty() {
+                return Err(SnapshotError::EmptyName);
+            }
+            if !names.insert(name) {
+                return Err(SnapshotError::DuplicateName(name.to_string()));
+            }
+            Ok(())
+        }
+
+        check_name(&mut HashSet::new(), &self.name)?;
+
+        let mut ids = HashSet::new();
+        for id in self.ids() {
+            let id = id?;
+            if !ids.insert(id) {
+                return Err(SnapshotError::DuplicateId(id.to_string()));
+            }
+        }
+
+        let mut rooms = HashSet::new();
+        let mut devices = HashSet::new();
+        for room in &self.rooms {
+            check_name(&mut rooms, &room.name)?;
+
+            let mut names = HashSet::new();
+            for device in &room.devices {
+                check_name(&mut names, &device.name)?;
+                device.kind.validate(&device.name)?;
+                devices.insert((room.name.as_str(), device.name.as_str()));
+            }
+        }
+
+        let mut groups = HashSet::new();
+        for group in &self.groups {
+            check_name(&mut groups, &group.name)?;
+
+            let mut members = HashSet::new();
+            for member in &group.members {
+                let member = (member.room.as_str(), member.device.as_str());
+
+                if !devices.contains(&member) || !members.insert(member) {
+                    return Err(SnapshotError::InvalidGroupMember {
+                        group: group.name.clone(),
+                        room: member.0.to_string(),
+                        device: member.1.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parsed ids of the home, its rooms and devices, missing ids are skipped
+    pub fn ids(&self) -> impl Iterator<Item = Result<Id, SnapshotError>> + '_ {
+        let rooms = self.rooms.iter().map(|room| &room.id);
+        let devices = self
+            .rooms
+            .iter()
+            .flat_map(|room| room.devices.iter().map(|device| &device.id));
+
+        [&self.id]
+            .into_iter()
+            .chain(rooms)
+            .chain(devices)
+            .filter_map(|id| id.as_deref())
+            .map(|id| parse_id(Some(id)))
+    }
+
+    pub async fn restore(self) -> Result<Home, SnapshotError> {
+        let mut home = Home::new(self.name).with_id(parse_id(self.id.as_deref())?);
+        self.metadata.restore(home.metadata_mut());
+
+        for room in self.rooms {
+            let name = room.name.clone();
+            home.add_room(room.restore().await?)
+                .map_err(|_| SnapshotError::DuplicateName(name))?;
+        }
+
+        for group in self.groups {
+            home.add_group(DeviceGroup::new(group.name.as_str()))
+                .map_err(|_| SnapshotError::DuplicateName(group.name.clone()))?;
+
+            for member in group.members {
+                home.add_group_member(
+                    &group.name,
+                    GroupMember::new(member.room.as_str(), member.device.as_str()),
+                )
+                .map_err(|_| SnapshotError::InvalidGroupMember {
+                    group: group.name.clone(),
+                    room: member.room,
+                    device: member.device,
+                })?;
+            }
+        }
+
+        Ok(home)
+    }
+}
+
+impl RoomSnapshot {
+    async fn capture(room: &Room) -> Self {
+        let mut devices = Vec::new();
+
+        for device in room.device_iter() {
+            match DeviceSnapshot::capture(device).await {
+                Some(device) => devices.push(device),
+                None => log::warn!(
+                    "Device {} of type {} can't be stored",
+                    device.name(),
+                    device.device_type()
+                ),
+            }
+        }
+
+        Self {
+            id: Some(room.id().to_string()),
+            name: room.name().to_string(),
+            floor: room.location().map(|location| location.floor.clone()),
+            zone: room.location().and_then(|location| location.zone.clone()),
+            metadata: room.metadata().into(),
+            devices,
+        }
+    }
+
+    async fn restore(self) -> Result<Room, SnapshotError> {
+        let mut room = Room::new(self.name).with_id(parse_id(self.id.as_deref())?);
+        self.metadata.restore(room.metadata_mut());
+        room.set_location(self.floor.map(|floor| Location {
+            floor,
+            zone: self.zone,
+        }));
+
+        for device in self.devices {
+            let name = device.name.clone();
+            room.add_device(device.restore().await?)
+                .map_err(|_| SnapshotError::DuplicateName(name))?;
+        }
+
+        Ok(room)
+    }
+}
+
+impl DeviceSnapshot {
+    async fn capture(device: &Device) -> Option<Self> {
+        let kind = if let Some(thermostat) = device.downcast_ref::<SmartThermostat>() {
+            DeviceKindSnapshot::Thermostat {
+                thermometer_addr: thermostat.thermometer().server_addr().to_string(),
+                heater_addr: thermostat.heater().server_addr().to_string(),
+                target_temperature: Some(thermostat.target_temperature().await),
+                hysteresis: Some(thermostat.hysteresis().await),
+                mode: Some(thermostat.mode().await.to_string()),
+            }
+        } else if let Some(socket) = device.downcast_ref::<SmartSocket>() {
+            DeviceKindSnapshot::Socket {
+                server_addr: socket.server_addr().to_string(),
+            }
+        } else if let Some(thermometer) = device.downcast_ref::<SmartThermometer>() {
+            DeviceKindSnapshot::Thermometer {
+                server_addr: thermometer.server_addr().to_string(),
+            }
+        } else if let Some(sensor) = device.downcast_ref::<SmartMultiSensor>() {
+            DeviceKindSnapshot::MultiSensor {
+                server_addr: sensor.server_addr().to_string(),
+            }
+        } else if let Some(sensor) = device.downcast_ref::<SmartBinarySensor>() {
+            DeviceKindSnapshot::BinarySensor {
+                server_addr: sensor.server_addr().to_string(),
+                kind: sensor.kind().to_string(),
+            }
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            id: Some(device.id().to_string()),
+            name: device.name().to_string(),
+            description: device.description().to_string(),
+            kind,
+            metadata: device.metadata().into(),
+        })
+    }
+
+    async fn restore(self) -> Result<Device, SnapshotError> {
+        let (name, description) = (self.name.as_str(), self.description.as_str());
+        let mut device = match self.kind {
+            DeviceKindSnapshot::Thermometer { server_addr } => {
+                Device::new(SmartThermometer::new(name, description, server_addr).await)
+            }
+            DeviceKindSnapshot::Socket { server_addr } => {
+                Device::new(SmartSocket::new(name, description, server_addr).await)
+            }
+            DeviceKindSnapshot::MultiSensor { server_addr } => {
+                Device::new(SmartMultiSensor::new(name, description, server_addr).await)
+            }
+            DeviceKindSnapshot::BinarySensor { server_addr, kind } => {
+                let kind: BinarySensorKind = kind
+                    .parse()
+                    .map_err(|_| SnapshotError::InvalidBinarySensorKind(kind))?;
+
+                Device::new(SmartBinarySensor::new(name, description, kind, server_addr).await)
+            }
+            DeviceKindSnapshot::Thermostat {
+                thermometer_addr,
+                heater_addr,
+                target_temperature,
+                hysteresis,
+                mode,
+            } => {
+                let mode: Option<ThermostatMode> = mode
+                    .map(|mode| {
+                        mode.parse()
+                            .map_err(|_| SnapshotError::InvalidThermostatMode(mode))
+                    })
+                    .transpose()?;
+                let thermostat = SmartThermostat::new(
+                    name,
+                    description,
+                    SmartThermometer::new(name, description, thermometer_addr).await,
+                    SmartSocket::new(name, description, heater_addr).await,
+                )
+                .await;
+
+                // only invalid settings fail, an unreachable heater is switched later
+                let invalid = |_| SnapshotError::InvalidThermostat(self.name.clone());
+                if let Some(target_temperature) = target_temperature {
+                    thermostat
+                        .set_target_temperature(target_temperature)
+                        .await
+                        .map_err(invalid)?;
+                }
+                if let Some(hysteresis) = hysteresis {
+                    thermostat
+                        .set_hysteresis(hysteresis)
+                        .await
+                        .map_err(invalid)?;
+                }
+                if let Some(mode) = mode {
+                    thermostat.set_mode(mode).await;
+                }
+
+                Device::new(thermostat)
+            }
+        };
+
+        device = device.with_id(parse_id(self.id.as_deref())?);
+        self.metadata.restore(device.metadata_mut());
+
+        Ok(device)
+    }
+}
+
+impl DeviceKindSnapshot {
+    /// Check the thermostat settings the same way the thermostat does and the binary sensor kind
+    fn validate(&self, name: &str) -> Result<(), SnapshotError> {
+        if let DeviceKindSnapshot::BinarySensor { kind, .. } = self {
+            kind.parse::<BinarySensorKind>()
+                .map_err(|_| SnapshotError::InvalidBinarySensorKind(kind.clone()))?;
+        }
+
+        if let DeviceKindSnapshot::Thermostat {
+            target_temperature,
+            hysteresis,
+            mode,
+            ..
+        } = self
+        {
+            if let Some(mode) = mode {
+                mode.parse::<ThermostatMode>()
+                    .map_err(|_| SnapshotError::InvalidThermostatMode(mode.clone()))?;
+            }
+
+            let valid_target = target_temperature.is_none_or(f64::is_finite);
+            let valid_hysteresis = hysteresis.is_none_or(|h| h.is_finite() && h >= 0.0);
+            if !valid_target || !valid_hysteresis {
+                return Err(SnapshotError::InvalidThermostat(name.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GroupSnapshot {
+    fn capture(group: &DeviceGroup) -> Self {
+        Self {
+            name: group.name().to_string(),
+            members: group
+                .members()
+                .map(|member| GroupMemberSnapshot {
+                    room: member.room.clone(),
+                    device: member.device.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::sample;
+
+    #[actix_web::test]
+    async fn round_trip() {
+        let snapshot = sample();
+        let expected = serde_json::to_value(&snapshot).unwrap();
+
+        let hub = snapshot.restore().await.unwrap();
+        let captured = HubSnapshot::capture(&hub).await;
+
+        assert_eq!(serde_json::to_value(&captured).unwrap(), expected);
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{Storage, StorageResult};
+use crate::snapshot::HubSnapshot;
+
+/// JSON document with the whole hub
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> StorageResult<HubSnapshot> {
+        match fs::read(&self.path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HubSnapshot::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, snapshot: &HubSnapshot) -> StorageResult<()> {
+        // write the copy and replace the file, so the file is never left half-written
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        fs::write(&tmp_path, serde_json::to_vec_pretty(snapshot)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::{sample, TempPath};
+
+    #[test]
+    fn round_trip() {
+        let path = TempPath::new("round-trip.json");
+        let snapshot = sample();
+
+        assert!(FileStorage::new(&path.0).load().unwrap().homes.is_empty());
+
+        FileStorage::new(&path.0).save(&snapshot).unwrap();
+        let loaded = FileStorage::new(&path.0).load().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&snapshot).unwrap()
+        );
+    }
+}
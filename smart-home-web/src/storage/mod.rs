@@ -0,0 +1,139 @@
+use std::{io, path::Path};
+
+use thiserror::Error;
+
+pub use self::{file::FileStorage, sqlite::SqliteStorage};
+use crate::snapshot::{HubSnapshot, SnapshotError};
+
+mod file;
+mod sqlite;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Invalid stored hub: {0}")]
+    Snapshot(#[from] SnapshotError),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// Persistent store of the hub state
+pub trait Storage: Send + Sync {
+    /// Load the stored state, empty if nothing is stored yet
+    fn load(&self) -> StorageResult<HubSnapshot>;
+
+    /// Replace the stored state
+    fn save(&self, snapshot: &HubSnapshot) -> StorageResult<()>;
+}
+
+/// Open the store, `.db`, `.sqlite` and `.sqlite3` files are SQLite databases, other files are
+/// JSON documents
+pub fn open(path: &Path) -> StorageResult<Box<dyn Storage>> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("db" | "sqlite" | "sqlite3") => Ok(Box::new(SqliteStorage::open(path)?)),
+        _ => Ok(Box::new(FileStorage::new(path))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use serde_json::json;
+
+    use crate::snapshot::HubSnapshot;
+
+    /// Hub with ids, metadata, locations, groups and thermostat settings set
+    pub fn sample() -> HubSnapshot {
+        serde_json::from_value(json!({
+            "homes": [{
+                "id": "4f8b2a4e-3c1d-4d6e-9a7b-1c2d3e4f5a6b",
+                "name": "Flat",
+                "tags": ["city"],
+                "properties": {"address": "Main st. 1"},
+                "rooms": [{
+                    "id": "0e4a1f5c-8b2d-4c3e-a9f1-2b3c4d5e6f70",
+                    "name": "Office",
+                    "floor": "2",
+                    "zone": "north",
+                    "tags": ["work"],
+                    "properties": {},
+                    "devices": [{
+                        "id": "7a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d",
+                        "name": "Lamp",
+                        "description": "Desk lamp",
+                        "device_type": "socket",
+                        "server_addr": "http://127.0.0.1:1",
+                        "tags": ["light"],
+                        "properties": {"power": "60"},
+                    }, {
+                        "id": "1b2c3d4e-5f6a-4b7c-8d9e-0f1a2b3c4d5e",
+                        "name": "Heating",
+                        "description": "",
+                        "device_type": "thermostat",
+                        "thermometer_addr": "127.0.0.1:0",
+                        "heater_addr": "http://127.0.0.1:1",
+                        "target_temperature": 22.5,
+                        "hysteresis": 0.5,
+                        "mode": "auto",
+                        "tags": [],
+                        "properties": {},
+                    }],
+                }, {
+                    "id": "2c3d4e5f-6a7b-4c8d-9e0f-1a2b3c4d5e6f",
+                    "name": "Kitchen",
+                    "floor": null,
+                    "zone": null,
+                    "tags": [],
+                    "properties": {},
+                    "devices": [{
+                        "id": "3d4e5f6a-7b8c-4d9e-8f1a-2b3c4d5e6f7a",
+                        "name": "Climate",
+                        "description": "",
+                        "device_type": "multi_sensor",
+                        "server_addr": "127.0.0.1:0",
+                        "tags": [],
+                        "properties": {},
+                    }, {
+                        "id": "4e5f6a7b-8c9d-4e0f-9a2b-3c4d5e6f7a8b",
+                        "name": "Door",
+                        "description": "Front door",
+                        "device_type": "binary_sensor",
+                        "server_addr": "127.0.0.1:0",
+                        "kind": "door",
+                        "tags": [],
+                        "properties": {},
+                    }],
+                }],
+                "groups": [{
+                    "name": "Lights",
+                    "members": [{"room": "Office", "device": "Lamp"}],
+                }, {
+                    "name": "Empty",
+                    "members": [],
+                }],
+            }],
+        }))
+        .unwrap()
+    }
+
+    /// Unique path in the temporary directory, the file is removed on drop
+    pub struct TempPath(pub PathBuf);
+
+    impl TempPath {
+        pub fn new(name: &str) -> Self {
+            Self(env::temp_dir().join(format!("smart-home-{}-{}", process::id(), name)))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
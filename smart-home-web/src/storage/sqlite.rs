@@ -0,0 +1,280 @@
+use std::{path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, Transaction};
+
+use super::{Storage, StorageResult};
+use crate::snapshot::{
+    DeviceSnapshot, GroupSnapshot, HomeSnapshot, HubSnapshot, MetadataSnapshot, RoomSnapshot,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS homes (
+    id TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    tags TEXT NOT NULL,
+    properties TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS rooms (
+    id TEXT PRIMARY KEY,
+    home_id TEXT NOT NULL REFERENCES homes (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    floor TEXT,
+    zone TEXT,
+    tags TEXT NOT NULL,
+    properties TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS devices (
+    id TEXT PRIMARY KEY,
+    room_id TEXT NOT NULL REFERENCES rooms (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    settings TEXT NOT NULL,
+    tags TEXT NOT NULL,
+    properties TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS device_groups (
+    home_id TEXT NOT NULL REFERENCES homes (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    members TEXT NOT NULL,
+    PRIMARY KEY (home_id, name)
+);
+";
+
+/// Embedded SQLite database, homes, rooms, devices and groups are stored in their own tables
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> StorageResult<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+/// Encode tags and properties as JSON columns
+fn metadata_columns(metadata: &MetadataSnapshot) -> StorageResult<(String, String)> {
+    Ok((
+        serde_json::to_string(&metadata.tags)?,
+        serde_json::to_string(&metadata.properties)?,
+    ))
+}
+
+fn metadata_from_columns(tags: &str, properties: &str) -> StorageResult<MetadataSnapshot> {
+    Ok(MetadataSnapshot {
+        tags: serde_json::from_str(tags)?,
+        properties: serde_json::from_str(properties)?,
+    })
+}
+
+fn save_home(tx: &Transaction, position: usize, home: &HomeSnapshot) -> StorageResult<()> {
+    let (tags, properties) = metadata_columns(&home.metadata)?;
+    tx.execute(
+        "INSERT INTO homes (id, position, name, tags, properties) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![home.id, position, home.name, tags, properties],
+    )?;
+
+    for (position, room) in home.rooms.iter().enumerate() {
+        let (tags, properties) = metadata_columns(&room.metadata)?;
+        tx.execute(
+            "INSERT INTO rooms (id, home_id, position, name, floor, zone, tags, properties)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![room.id, home.id, position, room.name, room.floor, room.zone, tags, properties],
+        )?;
+
+        for (position, device) in room.devices.iter().enumerate() {
+            let (tags, properties) = metadata_columns(&device.metadata)?;
+            tx.execute(
+                "INSERT INTO devices
+                 (id, room_id, position, name, description, settings, tags, properties)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    device.id,
+                    room.id,
+                    position,
+                    device.name,
+                    device.description,
+                    serde_json::to_string(&device.kind)?,
+                    tags,
+                    properties
+                ],
+            )?;
+        }
+    }
+
+    for (position, group) in home.groups.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO device_groups (home_id, position, name, members) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                home.id,
+                position,
+                group.name,
+                serde_json::to_string(&group.members)?
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn load_rooms(connection: &Connection, home_id: &str) -> StorageResult<Vec<RoomSnapshot>> {
+    let mut statement = connection.prepare(
+        "SELECT id, name, floor, zone, tags, properties FROM rooms
+         WHERE home_id = ?1 ORDER BY position",
+    )?;
+    let rows = statement.query_map([home_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    })?;
+
+    rows.map(|row| {
+        let (id, name, floor, zone, tags, properties) = row?;
+
+        Ok(RoomSnapshot {
+            devices: load_devices(connection, &id)?,
+            metadata: metadata_from_columns(&tags, &properties)?,
+            id: Some(id),
+            name,
+            floor,
+            zone,
+        })
+    })
+    .collect()
+}
+
+fn load_devices(connection: &Connection, room_id: &str) -> StorageResult<Vec<DeviceSnapshot>> {
+    let mut statement = connection.prepare(
+        "SELECT id, name, description, settings, tags, properties FROM devices
+         WHERE room_id = ?1 ORDER BY position",
+    )?;
+    let rows = statement.query_map([room_id], |row| {
+        Ok((
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    })?;
+
+    rows.map(|row| {
+        let (id, name, description, settings, tags, properties) = row?;
+
+        Ok(DeviceSnapshot {
+            id: Some(id),
+            name,
+            description,
+            kind: serde_json::from_str(&settings)?,
+            metadata: metadata_from_columns(&tags, &properties)?,
+        })
+    })
+    .collect()
+}
+
+fn load_groups(connection: &Connection, home_id: &str) -> StorageResult<Vec<GroupSnapshot>> {
+    let mut statement = connection
+        .prepare("SELECT name, members FROM device_groups WHERE home_id = ?1 ORDER BY position")?;
+    let rows = statement.query_map([home_id], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?;
+
+    rows.map(|row| {
+        let (name, members) = row?;
+
+        Ok(GroupSnapshot {
+            name,
+            members: serde_json::from_str(&members)?,
+        })
+    })
+    .collect()
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> StorageResult<HubSnapshot> {
+        let connection = self.connection.lock().expect("connection lock");
+        let mut statement =
+            connection.prepare("SELECT id, name, tags, properties FROM homes ORDER BY position")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let homes = rows
+            .map(|row| {
+                let (id, name, tags, properties) = row?;
+
+                Ok(HomeSnapshot {
+                    rooms: load_rooms(&connection, &id)?,
+                    groups: load_groups(&connection, &id)?,
+                    metadata: metadata_from_columns(&tags, &properties)?,
+                    id: Some(id),
+                    name,
+                })
+            })
+            .collect::<StorageResult<_>>()?;
+
+        Ok(HubSnapshot { homes })
+    }
+
+    fn save(&self, snapshot: &HubSnapshot) -> StorageResult<()> {
+        let mut connection = self.connection.lock().expect("connection lock");
+        let tx = connection.transaction()?;
+
+        // rooms, devices and groups are removed by the cascade
+        tx.execute("DELETE FROM homes", [])?;
+
+        for (position, home) in snapshot.homes.iter().enumerate() {
+            save_home(&tx, position, home)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::{sample, TempPath};
+
+    #[test]
+    fn round_trip() {
+        let path = TempPath::new("round-trip.db");
+        let snapshot = sample();
+
+        SqliteStorage::open(&path.0)
+            .unwrap()
+            .save(&snapshot)
+            .unwrap();
+        // the second save replaces the first one
+        SqliteStorage::open(&path.0)
+            .unwrap()
+            .save(&snapshot)
+            .unwrap();
+        let loaded = SqliteStorage::open(&path.0).unwrap().load().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&snapshot).unwrap()
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use actix_web::{
+    body::to_bytes,
+    http::{header, Method, StatusCode},
+    test,
+};
+use serde_json::{json, Value};
+
+use super::init_app;
+use crate::{
+    auth::{ApiKey, AuthConfig, Role},
+    home_list::GlobalContext,
+};
+
+const ADMIN: &str = "admin-key";
+const VIEWER: &str = "viewer-key";
+/// Admin of the `Flat` home only
+const FLAT_ADMIN: &str = "flat-admin-key";
+
+fn request(method: Method, uri: &str, token: &str) -> test::TestRequest {
+    test::TestRequest::default()
+        .method(method)
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+}
+
+fn context() -> GlobalContext {
+    let key = |key: &str, role, homes: Option<&[&str]>| ApiKey {
+        key: key.to_string(),
+        role,
+        homes: homes.map(|homes| homes.iter().map(|home| home.to_string()).collect()),
+    };
+
+    GlobalContext {
+        auth: Arc::new(AuthConfig {
+            api_keys: vec![
+                key(ADMIN, Role::Admin, None),
+                key(VIEWER, Role::Viewer, None),
+                key(FLAT_ADMIN, Role::Admin, Some(&["Flat"])),
+            ],
+            jwt_secret: None,
+        }),
+        ..Default::default()
+    }
+}
+
+#[actix_web::test]
+async fn unauthorized() {
+    let app = init_app(context()).await;
+
+    let requests = [
+        test::TestRequest::get().uri("/home/"),
+        request(Method::GET, "/home/", "wrong-key"),
+        test::TestRequest::get()
+            .uri("/home/")
+            .insert_header((header::AUTHORIZATION, ADMIN)),
+    ];
+
+    for request in requests {
+        // the middleware fails the request, the server turns the error into the response
+        let error = test::try_call_service(&app, request.to_request())
+            .await
+            .err()
+            .expect("unauthorized");
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let error: Value =
+            serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(error["error"], "Unauthorized");
+    }
+
+    let response =
+        test::call_service(&app, request(Method::GET, "/home/", ADMIN).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn forbidden() {
+    let app = init_app(context()).await;
+    let socket = json!({
+        "name": "Lamp",
+        "description": "Desk lamp",
+        "server_addr": "http://127.0.0.1:1",
+    });
+
+    let setup = [
+        request(Method::PUT, "/home/Flat", ADMIN),
+        request(Method::PUT, "/home/Flat/Office", ADMIN),
+        request(Method::POST, "/home/Flat/Office/socket", ADMIN).set_json(&socket),
+        request(Method::PUT, "/home/Cottage", ADMIN),
+        request(Method::PUT, "/home/Cottage/Hall", ADMIN),
+    ];
+    for request in setup {
+        let request = request.to_request();
+        let uri = request.uri().to_string();
+        let response = test::call_service(&app, request).await;
+        assert!(
+            response.status().is_success(),
+            "{} {}",
+            uri,
+            response.status()
+        );
+    }
+
+    let forbidden = [
+        // viewer can't change anything
+        request(Method::PUT, "/home/Flat/Kitchen", VIEWER),
+        request(Method::PATCH, "/home/Flat", VIEWER).set_json(json!({ "tags": ["new"] })),
+        request(Method::POST, "/home/Flat/Office/Lamp/on", VIEWER),
+        // scoped key can't reach other homes
+        request(Method::GET, "/home/Cottage", FLAT_ADMIN),
+        request(Method::PUT, "/home/Cottage/Kitchen", FLAT_ADMIN),
+        request(Method::DELETE, "/home/Cottage", FLAT_ADMIN),
+        request(Method::POST, "/home/Flat/Office/Lamp/move", FLAT_ADMIN)
+            .set_json(json!({ "home": "Cottage", "room": "Hall" })),
+        request(Method::PATCH, "/home/Flat", FLAT_ADMIN).set_json(json!({ "name": "Villa" })),
+    ];
+    for request in forbidden {
+        let request = request.to_request();
+        let uri = request.uri().to_string();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["error"], "Forbidden");
+    }
+
+    // nothing is changed and the scoped key still manages its home
+    let homes: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/", FLAT_ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(homes.as_array().unwrap().len(), 1);
+    assert_eq!(homes[0]["name"], "Flat");
+
+    let devices: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/Flat/Office/", FLAT_ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(devices[0]["name"], "Lamp");
+
+    let response = test::call_service(
+        &app,
+        request(Method::PUT, "/home/Flat/Kitchen", FLAT_ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[actix_web::test]
+async fn changes_by_id() {
+    let app = init_app(context()).await;
+    let socket = json!({
+        "name": "Lamp",
+        "description": "Desk lamp",
+        "server_addr": "http://127.0.0.1:1",
+    });
+
+    let setup = [
+        request(Method::PUT, "/home/Flat", ADMIN),
+        request(Method::PUT, "/home/Flat/Office", ADMIN),
+        request(Method::POST, "/home/Flat/Office/socket", ADMIN).set_json(&socket),
+        request(Method::PUT, "/home/Cottage", ADMIN),
+    ];
+    for request in setup {
+        let response = test::call_service(&app, request.to_request()).await;
+        assert!(response.status().is_success(), "{}", response.status());
+    }
+
+    let read = |uri: &str| request(Method::GET, uri, ADMIN).to_request();
+    let flat: Value = test::call_and_read_body_json(&app, read("/home/Flat")).await;
+    let cottage: Value = test::call_and_read_body_json(&app, read("/home/Cottage")).await;
+    let office: Value = test::call_and_read_body_json(&app, read("/home/Flat/Office")).await;
+    let lamp: Value = test::call_and_read_body_json(&app, read("/home/Flat/Office/Lamp")).await;
+    let home_uri = |home: &Value| format!("/id/home/{}", home["id"].as_str().unwrap());
+    let room_uri = format!("/id/room/{}", office["id"].as_str().unwrap());
+    let device_uri = format!("/id/device/{}", lamp["id"].as_str().unwrap());
+
+    let forbidden = [
+        request(Method::PATCH, &room_uri, VIEWER).set_json(json!({ "name": "Study" })),
+        request(Method::DELETE, &device_uri, VIEWER),
+        // the scope is checked against the home the id belongs to
+        request(Method::PATCH, &home_uri(&cottage), FLAT_ADMIN)
+            .set_json(json!({ "name": "Villa" })),
+        request(Method::DELETE, &home_uri(&cottage), FLAT_ADMIN),
+    ];
+    for request in forbidden {
+        let request = request.to_request();
+        let uri = request.uri().to_string();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+    }
+
+    let room: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::PATCH, &room_uri, FLAT_ADMIN)
+            .set_json(json!({ "name": "Study" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(room["name"], "Study");
+    assert_eq!(room["id"], office["id"]);
+
+    let home: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::PATCH, &home_uri(&flat), FLAT_ADMIN)
+            .set_json(json!({ "tags": ["city"] }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(home["tags"], json!(["city"]));
+
+    let device: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::DELETE, &device_uri, FLAT_ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(device["name"], "Lamp");
+    let response = test::call_service(&app, read(&device_uri)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = test::call_service(
+        &app,
+        request(Method::DELETE, &home_uri(&cottage), ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = test::call_service(&app, read("/home/Cottage")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = test::call_service(
+        &app,
+        request(Method::DELETE, "/id/room/not-an-id", ADMIN).to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
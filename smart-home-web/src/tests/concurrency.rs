@@ -0,0 +1,124 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::{json, Value};
+use smart_home_lib::{
+    async_trait, error::Result as LibResult, Home, Room, SmartDevice, Switchable,
+};
+use tokio::{sync::Notify, time};
+
+use super::init_app;
+use crate::home_list::GlobalContext;
+
+/// Time to serve a request which doesn't talk to the devices
+const FAST_REQUEST: Duration = Duration::from_secs(1);
+
+/// Switch which answers only after the gate is opened
+#[derive(Debug)]
+struct SlowSwitch {
+    name: String,
+    gate: Arc<Notify>,
+}
+
+impl SmartDevice for SlowSwitch {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "switch with a slow server"
+    }
+
+    fn device_type(&self) -> &str {
+        "slow_switch"
+    }
+
+    fn as_switchable(&self) -> Option<&dyn Switchable> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Switchable for SlowSwitch {
+    async fn on(&self) -> LibResult<()> {
+        self.gate.notified().await;
+        Ok(())
+    }
+
+    async fn off(&self) -> LibResult<()> {
+        self.gate.notified().await;
+        Ok(())
+    }
+}
+
+fn request(method: Method, uri: &str) -> test::TestRequest {
+    test::TestRequest::default().method(method).uri(uri)
+}
+
+#[actix_web::test]
+async fn slow_device_does_not_block_other_requests() {
+    let ctx = GlobalContext::default();
+    let gate = Arc::new(Notify::new());
+
+    {
+        let mut home_list = ctx.home_list.lock().await;
+        let home = home_list.add_home(Home::new("Flat")).unwrap();
+        let room = home.add_room(Room::new("Office")).unwrap();
+        room.add_device(SlowSwitch {
+            name: "Heater".to_string(),
+            gate: gate.clone(),
+        })
+        .unwrap();
+    }
+
+    let app = init_app(ctx).await;
+
+    let switch = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/Heater/on").to_request(),
+    );
+    let others = async {
+        // the switch request is pending while these are served
+        for uri in ["/home/", "/home/Flat/Office/", "/home/Flat/Office/Heater"] {
+            let response = time::timeout(
+                FAST_REQUEST,
+                test::call_service(&app, request(Method::GET, uri).to_request()),
+            )
+            .await
+            .unwrap_or_else(|_| panic!("GET {} is blocked by the pending device request", uri));
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // the device is renamed while it is in use
+        let response = time::timeout(
+            FAST_REQUEST,
+            test::call_service(
+                &app,
+                request(Method::PATCH, "/home/Flat/Office/Heater")
+                    .set_json(json!({ "name": "Radiator" }))
+                    .to_request(),
+            ),
+        )
+        .await
+        .expect("rename is blocked by the pending device request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let device: Value = test::read_body_json(response).await;
+        assert_eq!(device["name"], "Radiator");
+
+        gate.notify_one();
+    };
+
+    let (response, ()) = tokio::join!(switch, others);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // the finished request doesn't bring the old name back
+    let device: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/Flat/Office/Radiator").to_request(),
+    )
+    .await;
+    assert_eq!(device["name"], "Radiator");
+}
@@ -0,0 +1,133 @@
+use std::{future::poll_fn, pin::Pin, time::Duration};
+
+use actix_web::{
+    body::MessageBody,
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::{json, Value};
+use smart_home_lib::{
+    async_trait, error::Result as LibResult, Home, Room, SmartDevice, TemperatureSensor,
+};
+use tokio::time;
+
+use super::init_app;
+use crate::{events::read_devices, home_list::GlobalContext};
+
+/// Time to receive the event after the change
+const EVENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn request(method: Method, uri: &str) -> test::TestRequest {
+    test::TestRequest::default().method(method).uri(uri)
+}
+
+/// Read the next Server-Sent Events message, returns its event name and data
+async fn next_event<B: MessageBody>(body: &mut Pin<Box<B>>) -> (String, Value) {
+    let chunk = time::timeout(EVENT_TIMEOUT, poll_fn(|cx| body.as_mut().poll_next(cx)))
+        .await
+        .expect("event in time")
+        .expect("open stream")
+        .ok()
+        .expect("event chunk");
+    let message = String::from_utf8(chunk.to_vec()).unwrap();
+    let (event, data) = message
+        .strip_suffix("\n\n")
+        .and_then(|message| message.split_once('\n'))
+        .expect("event and data lines");
+
+    (
+        event.strip_prefix("event: ").unwrap().to_string(),
+        serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap(),
+    )
+}
+
+#[actix_web::test]
+async fn room_subscription() {
+    let app = init_app(GlobalContext::default()).await;
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+
+    let response = test::call_service(
+        &app,
+        request(Method::GET, "/events?home=Flat&room=Office").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut body = Box::pin(response.into_body());
+
+    let changes = [
+        request(Method::PATCH, "/home/Flat/Office").set_json(json!({ "name": "Study" })),
+        // other rooms and homes are filtered out
+        request(Method::PUT, "/home/Flat/Kitchen"),
+        request(Method::PUT, "/home/Cottage"),
+        request(Method::DELETE, "/home/Flat"),
+    ];
+    for change in changes {
+        let response = test::call_service(&app, change.to_request()).await;
+        assert!(response.status().is_success());
+    }
+
+    let (event, data) = next_event(&mut body).await;
+    assert_eq!(event, "room_renamed");
+    assert_eq!(data["home"], "Flat");
+    assert_eq!(data["room"], "Office");
+    assert_eq!(data["new_name"], "Study");
+
+    // the home events are sent to the subscribers of its rooms
+    let (event, data) = next_event(&mut body).await;
+    assert_eq!(event, "home_deleted");
+    assert_eq!(data["home"], "Flat");
+    assert_eq!(data["room"], Value::Null);
+}
+
+/// Thermometer which never answers
+#[derive(Debug)]
+struct Hanging(&'static str);
+
+impl SmartDevice for Hanging {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn description(&self) -> &str {
+        "thermometer which never answers"
+    }
+
+    fn device_type(&self) -> &str {
+        "hanging"
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl TemperatureSensor for Hanging {
+    async fn current_temperature(&self) -> LibResult<f64> {
+        std::future::pending().await
+    }
+}
+
+#[actix_web::test]
+async fn hung_devices_are_read_concurrently() {
+    time::pause();
+    let ctx = GlobalContext::default();
+    {
+        let mut hub = ctx.home_list.lock().await;
+        let room = hub
+            .add_home(Home::new("Flat"))
+            .unwrap()
+            .add_room(Room::new("Office"))
+            .unwrap();
+        for name in ["First", "Second", "Third"] {
+            room.add_device(Hanging(name)).unwrap();
+        }
+    }
+
+    let start = time::Instant::now();
+    read_devices(&ctx).await;
+    // all devices time out together instead of one after another
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,117 @@
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{Method, StatusCode},
+    test,
+};
+use smart_home_lib::{
+    async_trait, error::Result as LibResult, Home, PowerMeter, Room, SmartDevice, Switchable,
+};
+
+use super::init_app;
+use crate::home_list::GlobalContext;
+
+/// Socket which always draws the same power
+#[derive(Debug)]
+struct FixedSocket;
+
+impl SmartDevice for FixedSocket {
+    fn name(&self) -> &str {
+        "Lamp"
+    }
+
+    fn description(&self) -> &str {
+        "socket with a constant load"
+    }
+
+    fn device_type(&self) -> &str {
+        "fixed_socket"
+    }
+
+    fn as_switchable(&self) -> Option<&dyn Switchable> {
+        Some(self)
+    }
+
+    fn as_power_meter(&self) -> Option<&dyn PowerMeter> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Switchable for FixedSocket {
+    async fn on(&self) -> LibResult<()> {
+        Ok(())
+    }
+
+    async fn off(&self) -> LibResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PowerMeter for FixedSocket {
+    async fn current_power(&self) -> LibResult<f64> {
+        Ok(60.0)
+    }
+}
+
+fn request(method: Method, uri: &str) -> test::TestRequest {
+    test::TestRequest::default().method(method).uri(uri)
+}
+
+/// Read the metrics in the text format
+async fn scrape(
+    app: &impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error>,
+) -> String {
+    let response = test::call_service(app, request(Method::GET, "/metrics").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    String::from_utf8(test::read_body(response).await.to_vec()).unwrap()
+}
+
+#[actix_web::test]
+async fn scrape_devices_and_requests() {
+    let ctx = GlobalContext::default();
+
+    {
+        let mut home_list = ctx.home_list.lock().await;
+        let home = home_list.add_home(Home::new("Flat")).unwrap();
+        let room = home.add_room(Room::new("Office")).unwrap();
+        room.add_device(FixedSocket).unwrap();
+    }
+
+    let app = init_app(ctx).await;
+
+    test::call_service(&app, request(Method::GET, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::GET, "/home/Cottage").to_request()).await;
+
+    let metrics = scrape(&app).await;
+    let labels = r#"{device="Lamp",home="Flat",room="Office"}"#;
+
+    assert!(metrics.contains(&format!("smart_home_power_watts{} 60", labels)));
+    assert!(metrics.contains(&format!("smart_home_socket_on{} 1", labels)));
+    // the health checker has not seen the device yet
+    assert!(metrics.contains(&format!("smart_home_device_up{} 0", labels)));
+    assert!(!metrics.contains("smart_home_temperature_celsius{"));
+
+    // requests are labeled with the route pattern
+    for series in [
+        r#"smart_home_http_request_duration_seconds_count{method="GET",path="/home/{home}",status="200"} 1"#,
+        r#"smart_home_http_request_duration_seconds_count{method="GET",path="/home/{home}",status="404"} 1"#,
+    ] {
+        assert!(metrics.contains(series), "{} in\n{}", series, metrics);
+    }
+
+    // gauges of the removed devices disappear
+    let response = test::call_service(
+        &app,
+        request(Method::DELETE, "/home/Flat/Office/Lamp").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let metrics = scrape(&app).await;
+    assert!(!metrics.contains("smart_home_power_watts{"));
+    assert!(!metrics.contains("smart_home_device_up{"));
+}
@@ -0,0 +1,36 @@
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    middleware::from_fn,
+    test, web, App,
+};
+use utoipa_actix_web::AppExt;
+
+use crate::{
+    auth::authenticate, home_list::GlobalContext, metrics::track_requests, openapi::configure,
+};
+
+mod auth;
+mod concurrency;
+mod events;
+mod metrics;
+mod rest;
+
+/// Web server with the services of `main`
+async fn init_app(
+    ctx: GlobalContext,
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error> {
+    let (app, document) = App::new()
+        .into_utoipa_app()
+        .app_data(web::Data::new(ctx))
+        .configure(configure)
+        .split_for_parts();
+
+    test::init_service(
+        app.app_data(web::Data::new(document))
+            .wrap(from_fn(authenticate))
+            .wrap(from_fn(track_requests)),
+    )
+    .await
+}
@@ -0,0 +1,696 @@
+use std::{io, net::UdpSocket, sync::Arc, time::Duration};
+
+use actix_web::{
+    http::{header, Method, StatusCode},
+    test,
+};
+use serde_json::{json, Value};
+use smart_home_lib::Home;
+use tokio::time;
+
+use super::init_app;
+use crate::{
+    home_list::GlobalContext,
+    snapshot::HubSnapshot,
+    storage::{Storage, StorageResult},
+};
+
+fn request(method: Method, uri: &str) -> test::TestRequest {
+    test::TestRequest::default().method(method).uri(uri)
+}
+
+fn location(response: &actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>) -> &str {
+    response
+        .headers()
+        .get(header::LOCATION)
+        .expect("location header")
+        .to_str()
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn put_is_idempotent() {
+    let app = init_app(GlobalContext::default()).await;
+
+    let response = test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(location(&response).ends_with("/home/Flat"));
+
+    let response = test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = test::call_service(
+        &app,
+        request(Method::PUT, "/home/Flat/Living%20room").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(location(&response).ends_with("/home/Flat/Living%20room"));
+
+    let response = test::call_service(
+        &app,
+        request(Method::PUT, "/home/Flat/Living%20room").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let homes: Vec<Value> =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/").to_request()).await;
+    assert_eq!(homes.len(), 1);
+
+    let rooms: Vec<Value> =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/Flat/").to_request()).await;
+    assert_eq!(rooms.len(), 1);
+}
+
+#[actix_web::test]
+async fn post_creates_device() {
+    let app = init_app(GlobalContext::default()).await;
+    let socket = json!({
+        "name": "Lamp",
+        "description": "Desk lamp",
+        "server_addr": "http://127.0.0.1:1",
+    });
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/socket")
+            .set_json(&socket)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(location(&response).ends_with("/home/Flat/Office/Lamp"));
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/socket")
+            .set_json(&socket)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "DeviceAlreadyExists");
+
+    let response = test::call_service(
+        &app,
+        request(Method::GET, "/home/Flat/Office/Lamp").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn conflicts() {
+    let app = init_app(GlobalContext::default()).await;
+
+    for uri in ["/home/Flat", "/home/House", "/home/Flat/Office"] {
+        test::call_service(&app, request(Method::PUT, uri).to_request()).await;
+    }
+
+    let response = test::call_service(
+        &app,
+        request(Method::PATCH, "/home/Flat")
+            .set_json(json!({ "name": "House" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/thermometer")
+            .set_json(json!({
+                "name": "Thermometer",
+                "description": "",
+                "server_addr": "127.0.0.1:0",
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // a thermometer can't be switched
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/Thermometer/on").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "DeviceNotCompatible");
+}
+
+#[actix_web::test]
+async fn validation_errors() {
+    let app = init_app(GlobalContext::default()).await;
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/position")
+            .insert_header(header::ContentType::json())
+            .set_payload("{\"position\":")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "InvalidRequest");
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/position")
+            .set_json(json!({ "position": "first" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "InvalidBody");
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/position")
+            .set_json(json!({ "position": 1 }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/location")
+            .set_json(json!({ "floor": null, "zone": "North" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let invalid_devices = [
+        (
+            "socket",
+            json!({ "name": "Lamp", "server_addr": "not an address" }),
+        ),
+        // the gRPC server is reached only by http or https
+        (
+            "socket",
+            json!({ "name": "Lamp", "server_addr": "127.0.0.1:50051" }),
+        ),
+        (
+            "thermometer",
+            json!({ "name": "Thermometer", "server_addr": "127.0.0.1" }),
+        ),
+        (
+            "thermometer",
+            json!({ "name": "Thermometer", "server_addr": ":4000" }),
+        ),
+        (
+            "thermostat",
+            json!({
+                "name": "Thermostat",
+                "thermometer_addr": "127.0.0.1:port",
+                "heater_addr": "http://127.0.0.1:1",
+            }),
+        ),
+    ];
+    for (kind, mut device) in invalid_devices {
+        device["description"] = json!("");
+        let response = test::call_service(
+            &app,
+            request(Method::POST, &format!("/home/Flat/Office/{}", kind))
+                .set_json(&device)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            device
+        );
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["error"], "InvalidAddress");
+    }
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/thermometer")
+            .set_json(json!({
+                "name": "",
+                "description": "",
+                "server_addr": "127.0.0.1:0",
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "EmptyName");
+
+    let response = test::call_service(
+        &app,
+        request(Method::PATCH, "/home/Flat/Office")
+            .set_json(json!({ "name": "" }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "EmptyName");
+
+    let response = test::call_service(
+        &app,
+        request(Method::GET, "/id/home/not-an-id").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn device_transport_errors() {
+    let app = init_app(GlobalContext::default()).await;
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+    test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/socket")
+            .set_json(json!({
+                "name": "Lamp",
+                "description": "",
+                "server_addr": "http://127.0.0.1:1",
+            }))
+            .to_request(),
+    )
+    .await;
+    test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/thermometer")
+            .set_json(json!({
+                "name": "Thermometer",
+                "description": "",
+                "server_addr": "127.0.0.1:0",
+            }))
+            .to_request(),
+    )
+    .await;
+
+    // nothing listens on the port of the socket
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/Lamp/on").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "DeviceUnreachable");
+
+    // the thermometer has not sent the temperature yet
+    let response = test::call_service(
+        &app,
+        request(
+            Method::GET,
+            "/home/Flat/Office/Thermometer/current_temperature",
+        )
+        .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "MeasurementNotAvailable");
+}
+
+/// Storage which keeps the last saved state
+#[derive(Default)]
+struct MemoryStorage(std::sync::Mutex<Value>);
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> StorageResult<HubSnapshot> {
+        Ok(HubSnapshot::default())
+    }
+
+    fn save(&self, snapshot: &HubSnapshot) -> StorageResult<()> {
+        *self.0.lock().unwrap() = serde_json::to_value(snapshot).unwrap();
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+async fn thermostat_settings_without_heater() {
+    let storage = Arc::new(MemoryStorage::default());
+    let app = init_app(GlobalContext {
+        storage: Some(storage.clone()),
+        ..Default::default()
+    })
+    .await;
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/thermostat")
+            .set_json(json!({
+                "name": "Thermostat",
+                "description": "",
+                "thermometer_addr": "127.0.0.1:0",
+                "heater_addr": "http://127.0.0.1:1",
+            }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // neither a temperature nor the heater is available, the settings are applied anyway
+    let settings = [
+        request(Method::POST, "/home/Flat/Office/Thermostat/mode")
+            .set_json(json!({ "mode": "heat" })),
+        request(
+            Method::POST,
+            "/home/Flat/Office/Thermostat/target_temperature",
+        )
+        .set_json(json!({ "target_temperature": 23.5 })),
+    ];
+    for setting in settings {
+        let response = test::call_service(&app, setting.to_request()).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    let stored = storage.0.lock().unwrap().clone();
+    let thermostat = &stored["homes"][0]["rooms"][0]["devices"][0];
+    assert_eq!(thermostat["mode"], "heat");
+    assert_eq!(thermostat["target_temperature"], 23.5);
+
+    let response = test::call_service(
+        &app,
+        request(
+            Method::POST,
+            "/home/Flat/Office/Thermostat/target_temperature",
+        )
+        .set_json(json!({ "target_temperature": "hot" }))
+        .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_web::test]
+async fn expand_nested_objects() {
+    let app = init_app(GlobalContext::default()).await;
+
+    test::call_service(&app, request(Method::PUT, "/home/Flat").to_request()).await;
+    test::call_service(&app, request(Method::PUT, "/home/Flat/Office").to_request()).await;
+    test::call_service(
+        &app,
+        request(Method::POST, "/home/Flat/Office/socket")
+            .set_json(json!({
+                "name": "Lamp",
+                "description": "",
+                "server_addr": "http://127.0.0.1:1",
+            }))
+            .to_request(),
+    )
+    .await;
+
+    let home: Value =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/Flat").to_request()).await;
+    assert!(home.get("rooms").is_none());
+
+    let home: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/Flat?expand=rooms").to_request(),
+    )
+    .await;
+    assert_eq!(home["rooms"][0]["name"], "Office");
+    assert!(home["rooms"][0].get("devices").is_none());
+
+    let homes: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/?expand=devices").to_request(),
+    )
+    .await;
+    let device = &homes[0]["rooms"][0]["devices"][0];
+    assert_eq!(device["name"], "Lamp");
+    assert_eq!(device["server_addr"], "http://127.0.0.1:1");
+    assert!(device.get("state").is_none());
+
+    // the socket is unreachable, so the state has no readings
+    let device: Value = test::call_and_read_body_json(
+        &app,
+        request(Method::GET, "/home/Flat/Office/Lamp?expand=state").to_request(),
+    )
+    .await;
+    assert_eq!(device["state"], json!({}));
+
+    let response = test::call_service(
+        &app,
+        request(Method::GET, "/home/Flat/?expand=walls").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn import_export_topology() {
+    let app = init_app(GlobalContext::default()).await;
+
+    let topology = r#"
+name: Flat
+rooms:
+  - name: Kitchen
+    floor: "1"
+    devices:
+      - name: Kettle
+        device_type: socket
+        server_addr: http://127.0.0.1:1
+      - name: Sensor
+        device_type: thermometer
+        server_addr: 127.0.0.1:0
+      - name: Climate
+        device_type: multi_sensor
+        server_addr: 127.0.0.1:0
+      - name: Window
+        device_type: binary_sensor
+        server_addr: 127.0.0.1:0
+        kind: window
+groups:
+  - name: Appliances
+    members:
+      - room: Kitchen
+        device: Kettle
+"#;
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/import")
+            .insert_header((header::CONTENT_TYPE, "application/yaml"))
+            .set_payload(topology)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(location(&response).ends_with("/home/Flat"));
+
+    let response = test::call_service(
+        &app,
+        request(Method::GET, "/home/Flat/export?format=yaml").to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/yaml"
+    );
+
+    let mut export: Value =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/Flat/export").to_request())
+            .await;
+    assert_eq!(export["rooms"][0]["floor"], "1");
+    assert_eq!(export["rooms"][0]["devices"].as_array().unwrap().len(), 4);
+    assert_eq!(export["rooms"][0]["devices"][3]["kind"], "window");
+    assert_eq!(export["groups"][0]["members"][0]["device"], "Kettle");
+
+    // the exported ids are kept, so the same topology can't be imported twice
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/import")
+            .set_json(&export)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "HomeAlreadyExists");
+
+    export["name"] = json!("Copy");
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/import")
+            .set_json(&export)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = test::read_body_json(response).await;
+    assert_eq!(error["error"], "IdAlreadyExists");
+
+    // nothing is created from an invalid topology
+    let invalid = [
+        (
+            json!({
+                "name": "Copy",
+                "rooms": [{ "name": "Hall", "devices": [{
+                    "name": "Lamp",
+                    "device_type": "socket",
+                    "server_addr": "not an address",
+                }] }],
+            }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidAddress",
+        ),
+        (
+            json!({
+                "name": "Copy",
+                "rooms": [{ "name": "Hall", "devices": [{
+                    "name": "Sensor",
+                    "device_type": "thermometer",
+                    "server_addr": "127.0.0.1:10105",
+                }] }],
+                "groups": [{ "name": "Lights", "members": [{ "room": "Hall", "device": "Lamp" }] }],
+            }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidBody",
+        ),
+        (
+            json!({
+                "name": "Copy",
+                "rooms": [{ "name": "Hall", "devices": [{
+                    "name": "Door",
+                    "device_type": "binary_sensor",
+                    "server_addr": "127.0.0.1:10105",
+                    "kind": "gate",
+                }] }],
+            }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidBody",
+        ),
+        (
+            json!({ "name": "Copy", "rooms": [{ "name": "Hall" }, { "name": "Hall" }] }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidBody",
+        ),
+        (
+            json!({ "name": "Copy", "rooms": [{ "name": "" }] }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidBody",
+        ),
+        (
+            json!({ "rooms": [] }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "InvalidBody",
+        ),
+    ];
+
+    for (body, status, name) in invalid {
+        let response = test::call_service(
+            &app,
+            request(Method::POST, "/home/import")
+                .set_json(&body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), status, "{}", body);
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["error"], name);
+    }
+
+    let response = test::call_service(
+        &app,
+        request(Method::POST, "/home/import")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload("{")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let homes: Value =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/").to_request()).await;
+    assert_eq!(homes.as_array().unwrap().len(), 1);
+
+    // devices of the rejected homes are not started
+    time::sleep(Duration::from_millis(100)).await;
+    UdpSocket::bind("127.0.0.1:10105").expect("thermometer port is free");
+
+    // a room named `export` is read by id
+    let response =
+        test::call_service(&app, request(Method::PUT, "/home/Flat/export").to_request()).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let room: Value = test::read_body_json(response).await;
+    let room: Value = test::call_and_read_body_json(
+        &app,
+        request(
+            Method::GET,
+            &format!("/id/room/{}", room["id"].as_str().unwrap()),
+        )
+        .to_request(),
+    )
+    .await;
+    assert_eq!(room["room"]["name"], "export");
+}
+
+/// Storage which is never available
+struct BrokenStorage;
+
+impl Storage for BrokenStorage {
+    fn load(&self) -> StorageResult<HubSnapshot> {
+        Ok(HubSnapshot::default())
+    }
+
+    fn save(&self, _snapshot: &HubSnapshot) -> StorageResult<()> {
+        Err(io::Error::other("disk is full").into())
+    }
+}
+
+#[actix_web::test]
+async fn failed_save_keeps_state() {
+    let ctx = GlobalContext {
+        storage: Some(Arc::new(BrokenStorage)),
+        ..Default::default()
+    };
+    ctx.home_list
+        .lock()
+        .await
+        .add_home(Home::new("Flat"))
+        .unwrap();
+    let app = init_app(ctx).await;
+
+    let changes = [
+        request(Method::PUT, "/home/House"),
+        request(Method::PATCH, "/home/Flat").set_json(json!({ "name": "House", "tags": ["new"] })),
+        request(Method::PUT, "/home/Flat/Office"),
+        request(Method::DELETE, "/home/Flat"),
+    ];
+
+    for change in changes {
+        let response = test::call_service(&app, change.to_request()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let error: Value = test::read_body_json(response).await;
+        assert_eq!(error["error"], "StorageError");
+    }
+
+    let homes: Value =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/").to_request()).await;
+    assert_eq!(homes.as_array().unwrap().len(), 1);
+    assert_eq!(homes[0]["name"], "Flat");
+    assert_eq!(homes[0]["tags"], json!([]));
+
+    let rooms: Value =
+        test::call_and_read_body_json(&app, request(Method::GET, "/home/Flat/").to_request()).await;
+    assert_eq!(rooms, json!([]));
+}
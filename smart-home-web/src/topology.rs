@@ -0,0 +1,186 @@
+use actix_web::{get, http::header, post, web, web::Bytes, HttpRequest, HttpResponse};
+use smart_home_lib::{Home, Id, SmartHub};
+
+use crate::{
+    auth::{Admin, Viewer},
+    device_list::{check_listen_addr, check_socket_addr},
+    error::{Error, ErrorResponse, WebResult},
+    home_list::{created, GlobalContext},
+    snapshot::{DeviceKindSnapshot, HomeSnapshot},
+    types::{WebEvent, WebEventKind, WebExportQuery, WebTopologyFormat},
+    WebHome,
+};
+
+const YAML: &str = "application/yaml";
+
+/// Parse the YAML body if the content type says so, the JSON body otherwise
+fn parse(req: &HttpRequest, body: &[u8]) -> WebResult<HomeSnapshot> {
+    let is_yaml = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("yaml"));
+
+    if is_yaml {
+        serde_yaml::from_slice(body).map_err(|e| Error::InvalidBody(e.to_string()))
+    } else {
+        serde_json::from_slice(body).map_err(|e| match e.is_data() {
+            true => Error::InvalidBody(e.to_string()),
+            false => Error::InvalidRequest(e.to_string()),
+        })
+    }
+}
+
+/// Device addresses must be valid before the devices are connected
+fn check_addresses(home: &HomeSnapshot) -> WebResult<()> {
+    for device in home.rooms.iter().flat_map(|room| &room.devices) {
+        match &device.kind {
+            DeviceKindSnapshot::Socket { server_addr } => check_socket_addr(server_addr)?,
+            DeviceKindSnapshot::Thermostat {
+                thermometer_addr,
+                heater_addr,
+                ..
+            } => {
+                check_listen_addr(thermometer_addr)?;
+                check_socket_addr(heater_addr)?;
+            }
+            DeviceKindSnapshot::Thermometer { server_addr }
+            | DeviceKindSnapshot::MultiSensor { server_addr }
+            | DeviceKindSnapshot::BinarySensor { server_addr, .. } => {
+                check_listen_addr(server_addr)?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Name and ids of the imported home must be free in the hub
+fn check_free(hub: &SmartHub, name: &str, ids: impl IntoIterator<Item = Id>) -> WebResult<()> {
+    if hub.get_home(name).is_some() {
+        return Err(Error::HomeAlreadyExists);
+    }
+
+    for id in ids {
+        if hub.home_by_id(id).is_some()
+            || hub.room_by_id(id).is_some()
+            || hub.device_by_id(id).is_some()
+        {
+            return Err(Error::IdAlreadyExists);
+        }
+    }
+
+    Ok(())
+}
+
+/// Ids of the home, its rooms and devices
+fn home_ids(home: &Home) -> Vec<Id> {
+    let rooms = home.room_iter().map(|room| room.id());
+    let devices = home
+        .room_iter()
+        .flat_map(|room| room.device_iter())
+        .map(|device| device.id());
+
+    [home.id()]
+        .into_iter()
+        .chain(rooms)
+        .chain(devices)
+        .collect()
+}
+
+/// Dump the home with its rooms, devices and groups in the format of `POST /home/import`
+///
+/// A room named `export` can't be read by name, `GET /id/room/{id}` reads it.
+#[utoipa::path(
+    tag = "home",
+    params(WebExportQuery),
+    responses(
+        (status = 200, description = "Home topology", content(
+            (HomeSnapshot = "application/json"),
+            (HomeSnapshot = "application/yaml"),
+        )),
+        (status = 404, description = "Home is not found", body = ErrorResponse),
+    )
+)]
+#[get("/{home}/export")]
+async fn export_home(
+    ctx: web::Data<GlobalContext>,
+    _auth: Viewer,
+    path: web::Path<(String,)>,
+    query: web::Query<WebExportQuery>,
+) -> WebResult<HttpResponse> {
+    let home_list = ctx.home_list.lock().await;
+    let (home,) = path.into_inner();
+    let home = home_list.get_home(&home).ok_or(Error::HomeNotFound)?;
+    let snapshot = HomeSnapshot::capture(home).await;
+    drop(home_list);
+
+    match query.format.unwrap_or_default() {
+        WebTopologyFormat::Json => Ok(HttpResponse::Ok().json(snapshot)),
+        WebTopologyFormat::Yaml => {
+            let body = serde_yaml::to_string(&snapshot).map_err(|e| {
+                log::error!("Failed to encode the home: {}", e);
+                Error::Internal
+            })?;
+
+            Ok(HttpResponse::Ok().content_type(YAML).body(body))
+        }
+    }
+}
+
+/// Create the home with its rooms, devices and groups at once
+///
+/// The body is YAML if the content type contains `yaml`, JSON otherwise. Ids are kept, so an
+/// exported home can be restored, missing ids are generated. Nothing is created if any part is
+/// invalid.
+#[utoipa::path(
+    tag = "home",
+    request_body(content(
+        (HomeSnapshot = "application/json"),
+        (HomeSnapshot = "application/yaml"),
+    )),
+    responses(
+        (status = 201, description = "Created home, `Location` is its URL", body = WebHome),
+        (status = 400, description = "Malformed body", body = ErrorResponse),
+        (status = 409, description = "Home or id is already exists", body = ErrorResponse),
+        (status = 422, description = "Invalid topology", body = ErrorResponse),
+    )
+)]
+#[post("/import")]
+async fn import_home(
+    ctx: web::Data<GlobalContext>,
+    Admin(principal): Admin,
+    req: HttpRequest,
+    body: Bytes,
+) -> WebResult<HttpResponse> {
+    let snapshot = parse(&req, &body)?;
+    principal.check_home(&snapshot.name)?;
+    check_addresses(&snapshot)?;
+    snapshot
+        .validate()
+        .map_err(|e| Error::InvalidBody(e.to_string()))?;
+    // devices are not started for a home which can't be added anyway
+    let ids = snapshot.ids().filter_map(Result::ok);
+    check_free(&*ctx.home_list.lock().await, &snapshot.name, ids)?;
+
+    // the home is built before the hub is locked, a failed import only drops it
+    let home = snapshot
+        .restore()
+        .await
+        .map_err(|e| Error::InvalidBody(e.to_string()))?;
+    let name = home.name().to_string();
+    let response = created(&req, "read_home", [&name], WebHome::from(&home))?;
+    let event = WebEvent::home(WebEventKind::HomeCreated, &name);
+
+    let mut home_list = ctx.home_list.lock().await;
+    let previous = home_list.clone();
+
+    // the hub may be changed while the devices are connected
+    check_free(&home_list, &name, home_ids(&home))?;
+    home_list.add_home(home)?;
+
+    ctx.persist(&mut home_list, previous).await?;
+    ctx.publish(event);
+
+    Ok(response)
+}
@@ -1,51 +1,421 @@
+use std::{collections::BTreeMap, time::UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
-use smart_home_lib::{Device, Home, Room, SmartDevice};
+use smart_home_lib::{
+    Device, DeviceGroup, Health, Home, Location, Metadata, Room, SmartSocket, SmartThermometer,
+    SmartThermostat,
+};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebMetadata {
+    pub tags: Vec<String>,
+    pub properties: BTreeMap<String, String>,
+}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebHome {
+    pub id: String,
     pub name: String,
+    #[serde(flatten)]
+    pub metadata: WebMetadata,
+    /// Rooms of the home, only with `expand`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rooms: Option<Vec<WebRoom>>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebRoom {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub location: WebLocation,
+    #[serde(flatten)]
+    pub metadata: WebMetadata,
+    /// Devices of the room, only with `expand=devices` or `expand=state`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devices: Option<Vec<WebDevice>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebLocation {
+    /// Floor of the room, the room is not placed if missing
+    pub floor: Option<String>,
+    /// Zone on the floor
+    pub zone: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebFloor {
     pub name: String,
+    pub zones: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebGroupMember {
+    pub room: String,
+    pub device: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebGroup {
+    pub name: String,
+    pub members: Vec<WebGroupMember>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebSummary {
+    pub average_temperature: Option<f64>,
+    pub total_power: f64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebRoomPath {
+    pub home: String,
+    pub room: WebRoom,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebDevice {
+    pub id: String,
     pub name: String,
     pub description: String,
     pub device_type: String,
+    pub capabilities: Vec<String>,
+    pub health: WebHealth,
+    #[serde(flatten)]
+    pub address: WebDeviceAddress,
+    #[serde(flatten)]
+    pub metadata: WebMetadata,
+    /// Live readings, only with `expand=state`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<WebDeviceState>,
+}
+
+/// Addresses of the device servers
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+pub struct WebDeviceAddress {
+    /// Server of the thermometer or the socket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_addr: Option<String>,
+    /// Thermometer server of the thermostat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thermometer_addr: Option<String>,
+    /// Heater socket server of the thermostat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heater_addr: Option<String>,
+}
+
+/// Readings of the device capabilities, missing if the capability is not supported or the device
+/// is not available
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+pub struct WebDeviceState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_power: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_humidity: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_pressure: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_temperature: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebHealth {
+    pub status: String,
+    /// Unix time of the last answer or data from the device
+    pub last_seen: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebUpdate {
+    /// New name, unchanged if missing
+    pub name: Option<String>,
+    /// New tags, unchanged if missing
+    pub tags: Option<Vec<String>>,
+    /// New properties, unchanged if missing
+    pub properties: Option<BTreeMap<String, String>>,
+}
+
+impl WebUpdate {
+    /// Apply new tags and properties, returns `false` if neither is given
+    pub fn apply(self, metadata: &mut Metadata) -> bool {
+        let changed = self.tags.is_some() || self.properties.is_some();
+
+        if let Some(tags) = self.tags {
+            metadata.set_tags(tags);
+        }
+
+        if let Some(properties) = self.properties {
+            metadata.set_properties(properties);
+        }
+
+        changed
+    }
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebTagFilter {
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebExpandQuery {
+    /// Nested objects to include, comma-separated `rooms`, `devices` and `state`, every one
+    /// includes the previous ones
+    pub expand: Option<String>,
+}
+
+/// Format of the exported home
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebTopologyFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebExportQuery {
+    /// `json` if missing
+    pub format: Option<WebTopologyFormat>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebPosition {
+    /// New zero-based position in the list
+    pub position: usize,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebMoveDevice {
+    /// Target home, the current home if missing
+    pub home: Option<String>,
+    /// Target room
+    pub room: String,
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebDeviceSearch {
+    pub capability: Option<String>,
+    pub device_type: Option<String>,
+    pub name: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebDevicePath {
+    pub home: String,
+    pub room: String,
+    pub device: WebDevice,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebThermometer {
     pub name: String,
     pub description: String,
     pub server_addr: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebThermometerResult {
     pub current_temperature: f64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebSocket {
     pub name: String,
     pub description: String,
     pub server_addr: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WebSocketResult {
     pub current_power: f64,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebThermostat {
+    pub name: String,
+    pub description: String,
+    pub thermometer_addr: String,
+    pub heater_addr: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebThermostatState {
+    pub current_temperature: f64,
+    pub target_temperature: f64,
+    pub mode: String,
+    pub heating: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebThermostatTarget {
+    pub target_temperature: f64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebThermostatMode {
+    pub mode: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebEventKind {
+    HomeCreated,
+    HomeRenamed,
+    HomeUpdated,
+    HomeDeleted,
+    RoomCreated,
+    RoomRenamed,
+    RoomUpdated,
+    RoomDeleted,
+    DeviceCreated,
+    DeviceRenamed,
+    DeviceUpdated,
+    DeviceDeleted,
+    GroupCreated,
+    GroupUpdated,
+    GroupDeleted,
+    SocketOn,
+    SocketOff,
+    Reading,
+}
+
+impl WebEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebEventKind::HomeCreated => "home_created",
+            WebEventKind::HomeRenamed => "home_renamed",
+            WebEventKind::HomeUpdated => "home_updated",
+            WebEventKind::HomeDeleted => "home_deleted",
+            WebEventKind::RoomCreated => "room_created",
+            WebEventKind::RoomRenamed => "room_renamed",
+            WebEventKind::RoomUpdated => "room_updated",
+            WebEventKind::RoomDeleted => "room_deleted",
+            WebEventKind::DeviceCreated => "device_created",
+            WebEventKind::DeviceRenamed => "device_renamed",
+            WebEventKind::DeviceUpdated => "device_updated",
+            WebEventKind::DeviceDeleted => "device_deleted",
+            WebEventKind::GroupCreated => "group_created",
+            WebEventKind::GroupUpdated => "group_updated",
+            WebEventKind::GroupDeleted => "group_deleted",
+            WebEventKind::SocketOn => "socket_on",
+            WebEventKind::SocketOff => "socket_off",
+            WebEventKind::Reading => "reading",
+        }
+    }
+}
+
+/// Topology change or device reading sent to the event subscribers
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct WebEvent {
+    pub event: WebEventKind,
+    pub home: String,
+    pub room: Option<String>,
+    pub device: Option<String>,
+    /// Device group of the `group_*` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// New name of the `*_renamed` events, the event has the old one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_name: Option<String>,
+    /// Current temperature of the `reading` event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_temperature: Option<f64>,
+    /// Current power of the `reading` event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_power: Option<f64>,
+}
+
+impl WebEvent {
+    pub fn home(event: WebEventKind, home: &str) -> Self {
+        Self {
+            event,
+            home: home.to_string(),
+            room: None,
+            device: None,
+            group: None,
+            new_name: None,
+            current_temperature: None,
+            current_power: None,
+        }
+    }
+
+    pub fn room(event: WebEventKind, home: &str, room: &str) -> Self {
+        Self {
+            room: Some(room.to_string()),
+            ..Self::home(event, home)
+        }
+    }
+
+    pub fn device(event: WebEventKind, home: &str, room: &str, device: &str) -> Self {
+        Self {
+            device: Some(device.to_string()),
+            ..Self::room(event, home, room)
+        }
+    }
+
+    pub fn group(event: WebEventKind, home: &str, group: &str) -> Self {
+        Self {
+            group: Some(group.to_string()),
+            ..Self::home(event, home)
+        }
+    }
+
+    /// The `*_renamed` event of the home, room or device named in the event
+    pub fn renamed(self, new_name: &str) -> Self {
+        Self {
+            new_name: Some(new_name.to_string()),
+            ..self
+        }
+    }
+}
+
+/// Subscription filter, events of the whole hub are sent if empty
+///
+/// Events of the home or the room are sent to the filters inside them too, e.g. `home_deleted` is
+/// sent to the filter of a room in that home.
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebEventFilter {
+    pub home: Option<String>,
+    pub room: Option<String>,
+    pub device: Option<String>,
+}
+
+impl WebEventFilter {
+    pub fn matches(&self, event: &WebEvent) -> bool {
+        fn scope_matches(filter: &Option<String>, event: &Option<String>) -> bool {
+            match (filter, event) {
+                (Some(filter), Some(event)) => filter == event,
+                _ => true,
+            }
+        }
+
+        self.home.as_ref().is_none_or(|home| *home == event.home)
+            && scope_matches(&self.room, &event.room)
+            && scope_matches(&self.device, &event.device)
+    }
+}
+
 impl From<Home> for WebHome {
     fn from(h: Home) -> Self {
         WebHome {
+            id: h.id().to_string(),
             name: h.name().to_string(),
+            metadata: h.metadata().into(),
+            rooms: None,
         }
     }
 }
@@ -53,7 +423,10 @@ impl From<Home> for WebHome {
 impl From<&Home> for WebHome {
     fn from(h: &Home) -> Self {
         WebHome {
+            id: h.id().to_string(),
             name: h.name().to_string(),
+            metadata: h.metadata().into(),
+            rooms: None,
         }
     }
 }
@@ -61,7 +434,10 @@ impl From<&Home> for WebHome {
 impl From<&mut Home> for WebHome {
     fn from(h: &mut Home) -> Self {
         WebHome {
+            id: h.id().to_string(),
             name: h.name().to_string(),
+            metadata: h.metadata().into(),
+            rooms: None,
         }
     }
 }
@@ -69,7 +445,11 @@ impl From<&mut Home> for WebHome {
 impl From<Room> for WebRoom {
     fn from(r: Room) -> Self {
         WebRoom {
+            id: r.id().to_string(),
             name: r.name().to_string(),
+            location: r.location().into(),
+            metadata: r.metadata().into(),
+            devices: None,
         }
     }
 }
@@ -77,7 +457,11 @@ impl From<Room> for WebRoom {
 impl From<&Room> for WebRoom {
     fn from(r: &Room) -> Self {
         WebRoom {
+            id: r.id().to_string(),
             name: r.name().to_string(),
+            location: r.location().into(),
+            metadata: r.metadata().into(),
+            devices: None,
         }
     }
 }
@@ -85,7 +469,11 @@ impl From<&Room> for WebRoom {
 impl From<&mut Room> for WebRoom {
     fn from(r: &mut Room) -> Self {
         WebRoom {
+            id: r.id().to_string(),
             name: r.name().to_string(),
+            location: r.location().into(),
+            metadata: r.metadata().into(),
+            devices: None,
         }
     }
 }
@@ -93,9 +481,15 @@ impl From<&mut Room> for WebRoom {
 impl From<Device> for WebDevice {
     fn from(d: Device) -> Self {
         WebDevice {
+            id: d.id().to_string(),
             name: d.name().to_string(),
             description: d.description().to_string(),
             device_type: d.device_type().to_string(),
+            capabilities: d.capabilities().iter().map(|c| c.to_string()).collect(),
+            health: d.health().into(),
+            address: (&d).into(),
+            metadata: d.metadata().into(),
+            state: None,
         }
     }
 }
@@ -103,9 +497,15 @@ impl From<Device> for WebDevice {
 impl From<&Device> for WebDevice {
     fn from(d: &Device) -> Self {
         WebDevice {
+            id: d.id().to_string(),
             name: d.name().to_string(),
             description: d.description().to_string(),
             device_type: d.device_type().to_string(),
+            capabilities: d.capabilities().iter().map(|c| c.to_string()).collect(),
+            health: d.health().into(),
+            address: d.into(),
+            metadata: d.metadata().into(),
+            state: None,
         }
     }
 }
@@ -113,9 +513,88 @@ impl From<&Device> for WebDevice {
 impl From<&mut Device> for WebDevice {
     fn from(d: &mut Device) -> Self {
         WebDevice {
+            id: d.id().to_string(),
             name: d.name().to_string(),
             description: d.description().to_string(),
             device_type: d.device_type().to_string(),
+            capabilities: d.capabilities().iter().map(|c| c.to_string()).collect(),
+            health: d.health().into(),
+            address: (&*d).into(),
+            metadata: d.metadata().into(),
+            state: None,
+        }
+    }
+}
+
+impl From<&Device> for WebDeviceAddress {
+    fn from(d: &Device) -> Self {
+        if let Some(thermostat) = d.downcast_ref::<SmartThermostat>() {
+            WebDeviceAddress {
+                thermometer_addr: Some(thermostat.thermometer().server_addr().to_string()),
+                heater_addr: Some(thermostat.heater().server_addr().to_string()),
+                ..Default::default()
+            }
+        } else if let Some(socket) = d.downcast_ref::<SmartSocket>() {
+            WebDeviceAddress {
+                server_addr: Some(socket.server_addr().to_string()),
+                ..Default::default()
+            }
+        } else if let Some(thermometer) = d.downcast_ref::<SmartThermometer>() {
+            WebDeviceAddress {
+                server_addr: Some(thermometer.server_addr().to_string()),
+                ..Default::default()
+            }
+        } else {
+            WebDeviceAddress::default()
+        }
+    }
+}
+
+impl From<&Metadata> for WebMetadata {
+    fn from(m: &Metadata) -> Self {
+        WebMetadata {
+            tags: m.tags().map(|t| t.to_string()).collect(),
+            properties: m
+                .properties()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl From<Option<&Location>> for WebLocation {
+    fn from(l: Option<&Location>) -> Self {
+        WebLocation {
+            floor: l.map(|l| l.floor.clone()),
+            zone: l.and_then(|l| l.zone.clone()),
+        }
+    }
+}
+
+impl From<&DeviceGroup> for WebGroup {
+    fn from(g: &DeviceGroup) -> Self {
+        WebGroup {
+            name: g.name().to_string(),
+            members: g
+                .members()
+                .map(|m| WebGroupMember {
+                    room: m.room.clone(),
+                    device: m.device.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Health> for WebHealth {
+    fn from(h: Health) -> Self {
+        WebHealth {
+            status: h.status.to_string(),
+            last_seen: h
+                .last_seen
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            last_error: h.last_error,
         }
     }
 }
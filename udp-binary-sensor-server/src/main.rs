@@ -0,0 +1,29 @@
+use std::{net::UdpSocket, thread, time::Duration};
+
+use rand::Rng;
+
+const ANY: &str = "0.0.0.0:0";
+const BROADCAST_ADDR: &str = "255.255.255.255:10002";
+/// Probability of the state change per tick
+const CHANGE_PROBABILITY: f64 = 0.2;
+
+fn main() {
+    let socket = UdpSocket::bind(ANY).expect("bind to any");
+    let mut rng = rand::thread_rng();
+    let mut active = false;
+
+    socket.set_broadcast(true).expect("set SO_BROADCAST");
+
+    loop {
+        if rng.gen_bool(CHANGE_PROBABILITY) {
+            active = !active;
+        }
+
+        // state is repeated on every tick, so the lost packets are recovered
+        socket
+            .send_to(&[active as u8], BROADCAST_ADDR)
+            .expect("successful send");
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
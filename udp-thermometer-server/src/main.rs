@@ -1,14 +1,73 @@
-use std::{net::UdpSocket, thread, time::Duration};
+use std::{
+    env,
+    net::UdpSocket,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use hmac::{Hmac, Mac};
 use rand::Rng;
+use sha2::Sha256;
 
 const ANY: &str = "0.0.0.0:0";
 const BROADCAST_ADDR: &str = "255.255.255.255:10000";
+const MULTI_SENSOR_BROADCAST_ADDR: &str = "255.255.255.255:10001";
+
+const KIND_TEMPERATURE: u8 = 1;
+const KIND_HUMIDITY: u8 = 2;
+const KIND_PRESSURE: u8 = 3;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Thermometer packet signing with the pre-shared key
+struct Signer {
+    mac: HmacSha256,
+    sequence: u64,
+}
+
+impl Signer {
+    /// Take the key from `THERMOMETER_KEY`, packets are sent unsigned if it is not set
+    fn from_env() -> Option<Self> {
+        let key = env::var_os("THERMOMETER_KEY")?;
+        let mac = HmacSha256::new_from_slice(key.to_string_lossy().as_bytes())
+            .expect("HMAC accepts any key length");
+        // start from the current time, so receivers accept packets after the restart
+        let sequence = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time after the epoch")
+            .as_millis() as u64;
+
+        Some(Self { mac, sequence })
+    }
+
+    /// Encode signed thermometer packet: sequence number (u64 big endian), temperature
+    /// (f64 big endian) and HMAC-SHA256 of them
+    fn packet(&mut self, temperature: f64) -> Vec<u8> {
+        self.sequence += 1;
+
+        let mut packet = [self.sequence.to_be_bytes(), temperature.to_be_bytes()].concat();
+        let mut mac = self.mac.clone();
+        mac.update(&packet);
+        packet.extend(mac.finalize().into_bytes());
+        packet
+    }
+}
+
+/// Encode multi-sensor packet: sequence of (kind: u8, value: f64 big endian) records
+fn multi_sensor_packet(measurements: &[(u8, f64)]) -> Vec<u8> {
+    measurements
+        .iter()
+        .flat_map(|(kind, value)| std::iter::once(*kind).chain(value.to_be_bytes()))
+        .collect()
+}
 
 fn main() {
     let socket = UdpSocket::bind(ANY).expect("bind to any");
     let mut rng = rand::thread_rng();
     let mut temperature = 0.0;
+    let mut humidity: f64 = rng.gen_range(30.0..70.0);
+    let mut pressure: f64 = rng.gen_range(990.0..1030.0);
+    let mut signer = Signer::from_env();
 
     socket.set_broadcast(true).expect("set SO_BROADCAST");
 
@@ -21,8 +80,26 @@ fn main() {
             temperature
         };
 
+        let packet = match &mut signer {
+            Some(signer) => signer.packet(temperature),
+            None => temperature.to_be_bytes().to_vec(),
+        };
+
+        socket
+            .send_to(&packet, BROADCAST_ADDR)
+            .expect("successful send");
+
+        humidity = (humidity + rng.gen_range(-0.1..0.1)).clamp(0.0, 100.0);
+        pressure += rng.gen_range(-0.05..0.05);
+
+        let packet = multi_sensor_packet(&[
+            (KIND_TEMPERATURE, temperature),
+            (KIND_HUMIDITY, humidity),
+            (KIND_PRESSURE, pressure),
+        ]);
+
         socket
-            .send_to(&temperature.to_be_bytes(), BROADCAST_ADDR)
+            .send_to(&packet, MULTI_SENSOR_BROADCAST_ADDR)
             .expect("successful send");
 
         thread::sleep(Duration::from_secs(3));